@@ -1019,6 +1019,12 @@ impl Step for Compiletest {
             cmd.arg("--rustdoc-path").arg(builder.rustdoc(compiler));
         }
 
+        // The fuzz target fixtures in run-make-fulldeps run the generator, not rustdoc.
+        if mode == "run-make" && suite.ends_with("fulldeps") {
+            cmd.arg("--fuzz-target-generator-path")
+                .arg(builder.ensure(tool::FuzzTargetGenerator { compiler }));
+        }
+
         cmd.arg("--src-base").arg(builder.src.join("src/test").join(suite));
         cmd.arg("--build-base").arg(testdir(builder, compiler.host).join(suite));
         cmd.arg("--stage-id").arg(format!("stage{}-{}", compiler.stage, target));
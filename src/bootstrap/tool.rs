@@ -542,7 +542,7 @@ impl Step for FuzzTargetGenerator {
     const ONLY_HOSTS: bool = true;
 
     fn should_run(run: ShouldRun<'_>) -> ShouldRun<'_> {
-        run.path("src/tools/fuzz-target-generator").path("src/librustdoc")
+        run.path("src/tools/fuzz-target-generator").path("src/tools/rulf-core").path("src/librustdoc")
     }
 
     fn make_run(run: RunConfig<'_>) {
@@ -0,0 +1,128 @@
+//! Lints for public APIs that the fuzz target generator in rustdoc can not call.
+//!
+//! The generator builds every parameter either directly from fuzz input or from the return
+//! value of another public function, so the patterns below leave an API without targets.
+
+use crate::{LateContext, LateLintPass, LintContext};
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{self, Ty};
+use rustc_span::Span;
+use rustc_span::symbol::sym;
+
+declare_lint! {
+    pub FUZZ_BLOCKING_API,
+    Allow,
+    "detects public APIs whose parameters can not be constructed by a fuzz target"
+}
+
+#[derive(Default)]
+pub struct FuzzBlockingApis {
+    /// Local types returned by reachable functions, with references, `Box`, `Option` and
+    /// `Result` peeled off.
+    produced_types: FxHashSet<DefId>,
+    /// Parameters of reachable functions whose type is a local struct with private fields.
+    /// The `HirId` of the parameter type selects the lint level, since the lint is only
+    /// emitted after the whole crate has been visited.
+    opaque_params: Vec<(hir::HirId, Span, DefId)>,
+}
+
+impl_lint_pass!(FuzzBlockingApis => [FUZZ_BLOCKING_API]);
+
+impl FuzzBlockingApis {
+    fn check_fn(&mut self, cx: &LateContext<'_>, hir_id: hir::HirId, decl: &hir::FnDecl<'_>) {
+        if !cx.access_levels.is_reachable(hir_id) {
+            return;
+        }
+        let def_id = cx.tcx.hir().local_def_id(hir_id);
+        let sig = cx.tcx.fn_sig(def_id).skip_binder();
+
+        if let Some(did) = peel_type(cx, sig.output()).ty_adt_def().map(|def| def.did) {
+            self.produced_types.insert(did);
+        }
+
+        for (input_ty, input) in sig.inputs().iter().zip(decl.inputs.iter()) {
+            let ty = peel_type(cx, *input_ty);
+            let reason = match ty.kind {
+                // The generator passes local implementors of the trait to trait objects.
+                ty::Dynamic(predicates, _) => match predicates.principal_def_id() {
+                    Some(trait_did) if cx.tcx.all_impls(trait_did).any(|did| did.is_local()) => {
+                        continue;
+                    }
+                    _ => "no type in this crate implements the trait of this trait object",
+                },
+                ty::FnPtr(..) => "function pointers can not be built from fuzz input",
+                ty::Adt(def, _) if def.is_variant_list_non_exhaustive() && !def.did.is_local() => {
+                    "this enum is `#[non_exhaustive]` and defined in another crate"
+                }
+                ty::Adt(def, _) if def.is_struct() && def.did.is_local() => {
+                    if def.non_enum_variant().fields.iter().any(|f| f.vis != ty::Visibility::Public)
+                    {
+                        self.opaque_params.push((input.hir_id, input.span, def.did));
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            emit_lint(
+                cx,
+                input.span,
+                &format!("parameter of type `{}` blocks fuzzing", ty),
+                reason,
+            );
+        }
+    }
+}
+
+impl<'tcx> LateLintPass<'tcx> for FuzzBlockingApis {
+    fn check_item(&mut self, cx: &LateContext<'tcx>, item: &'tcx hir::Item<'tcx>) {
+        if let hir::ItemKind::Fn(ref sig, ..) = item.kind {
+            self.check_fn(cx, item.hir_id, sig.decl);
+        }
+    }
+
+    fn check_impl_item(&mut self, cx: &LateContext<'tcx>, impl_item: &'tcx hir::ImplItem<'tcx>) {
+        if let hir::ImplItemKind::Fn(ref sig, ..) = impl_item.kind {
+            self.check_fn(cx, impl_item.hir_id, sig.decl);
+        }
+    }
+
+    fn check_crate_post(&mut self, cx: &LateContext<'tcx>, _: &'tcx hir::Crate<'tcx>) {
+        for &(hir_id, span, did) in &self.opaque_params {
+            if self.produced_types.contains(&did) {
+                continue;
+            }
+            let msg = format!("no public function returns `{}`", cx.tcx.def_path_str(did));
+            cx.tcx.struct_span_lint_hir(FUZZ_BLOCKING_API, hir_id, span, |lint| {
+                lint.build(&msg)
+                    .note(
+                        "the type has private fields, so a fuzz target has no way to construct \
+                         it; consider adding a public constructor",
+                    )
+                    .emit()
+            });
+        }
+    }
+}
+
+fn emit_lint(cx: &LateContext<'_>, span: Span, msg: &str, note: &str) {
+    cx.struct_span_lint(FUZZ_BLOCKING_API, span, |lint| lint.build(msg).note(note).emit());
+}
+
+/// Strips the wrappers the generator can see through when matching producers and consumers.
+fn peel_type<'tcx>(cx: &LateContext<'tcx>, mut ty: Ty<'tcx>) -> Ty<'tcx> {
+    loop {
+        ty = match ty.kind {
+            ty::Ref(_, inner, _) => inner,
+            ty::Adt(def, substs) if def.is_box() => substs.type_at(0),
+            ty::Adt(def, substs)
+                if cx.match_def_path(def.did, &[sym::core, sym::option, sym::Option])
+                    || cx.match_def_path(def.did, &[sym::core, sym::result, sym::Result]) =>
+            {
+                substs.type_at(0)
+            }
+            _ => return ty,
+        };
+    }
+}
@@ -46,6 +46,7 @@ mod array_into_iter;
 pub mod builtin;
 mod context;
 mod early;
+mod fuzz_blocking;
 mod internal;
 mod late;
 mod levels;
@@ -71,6 +72,7 @@ use rustc_span::Span;
 
 use array_into_iter::ArrayIntoIter;
 use builtin::*;
+use fuzz_blocking::*;
 use internal::*;
 use non_ascii_idents::*;
 use nonstandard_style::*;
@@ -87,6 +89,7 @@ pub use passes::{EarlyLintPass, LateLintPass};
 pub use rustc_session::lint::Level::{self, *};
 pub use rustc_session::lint::{BufferedEarlyLint, FutureIncompatibleInfo, Lint, LintId};
 pub use rustc_session::lint::{LintArray, LintPass};
+pub use types::{is_ffi_function, is_internal_abi};
 
 pub fn provide(providers: &mut Providers) {
     levels::provide(providers);
@@ -157,6 +160,8 @@ macro_rules! late_lint_passes {
                 MissingDebugImplementations: MissingDebugImplementations::default(),
                 ArrayIntoIter: ArrayIntoIter,
                 ClashingExternDeclarations: ClashingExternDeclarations::new(),
+                // Tracks which types are returned by public functions across modules
+                FuzzBlockingApis: FuzzBlockingApis::default(),
             ]
         );
     };
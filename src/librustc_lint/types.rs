@@ -6,12 +6,13 @@ use rustc_attr as attr;
 use rustc_data_structures::fx::FxHashSet;
 use rustc_errors::Applicability;
 use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
 use rustc_hir::{is_range_literal, ExprKind, Node};
 use rustc_index::vec::Idx;
 use rustc_middle::mir::interpret::{sign_extend, truncate};
 use rustc_middle::ty::layout::{IntegerExt, SizeSkeleton};
 use rustc_middle::ty::subst::SubstsRef;
-use rustc_middle::ty::{self, AdtKind, Ty, TypeFoldable};
+use rustc_middle::ty::{self, AdtKind, Ty, TyCtxt, TypeFoldable};
 use rustc_span::source_map;
 use rustc_span::symbol::sym;
 use rustc_span::{Span, DUMMY_SP};
@@ -850,7 +851,7 @@ impl<'a, 'tcx> ImproperCTypesVisitor<'a, 'tcx> {
             ty::Array(inner_ty, _) => self.check_type_for_ffi(cache, inner_ty),
 
             ty::FnPtr(sig) => {
-                if self.is_internal_abi(sig.abi()) {
+                if is_internal_abi(sig.abi()) {
                     return FfiUnsafe {
                         ty,
                         reason: "this function pointer has Rust-specific calling convention".into(),
@@ -1051,22 +1052,31 @@ impl<'a, 'tcx> ImproperCTypesVisitor<'a, 'tcx> {
         let ty = self.cx.tcx.type_of(def_id);
         self.check_type_for_ffi_and_report_errors(span, ty, true, false);
     }
+}
 
-    fn is_internal_abi(&self, abi: Abi) -> bool {
-        if let Abi::Rust | Abi::RustCall | Abi::RustIntrinsic | Abi::PlatformIntrinsic = abi {
-            true
-        } else {
-            false
-        }
+/// Whether `abi` is one of the Rust ABIs, whose functions are not checked by `improper_ctypes`
+/// and `improper_ctypes_definitions`.
+pub fn is_internal_abi(abi: Abi) -> bool {
+    if let Abi::Rust | Abi::RustCall | Abi::RustIntrinsic | Abi::PlatformIntrinsic = abi {
+        true
+    } else {
+        false
     }
 }
 
+/// Whether calling the function `def_id` crosses an FFI boundary, i.e. whether it is checked
+/// by `improper_ctypes` (declared in a foreign block) or by `improper_ctypes_definitions`
+/// (defined with a foreign ABI). `def_id` must not be a closure.
+pub fn is_ffi_function(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    !is_internal_abi(tcx.fn_sig(def_id).abi())
+}
+
 impl<'tcx> LateLintPass<'tcx> for ImproperCTypesDeclarations {
     fn check_foreign_item(&mut self, cx: &LateContext<'_>, it: &hir::ForeignItem<'_>) {
         let mut vis = ImproperCTypesVisitor { cx, mode: ImproperCTypesMode::Declarations };
         let abi = cx.tcx.hir().get_foreign_abi(it.hir_id);
 
-        if !vis.is_internal_abi(abi) {
+        if !is_internal_abi(abi) {
             match it.kind {
                 hir::ForeignItemKind::Fn(ref decl, _, _) => {
                     vis.check_foreign_fn(it.hir_id, decl);
@@ -1099,7 +1109,7 @@ impl<'tcx> LateLintPass<'tcx> for ImproperCTypesDefinitions {
         };
 
         let mut vis = ImproperCTypesVisitor { cx, mode: ImproperCTypesMode::Definitions };
-        if !vis.is_internal_abi(abi) {
+        if !is_internal_abi(abi) {
             vis.check_foreign_fn(hir_id, decl);
         }
     }
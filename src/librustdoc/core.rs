@@ -7,6 +7,7 @@ use rustc_errors::json::JsonEmitter;
 use rustc_feature::UnstableFeatures;
 use rustc_hir::def::Namespace::TypeNS;
 use rustc_hir::def_id::{CrateNum, DefId, DefIndex, LocalDefId, CRATE_DEF_INDEX, LOCAL_CRATE};
+use rustc_hir::definitions::DefPathHash;
 use rustc_hir::itemlikevisit::ItemLikeVisitor;
 use rustc_hir::HirId;
 use rustc_interface::interface;
@@ -23,13 +24,18 @@ use rustc_span::symbol::sym;
 use rustc_span::DUMMY_SP;
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use crate::clean;
 use crate::clean::{AttributesExt, MAX_DEF_ID};
 use crate::config::{Options as RustdocOptions, RenderOptions};
+use crate::fuzz_target::api_function::ParamRelation;
+use crate::fuzz_target::{
+    ffi_util, instance_util, producer_util, projection_util, relation_util, unsafe_util,
+};
 use crate::html::render::RenderInfo;
 use crate::passes::{self, Condition::*, ConditionalPass};
 
@@ -66,6 +72,9 @@ pub struct DocContext<'tcx> {
     pub auto_traits: Vec<DefId>,
     /// The options given to rustdoc that could be relevant to a pass.
     pub render_options: RenderOptions,
+    /// Whether this context was created by `fuzz_target_generator_run_core`. Passes use it
+    /// to collect items that only the fuzz target generator needs.
+    pub generating_fuzz_targets: bool,
 }
 
 impl<'tcx> DocContext<'tcx> {
@@ -459,6 +468,7 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
                         .filter(|trait_def_id| tcx.trait_is_auto(*trait_def_id))
                         .collect(),
                     render_options,
+                    generating_fuzz_targets: false,
                 };
                 debug!("crate: {:?}", tcx.hir().krate());
 
@@ -632,6 +642,17 @@ pub fn fuzz_target_generator_run_core(
 
     // Add the doc cfg into the doc build.
     cfgs.push("doc".to_string());
+    // Compile the helpers which are only available under the helper cfg, e.g. `cfg(test)`.
+    if let Some(helper_cfg) = crate::fuzz_target::api_util::helper_cfg() {
+        cfgs.push(helper_cfg);
+    }
+    // Enable the cargo features selected for fuzzing, e.g. `FUZZ_TARGET_FEATURES=std,serde`.
+    for feature in crate::fuzz_target::api_util::resolve_features(&input, &cfgs) {
+        let feature_cfg = format!("feature=\"{}\"", feature);
+        if !cfgs.contains(&feature_cfg) {
+            cfgs.push(feature_cfg);
+        }
+    }
 
     let cpath = Some(input.clone());
     let input = Input::File(input);
@@ -685,6 +706,20 @@ pub fn fuzz_target_generator_run_core(
         ..Options::default()
     };
 
+    let body_analyses = if unsafe_util::unsafe_report_requested()
+        || ffi_util::ffi_classification_requested()
+        || relation_util::mir_relations_requested()
+    {
+        fuzz_target_body_analyses(
+            sessopts.clone(),
+            cfgs.clone(),
+            cpath.clone().unwrap(),
+            crate_name.clone(),
+        )
+    } else {
+        BodyAnalyses::default()
+    };
+
     let config = interface::Config {
         opts: sessopts,
         crate_cfg: interface::parse_cfgspecs(cfgs),
@@ -777,8 +812,25 @@ pub fn fuzz_target_generator_run_core(
                 };
 
                 let mut renderinfo = RenderInfo::default();
+                if instance_util::generic_instances_requested() {
+                    let instances_start = std::time::Instant::now();
+                    renderinfo.generic_instances =
+                        instance_util::generic_instances(tcx, &access_levels);
+                    println!(
+                        "found instances of {} generic functions in {:?}",
+                        renderinfo.generic_instances.instances.len(),
+                        instances_start.elapsed()
+                    );
+                }
                 renderinfo.access_levels = access_levels;
                 renderinfo.output_format = output_format;
+                renderinfo.unsafe_reachability.reachable_sites =
+                    by_local_def_id(tcx, body_analyses.unsafe_sites);
+                renderinfo.unsafe_reachability.all_sites = body_analyses.all_unsafe_sites;
+                renderinfo.ffi_classification.foreign_callees =
+                    by_local_def_id(tcx, body_analyses.foreign_callees);
+                renderinfo.mir_param_relations.relations =
+                    by_local_def_id(tcx, body_analyses.param_relations);
 
                 let mut ctxt = DocContext {
                     tcx,
@@ -800,6 +852,7 @@ pub fn fuzz_target_generator_run_core(
                         .filter(|trait_def_id| tcx.trait_is_auto(*trait_def_id))
                         .collect(),
                     render_options,
+                    generating_fuzz_targets: true,
                 };
                 debug!("crate: {:?}", tcx.hir().krate());
 
@@ -908,12 +961,125 @@ pub fn fuzz_target_generator_run_core(
 
                 ctxt.sess().abort_if_errors();
 
+                let normalized_signatures = projection_util::normalized_signatures(&ctxt);
+                ctxt.renderinfo.borrow_mut().normalized_signatures = normalized_signatures;
+                if !producer_util::producer_crates().is_empty() {
+                    let producer_functions = producer_util::producer_functions(&ctxt);
+                    ctxt.renderinfo.borrow_mut().producer_functions = producer_functions;
+                }
+
                 (krate, ctxt.renderinfo.into_inner(), ctxt.render_options)
             })
         })
     })
 }
 
+/// Results of `fuzz_target_body_analyses`. They are keyed by `DefPathHash`, because the
+/// `DefId`s of that session don't match the ones of the rustdoc session.
+#[derive(Default)]
+struct BodyAnalyses {
+    unsafe_sites: FxHashMap<DefPathHash, BTreeSet<String>>,
+    all_unsafe_sites: BTreeSet<String>,
+    foreign_callees: FxHashMap<DefPathHash, BTreeSet<String>>,
+    param_relations: FxHashMap<DefPathHash, Vec<ParamRelation>>,
+}
+
+/// Compiles the crate a second time in a normal rustc session, for the fuzz target analyses
+/// that need function bodies. Rustdoc replaces every body with `loop {}` (see
+/// `ReplaceBodyWithLoop`), so the MIR of the rustdoc session has no calls and no unsafe blocks.
+fn fuzz_target_body_analyses(
+    mut sessopts: Options,
+    cfgs: Vec<String>,
+    input: PathBuf,
+    crate_name: Option<String>,
+) -> BodyAnalyses {
+    sessopts.actually_rustdoc = false;
+    sessopts.lint_cap = Some(lint::Allow);
+    let config = interface::Config {
+        opts: sessopts,
+        crate_cfg: interface::parse_cfgspecs(cfgs),
+        input: Input::File(input.clone()),
+        input_path: Some(input),
+        output_file: None,
+        output_dir: None,
+        file_loader: None,
+        diagnostic_output: DiagnosticOutput::Default,
+        stderr: None,
+        crate_name,
+        lint_caps: Default::default(),
+        register_lints: None,
+        override_queries: None,
+        registry: rustc_driver::diagnostics_registry(),
+    };
+
+    let analyses_start = std::time::Instant::now();
+    let body_analyses = interface::create_compiler_and_run(config, |compiler| {
+        compiler.enter(|queries| {
+            let mut global_ctxt = match queries.global_ctxt() {
+                Ok(global_ctxt) => global_ctxt.take(),
+                Err(_) => return None,
+            };
+            global_ctxt.enter(|tcx| {
+                tcx.analysis(LOCAL_CRATE).ok()?;
+                let mut body_analyses = BodyAnalyses::default();
+                if unsafe_util::unsafe_report_requested() {
+                    let unsafe_reachability = unsafe_util::unsafe_reachability(tcx);
+                    body_analyses.unsafe_sites =
+                        by_def_path_hash(tcx, unsafe_reachability.reachable_sites);
+                    body_analyses.all_unsafe_sites = unsafe_reachability.all_sites;
+                }
+                if ffi_util::ffi_classification_requested() {
+                    let ffi_classification = ffi_util::ffi_classification(tcx);
+                    body_analyses.foreign_callees =
+                        by_def_path_hash(tcx, ffi_classification.foreign_callees);
+                }
+                if relation_util::mir_relations_requested() {
+                    let mir_param_relations = relation_util::mir_param_relations(tcx);
+                    body_analyses.param_relations =
+                        by_def_path_hash(tcx, mir_param_relations.relations);
+                }
+                Some(body_analyses)
+            })
+        })
+    });
+    match body_analyses {
+        Some(body_analyses) => {
+            println!("analyzed function bodies in {:?}", analyses_start.elapsed());
+            body_analyses
+        }
+        None => {
+            println!("can not compile the crate, function bodies are not analyzed");
+            BodyAnalyses::default()
+        }
+    }
+}
+
+fn by_def_path_hash<T>(
+    tcx: TyCtxt<'_>,
+    by_def_id: FxHashMap<DefId, T>,
+) -> FxHashMap<DefPathHash, T> {
+    by_def_id.into_iter().map(|(def_id, value)| (tcx.def_path_hash(def_id), value)).collect()
+}
+
+/// Maps the results of `fuzz_target_body_analyses` back to the functions of the rustdoc session.
+/// Closures don't exist in the rustdoc session; the analyses record them on their functions.
+fn by_local_def_id<T>(
+    tcx: TyCtxt<'_>,
+    mut by_def_path_hash: FxHashMap<DefPathHash, T>,
+) -> FxHashMap<DefId, T> {
+    let mut res = FxHashMap::default();
+    if by_def_path_hash.is_empty() {
+        return res;
+    }
+    for local_def_id in tcx.body_owners() {
+        let def_id = local_def_id.to_def_id();
+        if let Some(value) = by_def_path_hash.remove(&tcx.def_path_hash(def_id)) {
+            res.insert(def_id, value);
+        }
+    }
+    res
+}
+
 /// `DefId` or parameter index (`ty::ParamTy.index`) of a synthetic type parameter
 /// for `impl Trait` in argument position.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
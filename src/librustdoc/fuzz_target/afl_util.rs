@@ -1,6 +1,9 @@
 use crate::clean::PrimitiveType;
 use crate::fuzz_target::fuzzable_type::FuzzableType;
 use rustc_data_structures::fx::FxHashSet;
+
+//有多个动态长度参数时，用带长度前缀的分段代替平均分配，_sections中保存每段的起止位置
+pub static _SECTIONS: &'static str = "_sections";
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub enum _AflHelpers {
     _NoHelper,
@@ -22,7 +25,11 @@ pub enum _AflHelpers {
     _Bool,
     _Str,
     _Slice(Box<_AflHelpers>),
+    _Buffer(Box<_AflHelpers>),
     _Tuple(Vec<Box<_AflHelpers>>),
+    _ZeroSized(String),
+    //struct字面量，保存类型名和每个字段的helper
+    _Struct(String, Vec<(String, Box<_AflHelpers>)>),
 }
 
 impl _AflHelpers {
@@ -60,6 +67,23 @@ impl _AflHelpers {
                     .collect();
                 _AflHelpers::_Tuple(inner_afl_helpers)
             }
+            FuzzableType::ZeroSized { value, .. } => _AflHelpers::_ZeroSized(value.clone()),
+            FuzzableType::Struct { type_name, fields } => {
+                let field_afl_helpers = fields
+                    .iter()
+                    .map(|(field_name, field_fuzzable)| {
+                        (
+                            field_name.clone(),
+                            Box::new(_AflHelpers::_new_from_fuzzable(field_fuzzable)),
+                        )
+                    })
+                    .collect();
+                _AflHelpers::_Struct(type_name.clone(), field_afl_helpers)
+            }
+            FuzzableType::MutBuffer { inner, .. } => {
+                let inner_afl_helper = _AflHelpers::_new_from_fuzzable(inner);
+                _AflHelpers::_Buffer(Box::new(inner_afl_helper))
+            }
         }
     }
 
@@ -69,11 +93,20 @@ impl _AflHelpers {
     //Tuple在这一步已经全部排除掉了，所以接下来不会再有tuple的问题
     pub fn _get_all_dependent_afl_helpers(&self) -> Vec<_AflHelpers> {
         let mut helpers = Vec::new();
+        if let _AflHelpers::_ZeroSized(..) = self {
+            //直接构造，不需要helper函数
+            return helpers;
+        }
         if let _AflHelpers::_Tuple(inner_helpers) = self {
             for afl_helper in inner_helpers {
                 let mut inner_dependent = afl_helper._get_all_dependent_afl_helpers();
                 helpers.append(&mut inner_dependent);
             }
+        } else if let _AflHelpers::_Struct(_, field_helpers) = self {
+            for (_, afl_helper) in field_helpers {
+                let mut field_dependent = afl_helper._get_all_dependent_afl_helpers();
+                helpers.append(&mut field_dependent);
+            }
         } else {
             helpers.push(self.clone());
             match self {
@@ -84,7 +117,7 @@ impl _AflHelpers {
                 | _AflHelpers::_Str
                 | _AflHelpers::_F32
                 | _AflHelpers::_F64 => {}
-                _AflHelpers::_Bool => {
+                _AflHelpers::_Bool | _AflHelpers::_Buffer(..) => {
                     let mut u8_dependency = _AflHelpers::_U8._get_all_dependent_afl_helpers();
                     helpers.append(&mut u8_dependency);
                 }
@@ -132,7 +165,9 @@ impl _AflHelpers {
                     let mut u32_dependency = _AflHelpers::_U32._get_all_dependent_afl_helpers();
                     helpers.append(&mut u32_dependency);
                 }
-                _AflHelpers::_Tuple(..) => {}
+                _AflHelpers::_Tuple(..)
+                | _AflHelpers::_ZeroSized(..)
+                | _AflHelpers::_Struct(..) => {}
             }
         }
         helpers
@@ -159,7 +194,8 @@ impl _AflHelpers {
             _AflHelpers::_Bool => _data_to_bool(),
             _AflHelpers::_Str => _data_to_str(),
             _AflHelpers::_Slice(..) => _data_to_slice(),
-            _AflHelpers::_Tuple(..) => "",
+            _AflHelpers::_Buffer(..) => _data_to_buffer(),
+            _AflHelpers::_Tuple(..) | _AflHelpers::_ZeroSized(..) | _AflHelpers::_Struct(..) => "",
         }
     }
 
@@ -184,6 +220,9 @@ impl _AflHelpers {
             _AflHelpers::_Char => "char".to_string(),
             _AflHelpers::_Str => "str".to_string(),
             _AflHelpers::_Slice(..) => "slice".to_string(),
+            _AflHelpers::_Buffer(..) => "buffer".to_string(),
+            _AflHelpers::_ZeroSized(value) => value.clone(),
+            _AflHelpers::_Struct(type_name, _) => type_name.clone(),
             _AflHelpers::_Tuple(inner_afl_helpers) => {
                 let mut type_name = "(".to_string();
                 let inner_afl_helpers_length = inner_afl_helpers.len();
@@ -203,7 +242,7 @@ impl _AflHelpers {
 
     pub fn _to_function_name(&self) -> String {
         match self {
-            _AflHelpers::_Slice(inner_afl_helpers) => {
+            _AflHelpers::_Slice(inner_afl_helpers) | _AflHelpers::_Buffer(inner_afl_helpers) => {
                 //不考虑内部还是slice或者str的情况,这种函数在构建graph的时候就已经作为多维动态长度被删掉了
                 //tuple里面也不会出现slice或者
                 let inner_type_name = inner_afl_helpers._type_name();
@@ -213,7 +252,9 @@ impl _AflHelpers {
                     inner_type_name = inner_type_name
                 )
             }
-            _AflHelpers::_Tuple(..) => String::new(),
+            _AflHelpers::_Tuple(..) | _AflHelpers::_ZeroSized(..) | _AflHelpers::_Struct(..) => {
+                String::new()
+            }
             _ => {
                 format!("_to_{type_name}", type_name = self._type_name())
             }
@@ -238,6 +279,7 @@ impl _AflHelpers {
         println!("{}", _data_to_bool());
         println!("{}", _data_to_str());
         println!("{}", _data_to_slice());
+        println!("{}", _data_to_buffer());
         println!("{}", _data_to_f32());
         println!("{}", _data_to_f64());
     }
@@ -327,6 +369,26 @@ impl _AflHelpers {
                     fixed_start_index = fixed_start_index
                 )
             }
+            _AflHelpers::_Buffer(..) => {
+                let undersized = match origin_fuzzable_type {
+                    FuzzableType::MutBuffer { undersized, .. } => *undersized,
+                    _ => false,
+                };
+                format!(
+                    "{afl_function_name}(data, {fixed_start_index}, {undersized})",
+                    afl_function_name = self._to_function_name(),
+                    fixed_start_index = fixed_start_index,
+                    undersized = undersized
+                )
+            }
+            _AflHelpers::_Str | _AflHelpers::_Slice(..) if dynamic_param_length == _SECTIONS => {
+                format!(
+                    "{afl_function_name}(data, {sections}[{index}].0, {sections}[{index}].1)",
+                    afl_function_name = self._to_function_name(),
+                    sections = _SECTIONS,
+                    index = dynamic_param_index
+                )
+            }
             _AflHelpers::_Str | _AflHelpers::_Slice(..) => {
                 let latter_index = if dynamic_param_index == total_dynamic_param_numbers - 1 {
                     format!("data.len()")
@@ -380,6 +442,32 @@ impl _AflHelpers {
                     "Type not match in afl_util".to_string()
                 }
             }
+            _AflHelpers::_Struct(type_name, field_afl_helpers) => {
+                if let FuzzableType::Struct { fields, .. } = origin_fuzzable_type {
+                    //字段都是定长的primitive类型，依次从固定部分读取
+                    let mut field_initials = Vec::new();
+                    let mut field_fixed_start_index = fixed_start_index;
+                    for ((field_name, field_afl_helper), (_, field_fuzzable)) in
+                        field_afl_helpers.iter().zip(fields.iter())
+                    {
+                        let field_rhs = field_afl_helper._generate_param_initial_rhs(
+                            field_fixed_start_index,
+                            dynamic_start_index,
+                            dynamic_param_index,
+                            total_dynamic_param_numbers,
+                            dynamic_param_length,
+                            field_fuzzable,
+                        );
+                        field_initials.push(format!("{}: {}", field_name, field_rhs));
+                        field_fixed_start_index =
+                            field_fixed_start_index + field_fuzzable._fixed_part_length();
+                    }
+                    format!("{} {{ {} }}", type_name, field_initials.join(", "))
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            _AflHelpers::_ZeroSized(value) => value.clone(),
             _AflHelpers::_NoHelper => {
                 format!("No helper")
             }
@@ -419,11 +507,56 @@ pub fn _get_afl_helpers_functions_of_sequence(
             afl_helper_functions.push(afl_helper._to_full_function().to_string());
             continue;
         }
-        afl_helper_functions.push(afl_helper._to_full_function().to_string())
+        //buffer和slice一样是范型函数，不同元素类型只定义一次
+        let full_function = afl_helper._to_full_function().to_string();
+        if afl_helper_functions.contains(&full_function) {
+            continue;
+        }
+        afl_helper_functions.push(full_function)
     }
     Some(afl_helper_functions)
 }
 
+//从start开始，前section_number - 1段的开头是2字节(小端)的长度，最后一段是剩余的所有数据。
+//长度超出数据范围时截断，因此任何输入都可以被解析
+pub fn _split_sections_function() -> &'static str {
+    "fn _split_sections(data: &[u8], start: usize, section_number: usize) -> Vec<(usize, usize)> {
+    let mut sections = Vec::new();
+    let mut position = start;
+    for _ in 0..section_number - 1 {
+        let prefix_end = std::cmp::min(position + 2, data.len());
+        let mut section_length = 0;
+        for (i, byte) in data[position..prefix_end].iter().enumerate() {
+            section_length |= (*byte as usize) << (8 * i);
+        }
+        let section_end = std::cmp::min(prefix_end + section_length, data.len());
+        sections.push((prefix_end, section_end));
+        position = section_end;
+    }
+    sections.push((position, data.len()));
+    sections
+}\n"
+}
+
+//设置了FUZZ_TARGET_AFLPP_VARIANTS的话，afl target推迟fork server的启动，
+//并且额外生成用AFL_LLVM_CMPLOG=1编译的cmplog版本，给afl-fuzz的-c参数使用
+pub fn aflpp_variants_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_AFLPP_VARIANTS").is_some()
+}
+
+//afl-fuzz在二进制中找到这个签名时不在程序启动时fork，而是等到调用__afl_manual_init，
+//这样参数检查和静态数据的初始化只在fork server启动之前执行一次
+pub fn _deferred_init_function() -> &'static str {
+    "#[used]
+static _AFL_DEFER_FORKSRV: [u8; 25] = *b\"##SIG_AFL_DEFER_FORKSRV##\";
+extern \"C\" {
+    fn __afl_manual_init();
+}
+fn _afl_deferred_init() {
+    unsafe { __afl_manual_init() };
+}\n"
+}
+
 //获得可能的feature gate,
 pub fn _get_feature_gates_of_sequence(fuzzable_params: &Vec<FuzzableType>) -> Option<Vec<String>> {
     let all_afl_helpers = _get_all_dependent_afl_helpers_of_sequence(fuzzable_params);
@@ -597,3 +730,13 @@ pub fn _data_to_slice() -> &'static str {
     shorts
 }\n"
 }
+
+//输出buffer的长度由一个字节决定：正常的buffer为256的倍数，最长4096，
+//undersized的buffer只有0到15个元素，用于检查api对过小buffer的处理
+pub fn _data_to_buffer() -> &'static str {
+    "fn _to_buffer<T: Clone + Default>(data:&[u8], index: usize, undersized: bool)->Vec<T> {
+    let size_byte = _to_u8(data, index) as usize;
+    let length = if undersized { size_byte % 16 } else { 256 * (size_byte % 16 + 1) };
+    vec![T::default(); length]
+}\n"
+}
@@ -4,10 +4,15 @@ use crate::fuzz_target::api_util;
 use crate::fuzz_target::call_type::CallType;
 use crate::fuzz_target::fuzzable_type::{self, FuzzableType};
 use crate::fuzz_target::impl_util::FullNameMap;
+use crate::fuzz_target::prelude_type::_PreludeHelper;
 use rustc_hir::{self, Mutability};
 
 use crate::clean;
 
+//纯转换函数的名字前缀和trait，见ApiFunction::_converter_types
+static CONVERTER_PREFIXES: [&str; 3] = ["as_", "to_", "into_"];
+static CONVERTER_TRAITS: [&str; 2] = ["From", "AsRef"];
+
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub enum ApiUnsafety {
     Unsafe,
@@ -21,7 +26,24 @@ pub struct ApiFunction {
     pub inputs: Vec<clean::Type>,
     pub output: Option<clean::Type>,
     pub _trait_full_path: Option<String>, //Trait的全限定路径,因为使用trait::fun来调用函数的时候，需要将trait的全路径引入
+    pub _impl_type_full_path: Option<String>, //impl块的self type的全限定路径，不在impl块中的函数为None
     pub _unsafe_tag: ApiUnsafety,
+    pub _helper: Option<_PreludeHelper>, //由生成的helper函数实现的api,需要在target里面加入helper函数的定义
+    pub _param_relations: Vec<ParamRelation>, //参数之间的长度/下标关系
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum ParamRelationKind {
+    _Len,   //param == buffer.len()
+    _Index, //param < buffer.len()
+}
+
+//第param个参数是第buffer个参数的长度或者下标
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct ParamRelation {
+    pub param: usize,
+    pub buffer: usize,
+    pub kind: ParamRelationKind,
 }
 
 impl ApiUnsafety {
@@ -116,9 +138,96 @@ impl ApiFunction {
                 return true;
             }
         }
+        //const泛型参数(如fn chunk<const N: usize>() -> [u8; N])必须实例化之后才能调用
+        for param in &self.generics.params {
+            if let clean::GenericParamDefKind::Const { .. } = param.kind {
+                return true;
+            }
+        }
         return false;
     }
 
+    //名字是as_*/to_*/into_*，或者是From/AsRef的实现，只有一个不可变参数并且有返回值的函数，
+    //当作纯转换函数。返回(输入类型名, 输出类型名)，引用会被去掉。
+    //其他只有一个参数的函数(解析、哈希、getter)不一定可逆，不算
+    pub fn _converter_types(&self, full_name_map: &FullNameMap) -> Option<(String, String)> {
+        if self.inputs.len() != 1 || self.contains_mut_borrow() || self._unsafe_tag._is_unsafe() {
+            return None;
+        }
+        if !self._has_converter_name() {
+            return None;
+        }
+        let output = self.output.as_ref()?;
+        let input_name = api_util::_type_name(_strip_reference(&self.inputs[0]), full_name_map);
+        let output_name = api_util::_type_name(_strip_reference(output), full_name_map);
+        let unsupported = "Currently not supported";
+        if input_name == output_name || input_name == unsupported || output_name == unsupported {
+            return None;
+        }
+        Some((input_name, output_name))
+    }
+
+    fn _has_converter_name(&self) -> bool {
+        let last_name = |path: &str| path.rsplit("::").next().unwrap_or("").to_string();
+        let function_name = last_name(&self.full_name);
+        if CONVERTER_PREFIXES.iter().any(|prefix| function_name.starts_with(prefix)) {
+            return true;
+        }
+        match &self._trait_full_path {
+            Some(trait_path) => CONVERTER_TRAITS.contains(&last_name(trait_path).as_str()),
+            None => false,
+        }
+    }
+
+    //返回值借用了哪些参数。命名的生命周期按名字对应；返回值中省略的生命周期按照省略规则，
+    //来自唯一带有引用的参数，或者来自self(第一个参数)。'static不算借用
+    pub fn _output_borrowed_params(&self) -> Vec<usize> {
+        let output = match &self.output {
+            Some(output) => output,
+            None => return Vec::new(),
+        };
+        let mut output_lifetimes = Vec::new();
+        _collect_lifetimes(output, &mut output_lifetimes);
+        if output_lifetimes.is_empty() {
+            return Vec::new();
+        }
+        let input_lifetimes: Vec<Vec<Option<String>>> = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let mut lifetimes = Vec::new();
+                _collect_lifetimes(input, &mut lifetimes);
+                lifetimes
+            })
+            .collect();
+        let mut res = Vec::new();
+        for output_lifetime in &output_lifetimes {
+            match output_lifetime {
+                Some(name) if name == "'static" => {}
+                Some(name) => {
+                    for (index, lifetimes) in input_lifetimes.iter().enumerate() {
+                        if lifetimes.contains(output_lifetime) && !res.contains(&index) {
+                            res.push(index);
+                        }
+                    }
+                }
+                None => {
+                    let borrowing_inputs: Vec<usize> = input_lifetimes
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, lifetimes)| !lifetimes.is_empty())
+                        .map(|(index, _)| index)
+                        .collect();
+                    let index = if borrowing_inputs.len() == 1 { borrowing_inputs[0] } else { 0 };
+                    if !self.inputs.is_empty() && !res.contains(&index) {
+                        res.push(index);
+                    }
+                }
+            }
+        }
+        res
+    }
+
     pub fn _has_no_output(&self) -> bool {
         match self.output {
             None => true,
@@ -174,3 +283,77 @@ impl ApiFunction {
         return false;
     }
 }
+
+pub fn _strip_reference(ty_: &clean::Type) -> &clean::Type {
+    match ty_ {
+        clean::Type::BorrowedRef { type_, .. } => _strip_reference(&**type_),
+        _ => ty_,
+    }
+}
+
+//类型中出现的生命周期，None表示省略的引用生命周期
+fn _collect_lifetimes(ty_: &clean::Type, lifetimes: &mut Vec<Option<String>>) {
+    match ty_ {
+        clean::Type::BorrowedRef { lifetime, type_, .. } => {
+            lifetimes.push(lifetime.as_ref().map(|lifetime| lifetime.get_ref().to_string()));
+            _collect_lifetimes(type_, lifetimes);
+        }
+        clean::Type::ResolvedPath { path, .. } => {
+            for segment in &path.segments {
+                if let clean::GenericArgs::AngleBracketed { args, bindings } = &segment.args {
+                    //impl Iterator<Item = &'a u8>中的Item
+                    for binding in bindings {
+                        if let clean::TypeBindingKind::Equality { ty: binding_type } = &binding.kind
+                        {
+                            _collect_lifetimes(binding_type, lifetimes);
+                        }
+                    }
+                    for arg in args {
+                        match arg {
+                            clean::GenericArg::Lifetime(lifetime) => {
+                                let name = lifetime.get_ref();
+                                //'_和省略的生命周期一样处理
+                                if name == "'_" {
+                                    lifetimes.push(None);
+                                } else {
+                                    lifetimes.push(Some(name.to_string()));
+                                }
+                            }
+                            clean::GenericArg::Type(inner_type) => {
+                                _collect_lifetimes(inner_type, lifetimes)
+                            }
+                            clean::GenericArg::Const(..) => {}
+                        }
+                    }
+                }
+            }
+        }
+        clean::Type::Tuple(types) => {
+            for inner_type in types {
+                _collect_lifetimes(inner_type, lifetimes);
+            }
+        }
+        clean::Type::Slice(type_) | clean::Type::Array(type_, _) => {
+            _collect_lifetimes(type_, lifetimes)
+        }
+        //impl Iterator<Item = u8> + '_
+        clean::Type::ImplTrait(bounds) => {
+            for bound in bounds {
+                match bound {
+                    clean::GenericBound::TraitBound(clean::PolyTrait { trait_, .. }, _) => {
+                        _collect_lifetimes(trait_, lifetimes)
+                    }
+                    clean::GenericBound::Outlives(lifetime) => {
+                        let name = lifetime.get_ref();
+                        if name == "'_" {
+                            lifetimes.push(None);
+                        } else {
+                            lifetimes.push(Some(name.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
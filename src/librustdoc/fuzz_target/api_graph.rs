@@ -1,21 +1,32 @@
-use crate::fuzz_target::api_function::ApiFunction;
+use crate::fuzz_target::api_function::{self, ApiFunction, ApiUnsafety};
 use crate::fuzz_target::api_sequence::{ApiCall, ApiSequence, ParamType};
-use crate::fuzz_target::api_util;
+use crate::fuzz_target::api_util::{self, RulfAnnotation};
+use crate::fuzz_target::bug_report_util::MetadataError;
 use crate::fuzz_target::call_type::CallType;
+use crate::fuzz_target::ffi_util::{self, FfiClassification, FfiPolicy};
 use crate::fuzz_target::fuzzable_type;
 use crate::fuzz_target::fuzzable_type::FuzzableType;
 use crate::fuzz_target::impl_util::FullNameMap;
+use crate::fuzz_target::instance_util::{self, FunctionInstances, GenericInstances, InstanceType};
 use crate::fuzz_target::mod_visibility::ModVisibity;
 use crate::fuzz_target::prelude_type;
+use crate::fuzz_target::producer_util::ProducerFunctions;
+use crate::fuzz_target::projection_util::NormalizedSignatures;
+use crate::fuzz_target::relation_util::MirParamRelations;
+use crate::fuzz_target::unsafe_util::UnsafeReachability;
+use crate::fuzz_target::usage_util::ApiUsage;
 
 //use crate::clean::{PrimitiveType};
 use rand::{self, Rng};
+use rayon::prelude::*;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::time::Instant;
 
-use crate::clean::Visibility;
+use crate::clean::{self, types::GetDefId, Visibility};
+use rustc_hir::def_id::DefId;
 
-use super::generic_function::GenericFunction;
+use super::generic_function::{self, GenericFunction};
 
 lazy_static! {
     static ref RANDOM_WALK_STEPS: HashMap<&'static str, usize> = {
@@ -37,6 +48,20 @@ lazy_static! {
     };
 }
 
+static FOCUS_SEQUENCES_PER_API: usize = 4;
+//stateful序列中最多可以选择的&mut self方法数
+static MAX_STATEFUL_METHODS: usize = 16;
+//通过Deref链寻找参数类型时最多解引用的次数
+static MAX_DEREF_DEPTH: usize = 4;
+//为泛型函数选择实例时，不是fuzzable的参数和没有api产生的类型的代价
+static UNFUZZABLE_INPUT_COST: usize = 4;
+static UNPRODUCIBLE_TYPE_COST: usize = 64;
+//weighted random walk中api复杂度的权重：每个参数，unsafe fn，每个可以到达的unsafe位置，每若干行代码
+static PARAM_WEIGHT: usize = 2;
+static UNSAFE_FN_WEIGHT: usize = 4;
+static UNSAFE_SITE_WEIGHT: usize = 1;
+static LINES_PER_WEIGHT: usize = 5;
+
 #[derive(Clone, Debug)]
 pub struct ApiGraph {
     pub _crate_name: String,
@@ -47,10 +72,93 @@ pub struct ApiGraph {
     pub full_name_map: FullNameMap,  //did to full_name
     pub mod_visibility: ModVisibity, //the visibility of mods，to fix the problem of `pub use`
     pub generic_functions: Vec<GenericFunction>,
+    //trait solver得到的泛型函数的实例化，以及api -> 实例化
+    pub generic_instances: GenericInstances,
+    pub api_generic_instances: HashMap<String, FunctionInstances>,
+    //trait solver规范化之后的函数签名，用来替换签名中的关联类型
+    pub normalized_signatures: NormalizedSignatures,
     pub functions_with_unsupported_fuzzable_types: HashSet<String>,
+    //只在打开helper cfg时才编译的函数和mod
+    pub cfg_gated_functions: HashSet<String>,
+    //async api，调用时需要.await，整个序列在executor中执行
+    pub async_functions: HashSet<String>,
+    //执行async api的helper，被测crate依赖tokio时使用tokio的runtime
+    pub async_helper: prelude_type::_PreludeHelper,
+    pub cfg_gated_mods: HashSet<String>,
+    //api或mod需要的cargo feature，以及在选中的feature下不会被编译的api或mod
+    pub feature_gates: HashMap<String, BTreeSet<String>>,
+    pub feature_disabled: HashSet<String>,
+    //crate中的类型实现了哪些trait(只记录trait名的最后一段)
+    pub implemented_traits: HashMap<String, HashSet<String>>,
+    //实现了Deref的类型 -> Target，以及实现了DerefMut的类型
+    pub deref_targets: HashMap<DefId, clean::Type>,
+    pub deref_mut_types: HashSet<DefId>,
+    //crate中From和TryFrom impl给出的转换：(源类型, 目标类型, 是否是TryFrom)
+    pub conversions: Vec<(clean::Type, clean::Type, bool)>,
+    //trait coverage模式下为每个(trait, type)生成的序列
+    pub trait_coverage_sequences: Vec<ApiSequence>,
+    //stateful模式下对同一个值反复调用&mut self方法的序列
+    pub stateful_sequences: Vec<ApiSequence>,
+    //需要更多序列的api(由fuzz的覆盖率反馈得到)，以及为它们额外选择的序列
+    pub focus_sequences: Vec<ApiSequence>,
+    //格式化实现了Debug/Display的类型的函数，以及把它们合并到一起的format sweep序列
+    pub format_functions: Vec<ApiFunction>,
+    pub format_sweep_sequence: Option<ApiSequence>,
+    //检查序列化兼容性的函数，以及之前版本记录的golden序列化形式(类型名, json)
+    pub serde_functions: Vec<ApiFunction>,
+    pub serde_golden: Vec<(String, String)>,
+    //由MIR调用图得到的每个函数可以到达的unsafe位置，以及api -> 可以到达的unsafe位置
+    pub unsafe_reachability: UnsafeReachability,
+    pub api_unsafe_sites: HashMap<String, BTreeSet<String>>,
+    //由MIR得到的直接调用外部(C)函数的函数，以及api -> 调用的外部函数
+    pub ffi_classification: FfiClassification,
+    pub api_ffi_callees: HashMap<String, BTreeSet<String>>,
+    //由MIR得到的参数之间的长度/下标关系，构造ApiFunction时和按参数名推测的关系合并
+    pub mir_param_relations: MirParamRelations,
+    //api的源码行数，用于weighted random walk中的api复杂度
+    pub api_lines: HashMap<String, usize>,
+    //反向依赖中统计到的api使用情况，用于在选择序列时优先选择符合实际用法的序列
+    pub api_usage: ApiUsage,
+    //带有#[non_exhaustive]的struct和enum
+    pub non_exhaustive_types: HashSet<String>,
+    //提取api时因为元数据缺失而跳过的item
+    pub metadata_errors: Vec<MetadataError>,
+    //可以由helper函数构造的常见外部类型
+    pub ecosystem_types: HashMap<DefId, prelude_type::_PreludeHelper>,
+    //依赖crate中可以构造api参数类型的函数，以及加入图中的这些函数 -> 所在的crate
+    pub producer_functions: ProducerFunctions,
+    pub api_producer_crates: HashMap<String, String>,
+    //没有泛型参数的pub enum和它们可以构造的变体
+    pub enum_variants: Vec<(DefId, String, Vec<api_util::EnumVariant>)>,
+    //库作者用#[rulf::...]标注的api和类型：跳过的api，优先的api，类型 -> 指定的构造函数
+    pub skipped_functions: HashSet<String>,
+    pub fuzz_entries: Vec<String>,
+    pub annotated_constructors: HashMap<DefId, String>,
     //pub _sequences_of_all_algorithm : FxHashMap<GraphTraverseAlgorithm, Vec<ApiSequence>>
 }
 
+//分析一个item或impl之前图中各个列表的长度，以及分析时会修改的按类型记录的集合的副本。
+//分析时generator panic的话，用_rollback去掉这次分析加入的api，图中不会留下只分析了一半的item。
+//full_name_map中只有零大小类型和字面量struct是分析时加入的，也要恢复；
+//api_producer_crates在分析之后才加入，不需要恢复
+#[derive(Clone, Debug)]
+pub struct ApiGraphCheckpoint {
+    api_functions: usize,
+    generic_functions: usize,
+    conversions: usize,
+    enum_variants: usize,
+    format_functions: usize,
+    serde_functions: usize,
+    fuzz_entries: usize,
+    implemented_traits: HashMap<String, HashSet<String>>,
+    deref_targets: HashMap<DefId, clean::Type>,
+    deref_mut_types: HashSet<DefId>,
+    non_exhaustive_types: HashSet<String>,
+    annotated_constructors: HashMap<DefId, String>,
+    zero_sized_types: HashSet<DefId>,
+    literal_structs: HashMap<DefId, Vec<(String, clean::Type)>>,
+}
+
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub enum GraphTraverseAlgorithm {
     _Bfs,
@@ -59,6 +167,7 @@ pub enum GraphTraverseAlgorithm {
     _FastBfsEndPoint,
     _RandomWalk,
     _RandomWalkEndPoint,
+    _WeightedRandomWalk,
     _TryDeepBfs,
     _DirectBackwardSearch,
 }
@@ -90,7 +199,43 @@ impl ApiGraph {
             full_name_map: FullNameMap::new(),
             mod_visibility: ModVisibity::new(_crate_name),
             generic_functions: Vec::new(),
+            generic_instances: GenericInstances::default(),
+            api_generic_instances: HashMap::new(),
+            normalized_signatures: NormalizedSignatures::default(),
             functions_with_unsupported_fuzzable_types: HashSet::new(),
+            cfg_gated_functions: HashSet::new(),
+            async_functions: HashSet::new(),
+            async_helper: prelude_type::_PreludeHelper::_BlockOnHelper,
+            cfg_gated_mods: HashSet::new(),
+            feature_gates: HashMap::new(),
+            feature_disabled: HashSet::new(),
+            implemented_traits: HashMap::new(),
+            deref_targets: HashMap::new(),
+            deref_mut_types: HashSet::new(),
+            conversions: Vec::new(),
+            trait_coverage_sequences: Vec::new(),
+            stateful_sequences: Vec::new(),
+            focus_sequences: Vec::new(),
+            format_functions: Vec::new(),
+            format_sweep_sequence: None,
+            serde_functions: Vec::new(),
+            serde_golden: Vec::new(),
+            unsafe_reachability: UnsafeReachability::default(),
+            api_unsafe_sites: HashMap::new(),
+            ffi_classification: FfiClassification::default(),
+            api_ffi_callees: HashMap::new(),
+            mir_param_relations: MirParamRelations::default(),
+            api_lines: HashMap::new(),
+            api_usage: ApiUsage::default(),
+            non_exhaustive_types: HashSet::new(),
+            metadata_errors: Vec::new(),
+            ecosystem_types: HashMap::new(),
+            producer_functions: ProducerFunctions::default(),
+            api_producer_crates: HashMap::new(),
+            enum_variants: Vec::new(),
+            skipped_functions: HashSet::new(),
+            fuzz_entries: Vec::new(),
+            annotated_constructors: HashMap::new(),
             //_sequences_of_all_algorithm,
         }
     }
@@ -110,9 +255,918 @@ impl ApiGraph {
         self.mod_visibility.add_one_mod(mod_name, visibility);
     }
 
+    pub fn add_implemented_trait(&mut self, type_name: &String, trait_name: &String) {
+        self.implemented_traits.entry(type_name.clone()).or_default().insert(trait_name.clone());
+    }
+
+    //用crate中返回Result的api产生的错误类型来实例化以错误类型为泛型参数的函数
+    pub fn _instantiate_error_generics(&mut self) {
+        let mut error_types = Vec::new();
+        let mut error_type_names = HashSet::new();
+        for api_function in &self.api_functions {
+            let output = match &api_function.output {
+                Some(output) => output,
+                None => continue,
+            };
+            let prelude_type = prelude_type::PreludeType::from_type(output, &self.full_name_map);
+            if let prelude_type::PreludeType::PreludeResult { err_type, .. } = prelude_type {
+                if api_util::_is_generic_type(&err_type) {
+                    continue;
+                }
+                let err_type_name = api_util::_type_name(&err_type, &self.full_name_map);
+                if self.implemented_traits.contains_key(&err_type_name)
+                    && error_type_names.insert(err_type_name.clone())
+                {
+                    error_types.push((err_type, err_type_name));
+                }
+            }
+        }
+
+        let mut instantiated_functions = Vec::new();
+        for generic_function in &self.generic_functions {
+            let (param_name, required_traits) = match generic_function._error_type_param() {
+                Some(error_type_param) => error_type_param,
+                None => continue,
+            };
+            for (error_type, error_type_name) in &error_types {
+                let traits = &self.implemented_traits[error_type_name];
+                if !required_traits.iter().all(|required_trait| traits.contains(required_trait)) {
+                    continue;
+                }
+                let api_function =
+                    generic_function._instantiate(&param_name, error_type, error_type_name);
+                if !api_function._is_generic_function() {
+                    instantiated_functions.push(api_function);
+                }
+            }
+        }
+        println!("functions instantiated with error types : {}", instantiated_functions.len());
+        for api_function in instantiated_functions {
+            self.add_api_function(api_function);
+        }
+    }
+
+    //用trait solver检查过约束的具体类型实例化泛型函数。crate中的类型需要以同样的形式出现在
+    //其他api的参数或返回值中，这样实例化之后的api才能和它们连接起来
+    pub fn _instantiate_generics(&mut self) {
+        let mut known_types = HashMap::new();
+        for api_function in &self.api_functions {
+            for ty_ in api_function.inputs.iter().chain(api_function.output.iter()) {
+                _collect_concrete_types(ty_, &mut known_types);
+            }
+        }
+
+        let producer_costs = self._producer_costs();
+
+        let mut instantiated_functions = Vec::new();
+        let mut instantiated_names: HashSet<String> =
+            self.api_functions.iter().map(|api_function| api_function.full_name.clone()).collect();
+        for generic_function in &self.generic_functions {
+            let full_name = &generic_function.api_function.full_name;
+            let function_instances = match self.api_generic_instances.get(full_name) {
+                Some(function_instances) => function_instances,
+                None => continue,
+            };
+            //满足约束的实例按构造参数的代价排序，代价相同时保持trait solver找到的顺序
+            let mut ranked_instances: Vec<(usize, &Vec<(String, InstanceType)>)> =
+                function_instances
+                    .instances
+                    .iter()
+                    .map(|instance| {
+                        let cost = instance
+                            .iter()
+                            .map(|(_, instance_type)| {
+                                _instance_type_cost(instance_type, &producer_costs)
+                            })
+                            .sum();
+                        (cost, instance)
+                    })
+                    .collect();
+            ranked_instances.sort_by_key(|(cost, _)| *cost);
+            let mut instance_number = 0;
+            'instance: for (_, instance) in ranked_instances {
+                if instance_number >= instance_util::MAX_INSTANCES {
+                    break;
+                }
+                let mut substitutes = Vec::new();
+                for (param_name, instance_type) in instance {
+                    let concrete_type = match instance_type {
+                        InstanceType::Primitive(primitive_type) => {
+                            clean::Type::Primitive(*primitive_type)
+                        }
+                        InstanceType::StrRef => clean::Type::BorrowedRef {
+                            lifetime: None,
+                            mutability: rustc_hir::Mutability::Not,
+                            type_: Box::new(clean::Type::Primitive(clean::PrimitiveType::Str)),
+                        },
+                        InstanceType::Adt(def_id) => match known_types.get(def_id) {
+                            Some(known_type) => known_type.clone(),
+                            None => continue 'instance,
+                        },
+                    };
+                    let concrete_type_name =
+                        api_util::_type_name(&concrete_type, &self.full_name_map);
+                    substitutes.push((param_name.clone(), concrete_type, concrete_type_name));
+                }
+                let api_function = generic_function
+                    ._instantiate_all(&substitutes, function_instances.impl_param_count);
+                if !api_function._is_generic_function()
+                    && instantiated_names.insert(api_function.full_name.clone())
+                {
+                    instantiated_functions.push(api_function);
+                    instance_number += 1;
+                }
+            }
+        }
+        println!("functions instantiated by trait solver : {}", instantiated_functions.len());
+        for api_function in instantiated_functions {
+            self.add_api_function(api_function);
+        }
+    }
+
+    //crate中的类型 -> 产生它的最便宜的非泛型api的代价。每个fuzzable参数代价为1，
+    //其他参数需要先调用别的api，代价更高
+    fn _producer_costs(&self) -> HashMap<DefId, usize> {
+        let mut producer_costs = HashMap::new();
+        for api_function in &self.api_functions {
+            if api_function._is_generic_function() {
+                continue;
+            }
+            //Option和Result包着的类型同样可以产生
+            let output_def_id = match api_function.output.as_ref().and_then(|output| {
+                prelude_type::PreludeType::from_type(output, &self.full_name_map)
+                    ._get_final_type()
+                    .def_id()
+            }) {
+                Some(output_def_id) => output_def_id,
+                None => continue,
+            };
+            let cost = 1 + api_function
+                .inputs
+                .iter()
+                .map(|input| {
+                    if api_util::is_fuzzable_type(input, &self.full_name_map) {
+                        1
+                    } else {
+                        UNFUZZABLE_INPUT_COST
+                    }
+                })
+                .sum::<usize>();
+            let producer_cost = producer_costs.entry(output_def_id).or_insert(cost);
+            if cost < *producer_cost {
+                *producer_cost = cost;
+            }
+        }
+        producer_costs
+    }
+
+    //只有const泛型参数的函数，每个const值生成一个单态的api，如chunk::<4>。
+    //有多个const参数时，所有参数取同一个值
+    pub fn _instantiate_const_generics(&mut self) {
+        let values = api_util::const_generic_values();
+        let mut instantiated_functions = Vec::new();
+        for generic_function in &self.generic_functions {
+            let const_params = match generic_function._const_params() {
+                Some(const_params) => const_params,
+                None => continue,
+            };
+            for value in &values {
+                let fits = const_params.iter().all(|(_, primitive_type)| {
+                    generic_function::_const_max_value(primitive_type)
+                        .map_or(false, |max_value| *value <= max_value)
+                });
+                if !fits {
+                    continue;
+                }
+                let const_values: Vec<(String, String)> = const_params
+                    .iter()
+                    .map(|(param_name, _)| (param_name.clone(), value.to_string()))
+                    .collect();
+                let api_function = generic_function._instantiate_consts(&const_values);
+                if !api_function._is_generic_function() {
+                    instantiated_functions.push(api_function);
+                }
+            }
+        }
+        println!("functions instantiated with const values : {}", instantiated_functions.len());
+        for api_function in instantiated_functions {
+            self.add_api_function(api_function);
+        }
+    }
+
+    //返回impl Stream<Item = T>的api，用helper函数取出其中的item，作为T类型的生产者。
+    //每一种stream类型一个helper api，名字中带上item类型，如_stream_next::<u8, _>
+    pub fn _add_stream_consumers(&mut self) {
+        self._add_item_consumers(
+            "stream",
+            "_stream_next",
+            api_util::_impl_stream_item_type,
+            prelude_type::_PreludeHelper::_StreamNextHelper,
+        );
+    }
+
+    //返回impl Iterator<Item = T>的api，用helper函数调用next取出其中的item，作为T类型的生产者
+    pub fn _add_iterator_consumers(&mut self) {
+        self._add_item_consumers(
+            "iterator",
+            "_iter_next",
+            api_util::_impl_iterator_item_type,
+            prelude_type::_PreludeHelper::_IterNextHelper,
+        );
+    }
+
+    fn _add_item_consumers(
+        &mut self,
+        kind: &str,
+        helper_name: &str,
+        item_type_of: fn(&clean::Type) -> Option<clean::Type>,
+        helper: prelude_type::_PreludeHelper,
+    ) {
+        let mut producer_types: Vec<(clean::Type, clean::Type)> = Vec::new();
+        for api_function in &self.api_functions {
+            let output = match &api_function.output {
+                Some(output) => output,
+                None => continue,
+            };
+            if let Some(item_type) = item_type_of(output) {
+                if !producer_types.iter().any(|(producer_type, _)| producer_type == output) {
+                    producer_types.push((output.clone(), item_type));
+                }
+            }
+        }
+        println!("{} types consumed by helper : {}", kind, producer_types.len());
+        for (producer_type, item_type) in producer_types {
+            let item_name = api_util::_type_name(&item_type, &self.full_name_map);
+            let api_function = ApiFunction {
+                full_name: format!("{}::<{}, _>", helper_name, item_name),
+                generics: clean::Generics::default(),
+                inputs: vec![producer_type],
+                output: Some(item_type),
+                _trait_full_path: None,
+                _impl_type_full_path: None,
+                _unsafe_tag: ApiUnsafety::Normal,
+                _helper: Some(helper.clone()),
+                _param_relations: Vec::new(),
+            };
+            self.add_api_function(api_function);
+        }
+    }
+
+    //api的参数中出现bytes::Bytes等常见外部类型时，加入构造这些类型的helper函数，
+    //否则这些api因为没有其他api能产生这些类型而无法调用
+    pub fn _add_ecosystem_constructors(&mut self) {
+        let mut ecosystem_inputs: Vec<(clean::Type, prelude_type::_PreludeHelper)> = Vec::new();
+        for api_function in &self.api_functions {
+            for input in &api_function.inputs {
+                let input = match input {
+                    clean::Type::BorrowedRef { type_, .. } => &**type_,
+                    _ => input,
+                };
+                let helper = match input.def_id().and_then(|did| self.ecosystem_types.get(&did)) {
+                    Some(helper) => helper,
+                    None => continue,
+                };
+                if !ecosystem_inputs.iter().any(|(ecosystem_type, _)| ecosystem_type == input) {
+                    ecosystem_inputs.push((input.clone(), helper.clone()));
+                }
+            }
+        }
+        println!("ecosystem types constructed by helper : {}", ecosystem_inputs.len());
+        for (ecosystem_type, helper) in ecosystem_inputs {
+            let (function_name, input_type) = match helper._ecosystem_constructor() {
+                Some(constructor) => constructor,
+                None => continue,
+            };
+            let api_function = ApiFunction {
+                full_name: function_name.to_string(),
+                generics: clean::Generics::default(),
+                inputs: vec![input_type],
+                output: Some(ecosystem_type),
+                _trait_full_path: None,
+                _impl_type_full_path: None,
+                _unsafe_tag: ApiUnsafety::Normal,
+                _helper: Some(helper),
+                _param_relations: Vec::new(),
+            };
+            self.add_api_function(api_function);
+        }
+    }
+
+    //api的参数是FUZZ_TARGET_PRODUCER_CRATES中的crate的类型时，加入这个类型在依赖crate中的构造函数。
+    //这些函数只用来产生参数，不算作被测crate的api
+    pub fn _add_producer_functions(&mut self) {
+        let mut producers = Vec::new();
+        for producer_function in &self.producer_functions.functions {
+            let produced_type = match self._consumed_type(&producer_function.produced_type) {
+                Some(produced_type) => produced_type,
+                None => continue,
+            };
+            if !producer_function
+                .inputs
+                .iter()
+                .all(|input| api_util::is_fuzzable_type(input, &self.full_name_map))
+            {
+                continue;
+            }
+            let output = match _with_produced_type(
+                &producer_function.output,
+                producer_function.produced_type,
+                &produced_type,
+            ) {
+                Some(output) => output,
+                None => continue,
+            };
+            producers.push((producer_function.clone(), output));
+        }
+        println!("producer functions from dependency crates : {}", producers.len());
+        for (producer_function, output) in producers {
+            self.api_producer_crates
+                .insert(producer_function.full_name.clone(), producer_function.crate_name.clone());
+            let api_function = ApiFunction {
+                full_name: producer_function.full_name,
+                generics: clean::Generics::default(),
+                inputs: producer_function.inputs,
+                output: Some(output),
+                _trait_full_path: None,
+                _impl_type_full_path: None,
+                _unsafe_tag: ApiUnsafety::Normal,
+                _helper: None,
+                _param_relations: Vec::new(),
+            };
+            self.add_api_function(api_function);
+        }
+    }
+
+    pub fn _is_producer_function(&self, full_name: &String) -> bool {
+        self.api_producer_crates.contains_key(full_name)
+    }
+
+    pub fn add_enum_variants(
+        &mut self,
+        enum_def_id: DefId,
+        enum_path: &String,
+        variants: Vec<api_util::EnumVariant>,
+    ) {
+        //pub use导出的enum在clean之后的模块树中会出现两次
+        if self.enum_variants.iter().any(|(def_id, ..)| *def_id == enum_def_id) {
+            return;
+        }
+        self.enum_variants.push((enum_def_id, enum_path.clone(), variants));
+    }
+
+    //enum的变体作为构造这个enum的api。字段全是fuzzable的变体合并成一个以enum为名的api，
+    //由一个输入字节选择构造哪个变体；其余带字段的变体各自作为一个api，字段由其他api产生
+    pub fn _add_variant_constructors(&mut self) {
+        let mut constructors = Vec::new();
+        for (enum_def_id, enum_path, variants) in &self.enum_variants {
+            //只为作为参数出现过的enum加入构造函数
+            let enum_type = match self._consumed_type(enum_def_id) {
+                Some(enum_type) => enum_type,
+                None => continue,
+            };
+            let (fuzzable_variants, mut other_variants): (Vec<_>, Vec<_>) = variants
+                .iter()
+                .map(|variant| self._string_fields_as_str(variant))
+                .partition(|variant| {
+                    variant.fields.iter().all(|(_, field_type)| {
+                        api_util::is_fuzzable_type(field_type, &self.full_name_map)
+                    })
+                });
+            if fuzzable_variants.len() > 1 {
+                let mut inputs = vec![clean::Type::Primitive(clean::PrimitiveType::U8)];
+                for variant in &fuzzable_variants {
+                    inputs.extend(variant.fields.iter().map(|(_, field_type)| field_type.clone()));
+                }
+                constructors.push((
+                    enum_path.clone(),
+                    inputs,
+                    enum_type.clone(),
+                    fuzzable_variants,
+                ));
+            } else {
+                other_variants.extend(fuzzable_variants);
+            }
+            for variant in other_variants {
+                let inputs =
+                    variant.fields.iter().map(|(_, field_type)| field_type.clone()).collect();
+                constructors.push((variant.path.clone(), inputs, enum_type.clone(), vec![variant]));
+            }
+        }
+        println!("enum variant constructors : {}", constructors.len());
+        for (full_name, inputs, enum_type, variants) in constructors {
+            self.full_name_map.push_variant_constructor(&full_name, variants);
+            let api_function = ApiFunction {
+                full_name,
+                generics: clean::Generics::default(),
+                inputs,
+                output: Some(enum_type),
+                _trait_full_path: None,
+                _impl_type_full_path: None,
+                _unsafe_tag: ApiUnsafety::Normal,
+                _helper: None,
+                _param_relations: Vec::new(),
+            };
+            self.add_api_function(api_function);
+        }
+    }
+
+    //String字段没有api可以产生，改为读入&str再转换
+    fn _string_fields_as_str(&self, variant: &api_util::EnumVariant) -> api_util::EnumVariant {
+        let mut variant = variant.clone();
+        for (i, (_, field_type)) in variant.fields.iter_mut().enumerate() {
+            if prelude_type::is_string(field_type, &self.full_name_map) {
+                *field_type = clean::Type::BorrowedRef {
+                    lifetime: None,
+                    mutability: rustc_hir::Mutability::Not,
+                    type_: Box::new(clean::Type::Primitive(clean::PrimitiveType::Str)),
+                };
+                variant.string_fields.push(i);
+            }
+        }
+        variant
+    }
+
+    //api参数中出现的、def id为给定值的类型(去掉引用)
+    fn _consumed_type(&self, def_id: &DefId) -> Option<clean::Type> {
+        for api_function in &self.api_functions {
+            for input in &api_function.inputs {
+                let input = match input {
+                    clean::Type::BorrowedRef { type_, .. } => &**type_,
+                    _ => input,
+                };
+                if input.def_id().as_ref() == Some(def_id) {
+                    return Some(input.clone());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn add_non_exhaustive_type(&mut self, type_name: &String) {
+        self.non_exhaustive_types.insert(type_name.clone());
+    }
+
+    pub fn add_metadata_error(&mut self, metadata_error: MetadataError) {
+        self.metadata_errors.push(metadata_error);
+    }
+
+    pub fn _checkpoint(&self) -> ApiGraphCheckpoint {
+        ApiGraphCheckpoint {
+            api_functions: self.api_functions.len(),
+            generic_functions: self.generic_functions.len(),
+            conversions: self.conversions.len(),
+            enum_variants: self.enum_variants.len(),
+            format_functions: self.format_functions.len(),
+            serde_functions: self.serde_functions.len(),
+            fuzz_entries: self.fuzz_entries.len(),
+            implemented_traits: self.implemented_traits.clone(),
+            deref_targets: self.deref_targets.clone(),
+            deref_mut_types: self.deref_mut_types.clone(),
+            non_exhaustive_types: self.non_exhaustive_types.clone(),
+            annotated_constructors: self.annotated_constructors.clone(),
+            zero_sized_types: self.full_name_map.zero_sized_types.clone(),
+            literal_structs: self.full_name_map.literal_structs.clone(),
+        }
+    }
+
+    //去掉checkpoint之后加入的api，以及按api全名记录的属性，按类型记录的集合恢复成checkpoint时的内容。
+    //checkpoint之前已经有同名api(如pub use导出两次)的话，保留它的属性
+    pub fn _rollback(&mut self, checkpoint: ApiGraphCheckpoint) {
+        let kept_names: HashSet<&String> = self.api_functions[..checkpoint.api_functions]
+            .iter()
+            .chain(
+                self.generic_functions[..checkpoint.generic_functions]
+                    .iter()
+                    .map(|generic_function| &generic_function.api_function),
+            )
+            .map(|api_function| &api_function.full_name)
+            .collect();
+        let removed_names: HashSet<String> = self.api_functions[checkpoint.api_functions..]
+            .iter()
+            .chain(
+                self.generic_functions[checkpoint.generic_functions..]
+                    .iter()
+                    .map(|generic_function| &generic_function.api_function),
+            )
+            .map(|api_function| &api_function.full_name)
+            .filter(|full_name| !kept_names.contains(full_name))
+            .cloned()
+            .collect();
+        self.api_functions.truncate(checkpoint.api_functions);
+        self.generic_functions.truncate(checkpoint.generic_functions);
+        self.conversions.truncate(checkpoint.conversions);
+        self.enum_variants.truncate(checkpoint.enum_variants);
+        self.format_functions.truncate(checkpoint.format_functions);
+        self.serde_functions.truncate(checkpoint.serde_functions);
+        self.fuzz_entries.truncate(checkpoint.fuzz_entries);
+        self.implemented_traits = checkpoint.implemented_traits;
+        self.deref_targets = checkpoint.deref_targets;
+        self.deref_mut_types = checkpoint.deref_mut_types;
+        self.non_exhaustive_types = checkpoint.non_exhaustive_types;
+        self.annotated_constructors = checkpoint.annotated_constructors;
+        self.full_name_map.zero_sized_types = checkpoint.zero_sized_types;
+        self.full_name_map.literal_structs = checkpoint.literal_structs;
+        for full_name in &removed_names {
+            self.async_functions.remove(full_name);
+            self.cfg_gated_functions.remove(full_name);
+            self.feature_gates.remove(full_name);
+            self.feature_disabled.remove(full_name);
+            self.skipped_functions.remove(full_name);
+            self.api_lines.remove(full_name);
+            self.api_unsafe_sites.remove(full_name);
+            self.api_ffi_callees.remove(full_name);
+            self.api_generic_instances.remove(full_name);
+        }
+    }
+
+    //兼容性报告：按类型统计跳过的item，而不是在第一个错误处中止
+    pub fn _report_metadata_errors(&self) {
+        if self.metadata_errors.is_empty() {
+            return;
+        }
+        let mut errors_by_kind: BTreeMap<&'static str, Vec<&MetadataError>> = BTreeMap::new();
+        for metadata_error in &self.metadata_errors {
+            errors_by_kind.entry(metadata_error.kind()).or_default().push(metadata_error);
+        }
+        println!("compatibility report: {} items skipped", self.metadata_errors.len());
+        for (kind, metadata_errors) in &errors_by_kind {
+            println!("{}: {}", kind, metadata_errors.len());
+            for metadata_error in metadata_errors {
+                println!("    {}", metadata_error);
+            }
+        }
+    }
+
+    //#[non_exhaustive]的类型只能由api返回(包括Default::default)，没有任何api返回的话，
+    //以它为参数的api都无法被调用
+    pub fn _report_non_exhaustive_types(&self) {
+        let mut produced_types = HashSet::new();
+        for api_function in &self.api_functions {
+            if let Some(output) = &api_function.output {
+                let prelude_type =
+                    prelude_type::PreludeType::from_type(output, &self.full_name_map);
+                let final_type = prelude_type._get_final_type();
+                produced_types.insert(api_util::_type_name(&final_type, &self.full_name_map));
+            }
+        }
+        let mut unconstructible_types: Vec<&String> = self
+            .non_exhaustive_types
+            .iter()
+            .filter(|type_name| !produced_types.contains(*type_name))
+            .collect();
+        unconstructible_types.sort();
+        for type_name in unconstructible_types {
+            println!(
+                "{} is #[non_exhaustive] and no api returns it, it can not be constructed",
+                type_name
+            );
+        }
+    }
+
+    pub fn add_function_annotations(&mut self, full_name: &String, annotations: &[RulfAnnotation]) {
+        for annotation in annotations {
+            match annotation {
+                RulfAnnotation::FuzzEntry => {
+                    if !self.fuzz_entries.contains(full_name) {
+                        self.fuzz_entries.push(full_name.clone());
+                    }
+                }
+                RulfAnnotation::Skip => {
+                    self.skipped_functions.insert(full_name.clone());
+                }
+                RulfAnnotation::ConstructWith(..) => {
+                    println!("rulf::construct_with on function {} is ignored", full_name);
+                }
+            }
+        }
+    }
+
+    //没有设置FUZZ_TARGET_UNSAFE_REPORT时unsafe_reachability为空，不记录
+    pub fn add_unsafe_sites(&mut self, full_name: &String, def_id: DefId) {
+        if let Some(sites) = self.unsafe_reachability.reachable_sites.get(&def_id) {
+            self.api_unsafe_sites.insert(full_name.clone(), sites.clone());
+        }
+    }
+
+    //没有设置FUZZ_TARGET_FFI_APIS时ffi_classification为空，不记录
+    pub fn add_ffi_callees(&mut self, full_name: &String, def_id: DefId) {
+        if let Some(callees) = self.ffi_classification.foreign_callees.get(&def_id) {
+            self.api_ffi_callees.insert(full_name.clone(), callees.clone());
+        }
+    }
+
+    pub fn add_api_lines(&mut self, full_name: &String, source: &clean::Span) {
+        if source.hiline >= source.loline && source.loline > 0 {
+            self.api_lines.insert(full_name.clone(), source.hiline - source.loline + 1);
+        }
+    }
+
+    //api的复杂度：参数越多、用到unsafe越多、代码越长，weighted random walk越倾向于走到这个api
+    fn _api_complexity(&self, api_function: &ApiFunction) -> usize {
+        let full_name = _name_without_turbofish(&api_function.full_name);
+        let mut complexity = 1 + PARAM_WEIGHT * api_function.inputs.len();
+        if let ApiUnsafety::Unsafe = api_function._unsafe_tag {
+            complexity += UNSAFE_FN_WEIGHT;
+        }
+        if let Some(sites) = self.api_unsafe_sites.get(&full_name) {
+            complexity += UNSAFE_SITE_WEIGHT * sites.len();
+        }
+        if let Some(lines) = self.api_lines.get(&full_name) {
+            complexity += lines / LINES_PER_WEIGHT;
+        }
+        complexity
+    }
+
+    //实例化之后的泛型api按实例化之前的名字判断
+    pub fn _ffi_callees_of(&self, full_name: &String) -> Option<&BTreeSet<String>> {
+        if self.api_ffi_callees.is_empty() {
+            return None;
+        }
+        self.api_ffi_callees.get(&_name_without_turbofish(full_name))
+    }
+
+    //derived impl检查中用来加深嵌套的api：唯一的输入是这个类型自己或Box<这个类型>，输出这个类型。
+    //返回以value为参数调用它的代码
+    pub fn _nest_call_string(&self, def_id: &DefId, value: &str) -> Option<String> {
+        let is_self_type = |type_: &clean::Type| type_.def_id().as_ref() == Some(def_id);
+        self.api_functions.iter().find_map(|api_function| {
+            if api_function._helper.is_some()
+                || api_function._unsafe_tag != ApiUnsafety::Normal
+                || api_function._trait_full_path.is_some()
+                || api_function._is_generic_function()
+                || !api_function.output.as_ref().map_or(false, is_self_type)
+            {
+                return None;
+            }
+            let param_string = match api_function.inputs.as_slice() {
+                [input] if is_self_type(input) => value.to_string(),
+                [input] if _boxed_type(input).map_or(false, is_self_type) => {
+                    format!("Box::new({})", value)
+                }
+                _ => return None,
+            };
+            let param_strings = vec![param_string];
+            Some(
+                self.full_name_map
+                    ._variant_call_string(&api_function.full_name, &param_strings)
+                    .unwrap_or_else(|| format!("{}({})", api_function.full_name, param_strings[0])),
+            )
+        })
+    }
+
+    pub fn add_generic_instances(&mut self, full_name: &String, def_id: DefId) {
+        if let Some(instances) = self.generic_instances.instances.get(&def_id) {
+            self.api_generic_instances.insert(full_name.clone(), instances.clone());
+        }
+    }
+
+    //只替换含有关联类型的参数和返回值，其他位置保留clean得到的类型，以便和其他api的类型比较
+    pub fn normalize_projections(
+        &self,
+        def_id: DefId,
+        inputs: &mut Vec<clean::Type>,
+        output: &mut Option<clean::Type>,
+    ) {
+        let (normalized_inputs, normalized_output) =
+            match self.normalized_signatures.signatures.get(&def_id) {
+                Some(signature) => signature,
+                None => return,
+            };
+        for (input, normalized_input) in inputs.iter_mut().zip(normalized_inputs) {
+            if let Some(normalized_input) = normalized_input {
+                if api_util::_contains_qpath(input) {
+                    *input = normalized_input.clone();
+                }
+            }
+        }
+        if let (Some(output), Some(normalized_output)) = (output.as_mut(), normalized_output) {
+            if api_util::_contains_qpath(output) {
+                *output = normalized_output.clone();
+            }
+        }
+    }
+
+    pub fn add_type_annotations(
+        &mut self,
+        def_id: DefId,
+        type_name: &String,
+        annotations: &[RulfAnnotation],
+    ) {
+        for annotation in annotations {
+            match annotation {
+                RulfAnnotation::ConstructWith(constructor) => {
+                    self.annotated_constructors.insert(def_id, constructor.clone());
+                }
+                _ => println!("{:?} on type {} is ignored", annotation, type_name),
+            }
+        }
+    }
+
+    pub fn add_deref_target(&mut self, def_id: DefId, target_type: clean::Type) {
+        self.deref_targets.insert(def_id, target_type);
+    }
+
+    pub fn add_deref_mut_type(&mut self, def_id: DefId) {
+        self.deref_mut_types.insert(def_id);
+    }
+
+    //输出类型沿着Deref链解引用之后和参数引用的类型相同时，用&*或者&mut *传入。
+    //可变引用要求链上的每个类型都实现了DerefMut
+    fn _deref_call_type(&self, output_type: &clean::Type, input_type: &clean::Type) -> CallType {
+        let (mutability, referenced_type) = match input_type {
+            clean::Type::BorrowedRef { mutability, type_, .. } => (*mutability, &**type_),
+            _ => return CallType::_NotCompatible,
+        };
+        let mut current_type = output_type;
+        let mut deref_call_type = CallType::_DirectCall;
+        let mut deref_mut = true;
+        for _ in 0..MAX_DEREF_DEPTH {
+            let def_id = match current_type {
+                clean::Type::ResolvedPath { did, .. } => *did,
+                _ => return CallType::_NotCompatible,
+            };
+            let target_type = match self.deref_targets.get(&def_id) {
+                Some(target_type) => target_type,
+                None => return CallType::_NotCompatible,
+            };
+            deref_mut = deref_mut && self.deref_mut_types.contains(&def_id);
+            deref_call_type = CallType::_Deref(Box::new(deref_call_type));
+            let same_type =
+                api_util::_same_type(target_type, referenced_type, true, &self.full_name_map);
+            if same_type == CallType::_DirectCall {
+                return match mutability {
+                    rustc_hir::Mutability::Not => CallType::_BorrowedRef(Box::new(deref_call_type)),
+                    rustc_hir::Mutability::Mut if deref_mut => {
+                        CallType::_MutBorrowedRef(Box::new(deref_call_type))
+                    }
+                    rustc_hir::Mutability::Mut => CallType::_NotCompatible,
+                };
+            }
+            current_type = target_type;
+        }
+        CallType::_NotCompatible
+    }
+
+    pub fn add_conversion(&mut self, from_type: clean::Type, to_type: clean::Type, fallible: bool) {
+        self.conversions.push((from_type, to_type, fallible));
+    }
+
+    //输出类型可以通过crate中的From/TryFrom impl转换为参数需要的类型时，插入into或者try_into。
+    //try_into的结果和其他Result一样unwrap
+    fn _conversion_call_type(
+        &self,
+        output_type: &clean::Type,
+        input_type: &clean::Type,
+    ) -> CallType {
+        for (from_type, to_type, fallible) in &self.conversions {
+            if api_util::_same_type(output_type, from_type, true, &self.full_name_map)
+                != CallType::_DirectCall
+            {
+                continue;
+            }
+            let to_input_call_type =
+                api_util::_same_type(to_type, input_type, true, &self.full_name_map);
+            if to_input_call_type == CallType::_NotCompatible
+                || to_input_call_type._contains_move_call_type()
+            {
+                continue;
+            }
+            let conversion_call_type = if *fallible {
+                CallType::_UnwrapResult(Box::new(CallType::_TryInto(
+                    Box::new(CallType::_DirectCall),
+                    to_type.clone(),
+                )))
+            } else {
+                CallType::_Into(Box::new(CallType::_DirectCall), to_type.clone())
+            };
+            return to_input_call_type._with_inner_call_type(conversion_call_type);
+        }
+        CallType::_NotCompatible
+    }
+
+    pub fn add_cfg_gated_function(&mut self, full_name: &String) {
+        self.cfg_gated_functions.insert(full_name.clone());
+    }
+
+    pub fn add_async_function(&mut self, full_name: &String) {
+        self.async_functions.insert(full_name.clone());
+    }
+
+    //实例化之后的泛型api(如send::<u8>、Pool::<u8>::get)按实例化之前的名字判断
+    pub fn _is_async_function(&self, full_name: &String) -> bool {
+        if self.async_functions.is_empty() {
+            return false;
+        }
+        self.async_functions.contains(&_name_without_turbofish(full_name))
+    }
+
+    pub fn add_cfg_gated_mod(&mut self, mod_name: &String) {
+        self.cfg_gated_mods.insert(mod_name.clone());
+    }
+
+    pub fn _is_cfg_gated_function(&self, full_name: &String) -> bool {
+        if self.cfg_gated_functions.contains(full_name) {
+            return true;
+        }
+        self.cfg_gated_mods
+            .iter()
+            .any(|mod_name| full_name.starts_with(format!("{}::", mod_name).as_str()))
+    }
+
+    //name是api的全名或者mod的路径
+    pub fn add_feature_gates(&mut self, name: &String, attrs: &clean::Attributes) {
+        let features = api_util::_required_features(attrs);
+        if !features.is_empty() {
+            self.feature_gates.insert(name.clone(), features);
+        }
+        if !api_util::_is_enabled_by_features(attrs) {
+            self.feature_disabled.insert(name.clone());
+        }
+    }
+
+    //api自己和所在的mod需要的feature，实例化之后的泛型api(如largest::<u8>)也按前缀匹配
+    pub fn _feature_gates_of(&self, full_name: &String) -> BTreeSet<String> {
+        let mut res = BTreeSet::new();
+        for (name, features) in &self.feature_gates {
+            if full_name == name || full_name.starts_with(format!("{}::", name).as_str()) {
+                res.extend(features.iter().cloned());
+            }
+        }
+        res
+    }
+
+    pub fn _is_feature_disabled(&self, full_name: &String) -> bool {
+        self.feature_disabled
+            .iter()
+            .any(|name| full_name == name || full_name.starts_with(format!("{}::", name).as_str()))
+    }
+
     pub fn filter_functions(&mut self) {
         self.filter_functions_defined_on_prelude_type();
         self.filter_api_functions_by_mod_visibility();
+        self.filter_skipped_functions();
+        self.filter_feature_disabled_functions();
+        self.filter_unsafe_functions();
+        self.filter_ffi_functions();
+    }
+
+    pub fn filter_ffi_functions(&mut self) {
+        if ffi_util::ffi_policy() != FfiPolicy::Exclude {
+            return;
+        }
+        let old_function_num = self.api_functions.len();
+        let mut api_functions = std::mem::take(&mut self.api_functions);
+        api_functions
+            .retain(|api_function| self._ffi_callees_of(&api_function.full_name).is_none());
+        self.api_functions = api_functions;
+        println!(
+            "skipped {} functions forwarding to foreign functions",
+            old_function_num - self.api_functions.len()
+        );
+    }
+
+    pub fn filter_unsafe_functions(&mut self) {
+        if !api_util::skip_unsafe() {
+            return;
+        }
+        let old_function_num = self.api_functions.len();
+        self.api_functions.retain(|api_function| !api_function._unsafe_tag._is_unsafe());
+        let skipped_num = old_function_num - self.api_functions.len();
+        if skipped_num > 0 {
+            println!(
+                "skipped {} unsafe functions because FUZZ_TARGET_NO_UNSAFE is set",
+                skipped_num
+            );
+        }
+    }
+
+    //只因为cfg(doc)而出现的api，在选中的feature下调用它们的target无法编译
+    pub fn filter_feature_disabled_functions(&mut self) {
+        if self.feature_disabled.is_empty() {
+            return;
+        }
+        let old_function_num = self.api_functions.len();
+        let mut api_functions = std::mem::take(&mut self.api_functions);
+        api_functions.retain(|api_function| !self._is_feature_disabled(&api_function.full_name));
+        self.api_functions = api_functions;
+        println!(
+            "skipped {} functions not enabled by the selected features",
+            old_function_num - self.api_functions.len()
+        );
+    }
+
+    pub fn filter_skipped_functions(&mut self) {
+        if self.skipped_functions.is_empty() {
+            return;
+        }
+        let old_function_num = self.api_functions.len();
+        let skipped_functions = &self.skipped_functions;
+        self.api_functions
+            .retain(|api_function| !skipped_functions.contains(&api_function.full_name));
+        println!(
+            "skipped {} functions marked with rulf::skip",
+            old_function_num - self.api_functions.len()
+        );
     }
 
     /// functions of prelude type. These functions are not in current crate
@@ -136,18 +1190,21 @@ impl ApiGraph {
             return;
         }
 
+        //按完整的路径段比较，私有的mod parse不应该挡住通过pub use导出到上一层的parse_all
+        let invisible_prefixes: Vec<String> =
+            invisible_mods.iter().map(|invisible_mod| format!("{}::", invisible_mod)).collect();
         let mut new_api_functions = Vec::new();
         for api_func in &self.api_functions {
             let api_func_name = &api_func.full_name;
             let trait_name = &api_func._trait_full_path;
             let mut invisible_flag = false;
-            for invisible_mod in &invisible_mods {
-                if api_func_name.as_str().starts_with(invisible_mod.as_str()) {
+            for invisible_prefix in &invisible_prefixes {
+                if api_func_name.as_str().starts_with(invisible_prefix.as_str()) {
                     invisible_flag = true;
                     break;
                 }
                 if let Some(trait_name_) = trait_name {
-                    if trait_name_.as_str().starts_with(invisible_mod) {
+                    if trait_name_.as_str().starts_with(invisible_prefix.as_str()) {
                         invisible_flag = true;
                         break;
                     }
@@ -188,12 +1245,38 @@ impl ApiGraph {
                     let input_params_num = input_params.len();
                     for k in 0..input_params_num {
                         let input_param = &input_params[k];
-                        let call_type = api_util::_same_type(
+                        let call_type = match api_util::_same_type(
                             output_type,
                             input_param,
                             true,
                             &self.full_name_map,
-                        );
+                        ) {
+                            //_same_type不比较impl Trait，返回impl Stream/impl Iterator的api
+                            //直接连到取出同一种类型的item的helper api上
+                            CallType::_NotCompatible
+                                if _is_item_consumer(second_fun) && output_type == input_param =>
+                            {
+                                CallType::_DirectCall
+                            }
+                            CallType::_NotCompatible => {
+                                match self._deref_call_type(output_type, input_param) {
+                                    CallType::_NotCompatible => {
+                                        self._conversion_call_type(output_type, input_param)
+                                    }
+                                    call_type => call_type,
+                                }
+                            }
+                            call_type => call_type,
+                        };
+                        //其他api返回的裸指针可能是悬垂指针或空指针，不解引用，也不传给unsafe函数，
+                        //只原样传给接受同一种裸指针的安全函数
+                        if let clean::Type::RawPointer(..) = output_type {
+                            if call_type != CallType::_DirectCall
+                                || second_fun._unsafe_tag._is_unsafe()
+                            {
+                                continue;
+                            }
+                        }
                         match &call_type {
                             CallType::_NotCompatible => {
                                 continue;
@@ -214,6 +1297,95 @@ impl ApiGraph {
         }
     }
 
+    //#[rulf::construct_with(path)]标注的类型只由path提供：去掉其它函数提供这个类型的依赖。
+    //本身以这个类型为输入的函数(例如builder的方法)仍然可以提供
+    pub fn _apply_annotated_constructors(&mut self) {
+        if self.annotated_constructors.is_empty() {
+            return;
+        }
+        let api_functions = &self.api_functions;
+        let annotated_constructors = &self.annotated_constructors;
+        let is_constructor = |api_function: &ApiFunction, constructor: &String| {
+            api_function.full_name == *constructor
+                || api_function.full_name.ends_with(format!("::{}", constructor).as_str())
+        };
+        for constructor in annotated_constructors.values() {
+            if !api_functions.iter().any(|api_function| is_constructor(api_function, constructor)) {
+                println!("constructor {} from rulf::construct_with is not found", constructor);
+            }
+        }
+        let annotated_type = |ty_: &clean::Type| {
+            api_function::_strip_reference(ty_)
+                .def_id()
+                .filter(|def_id| annotated_constructors.contains_key(def_id))
+        };
+        let old_dependency_num = self.api_dependencies.len();
+        self.api_dependencies.retain(|dependency| {
+            let (_, output_index) = dependency.output_fun;
+            let (_, input_index) = dependency.input_fun;
+            let input_type = &api_functions[input_index].inputs[dependency.input_param_index];
+            let def_id = match annotated_type(input_type) {
+                Some(def_id) => def_id,
+                None => return true,
+            };
+            let output_function = &api_functions[output_index];
+            is_constructor(output_function, &annotated_constructors[&def_id])
+                || output_function.inputs.iter().any(|input| annotated_type(input) == Some(def_id))
+        });
+        println!(
+            "removed {} dependencies bypassing annotated constructors",
+            old_dependency_num - self.api_dependencies.len()
+        );
+    }
+
+    //像as_str -> to_string -> as_str这样的转换函数之间的环会让搜索空间膨胀
+    //对于A->B和B->A的两个转换函数，两个方向的边都存在时去掉其中一条，只保留一个方向
+    pub fn prune_converter_cycles(&mut self) {
+        let api_num = self.api_functions.len();
+        let mut converters = HashMap::new();
+        for i in 0..api_num {
+            if let Some(converter) = self.api_functions[i]._converter_types(&self.full_name_map) {
+                converters.insert(i, converter);
+            }
+        }
+        if converters.len() <= 0 {
+            return;
+        }
+
+        let converter_edges: HashSet<(usize, usize)> = self
+            .api_dependencies
+            .iter()
+            .map(|dependency| (dependency.output_fun.1, dependency.input_fun.1))
+            .filter(|(output_index, input_index)| {
+                converters.contains_key(output_index) && converters.contains_key(input_index)
+            })
+            .collect();
+        let old_dependency_num = self.api_dependencies.len();
+        let api_dependencies = std::mem::take(&mut self.api_dependencies);
+        self.api_dependencies = api_dependencies
+            .into_iter()
+            .filter(|dependency| {
+                let (_, output_index) = dependency.output_fun;
+                let (_, input_index) = dependency.input_fun;
+                match (converters.get(&output_index), converters.get(&input_index)) {
+                    (Some((output_from, output_to)), Some((input_from, input_to))) => {
+                        //round trip: A->B后面接B->A,反方向的边也存在时
+                        //只保留从字典序较小的类型出发的那条
+                        !(output_to == input_from
+                            && output_from == input_to
+                            && output_from > output_to
+                            && converter_edges.contains(&(input_index, output_index)))
+                    }
+                    _ => true,
+                }
+            })
+            .collect();
+        let pruned_num = old_dependency_num - self.api_dependencies.len();
+        if pruned_num > 0 {
+            println!("pruned {} dependencies between converter functions", pruned_num);
+        }
+    }
+
     pub fn default_generate_sequences(&mut self) {
         //BFS + backward search
         self.generate_all_possoble_sequences(GraphTraverseAlgorithm::_BfsEndPoint);
@@ -223,6 +1395,272 @@ impl ApiGraph {
         //self.generate_all_possoble_sequences(GraphTraverseAlgorithm::_DirectBackwardSearch);
     }
 
+    //把以格式化函数结尾的最短序列合并成一个format sweep序列，其余以格式化函数结尾的序列不再单独生成target
+    pub fn _build_format_sweep(&mut self) {
+        let format_function_indexes: HashSet<usize> = self
+            .api_functions
+            .iter()
+            .enumerate()
+            .filter(|(_, api_function)| match api_function._helper {
+                Some(prelude_type::_PreludeHelper::_FormatDebugHelper)
+                | Some(prelude_type::_PreludeHelper::_FormatDisplayHelper) => true,
+                _ => false,
+            })
+            .map(|(index, _)| index)
+            .collect();
+        //格式化函数 -> 以它结尾的最短序列
+        let mut sweep_parts: BTreeMap<usize, usize> = BTreeMap::new();
+        for (sequence_index, sequence) in self.api_sequences.iter().enumerate() {
+            let last_index = match sequence._last_api_func_index() {
+                Some(last_index) if format_function_indexes.contains(&last_index) => last_index,
+                _ => continue,
+            };
+            let is_shorter = match sweep_parts.get(&last_index) {
+                Some(shortest_index) => sequence.len() < self.api_sequences[*shortest_index].len(),
+                None => true,
+            };
+            if is_shorter {
+                sweep_parts.insert(last_index, sequence_index);
+            }
+        }
+        println!(
+            "format sweep: {} of {} formatting functions reachable",
+            sweep_parts.len(),
+            format_function_indexes.len()
+        );
+        if !sweep_parts.is_empty() {
+            let sweep_parts: Vec<ApiSequence> =
+                sweep_parts.values().map(|index| self.api_sequences[*index].clone()).collect();
+            self.format_sweep_sequence = Some(ApiSequence::_merge_sequences(&sweep_parts));
+        }
+        self.api_sequences.retain(|sequence| match sequence._last_api_func_index() {
+            Some(last_index) => !format_function_indexes.contains(&last_index),
+            None => true,
+        });
+    }
+
+    //按照(trait, type)对impl块中的函数进行分组
+    pub fn _trait_impl_groups(&self) -> BTreeMap<(String, String), Vec<usize>> {
+        let mut res: BTreeMap<(String, String), Vec<usize>> = BTreeMap::new();
+        for (index, api_function) in self.api_functions.iter().enumerate() {
+            if let (Some(trait_name), Some(type_name)) =
+                (&api_function._trait_full_path, &api_function._impl_type_full_path)
+            {
+                res.entry((trait_name.clone(), type_name.clone())).or_default().push(index);
+            }
+        }
+        res
+    }
+
+    //focus_apis中每一项是api的全限定名或者mod/类型的路径前缀，
+    //为以匹配的api结尾的序列每项额外选择最多FOCUS_SEQUENCES_PER_API个，短的序列优先
+    pub fn _build_focus_sequences(&mut self, focus_apis: &Vec<String>) {
+        for focus_api in focus_apis {
+            let mut candidates: Vec<&ApiSequence> = self
+                .api_sequences
+                .iter()
+                .filter(|sequence| match sequence._last_api_func_index() {
+                    Some(last_index) => {
+                        self.api_functions[last_index].full_name.starts_with(focus_api.as_str())
+                    }
+                    None => false,
+                })
+                .collect();
+            candidates.sort_by_key(|sequence| sequence.len());
+            let mut chosen = 0;
+            for candidate in candidates {
+                if chosen >= FOCUS_SEQUENCES_PER_API {
+                    break;
+                }
+                if !self.focus_sequences.contains(candidate) {
+                    self.focus_sequences.push(candidate.clone());
+                    chosen = chosen + 1;
+                }
+            }
+            println!("focus on {}: {} sequences", focus_api, chosen);
+        }
+    }
+
+    //保证每个(trait, type)至少有一个序列调用了impl块中的所有函数，并打印trait coverage矩阵
+    pub fn _cover_trait_impls(&mut self) {
+        let trait_impl_groups = self._trait_impl_groups();
+        let mut covered_pairs = 0;
+        println!("trait coverage:");
+        for ((trait_name, type_name), functions) in &trait_impl_groups {
+            let already_covered = self.api_sequences.iter().any(|sequence| {
+                functions.iter().all(|function| sequence._contains_api_function(*function))
+            });
+            let covered = if already_covered {
+                true
+            } else if let Some(sequence) = self._sequence_calling_all(functions) {
+                self.trait_coverage_sequences.push(sequence);
+                true
+            } else {
+                false
+            };
+            if covered {
+                covered_pairs = covered_pairs + 1;
+            }
+            println!(
+                "    {} for {} ({} methods): {}",
+                trait_name,
+                type_name,
+                functions.len(),
+                if covered { "covered" } else { "not covered" }
+            );
+        }
+        println!("trait impls covered = {}/{}", covered_pairs, trait_impl_groups.len());
+    }
+
+    //依次把函数加入序列，参数无法由序列中已有的返回值满足时，先合并一个以该函数结尾的最短序列
+    fn _sequence_calling_all(&self, functions: &Vec<usize>) -> Option<ApiSequence> {
+        let input_type = ApiType::BareFunction;
+        let mut sequence = ApiSequence::new();
+        for function in functions {
+            if let Some(new_sequence) = self.is_fun_satisfied(&input_type, *function, &sequence) {
+                sequence = new_sequence;
+                continue;
+            }
+            let shortest_ending_sequence = self
+                .api_sequences
+                .iter()
+                .filter(|candidate| candidate._last_api_func_index() == Some(*function))
+                .min_by_key(|candidate| candidate.len())?;
+            sequence = sequence._merge_another_sequence(shortest_ending_sequence);
+            if sequence._contains_multi_dynamic_length_fuzzable() {
+                return None;
+            }
+        }
+        Some(sequence)
+    }
+
+    //对产生同一组&mut self方法的self值的函数，取以它结尾的最短序列，
+    //在后面加入由输入字节选择、反复调用这些方法的循环
+    pub fn _build_stateful_sequences(&mut self) {
+        let stateful_methods: Vec<usize> = (0..self.api_functions.len())
+            .filter(|index| self._is_stateful_method(*index))
+            .collect();
+        //方法 -> 以可以作为它们self的返回值结尾的最短序列
+        let mut shortest_sequences: BTreeMap<Vec<usize>, usize> = BTreeMap::new();
+        for (sequence_index, sequence) in self.api_sequences.iter().enumerate() {
+            let last_index = match sequence._last_api_func_index() {
+                Some(last_index) => last_index,
+                None => continue,
+            };
+            let mutable_self = CallType::_MutBorrowedRef(Box::new(CallType::_DirectCall));
+            let methods: Vec<usize> = stateful_methods
+                .iter()
+                .cloned()
+                .filter(|method| {
+                    self.check_dependency(
+                        &ApiType::BareFunction,
+                        last_index,
+                        &ApiType::BareFunction,
+                        *method,
+                        0,
+                    )
+                    .map_or(false, |dependency_index| {
+                        self.api_dependencies[dependency_index].call_type == mutable_self
+                    })
+                })
+                .take(MAX_STATEFUL_METHODS)
+                .collect();
+            if methods.is_empty() {
+                continue;
+            }
+            let is_shorter = match shortest_sequences.get(&methods) {
+                Some(shortest_index) => sequence.len() < self.api_sequences[*shortest_index].len(),
+                None => true,
+            };
+            if is_shorter {
+                shortest_sequences.insert(methods, sequence_index);
+            }
+        }
+        for (methods, sequence_index) in &shortest_sequences {
+            let sequence = &self.api_sequences[*sequence_index];
+            if let Some(stateful_sequence) = self._stateful_sequence(sequence, methods) {
+                self.stateful_sequences.push(stateful_sequence);
+            }
+        }
+        println!("stateful sequences = {}", self.stateful_sequences.len());
+    }
+
+    //第一个参数是&mut self，其余参数都是可以在循环中反复使用的fuzzable参数
+    fn _is_stateful_method(&self, index: usize) -> bool {
+        let api_function = &self.api_functions[index];
+        match api_function.inputs.first() {
+            Some(clean::Type::BorrowedRef {
+                mutability: rustc_hir::Mutability::Mut,
+                type_,
+                ..
+            }) if !api_util::is_fuzzable_type(type_, &self.full_name_map) => {}
+            _ => return false,
+        }
+        if self._is_async_function(&api_function.full_name) {
+            return false;
+        }
+        api_function.inputs.iter().skip(1).all(|input_type| {
+            if !api_util::is_fuzzable_type(input_type, &self.full_name_map) {
+                return false;
+            }
+            let (fuzzable_type, call_type) =
+                fuzzable_type::fuzzable_call_type(input_type, &self.full_name_map)
+                    .generate_fuzzable_type_and_call_type();
+            match fuzzable_type {
+                //zero sized的值和struct字面量不一定是Copy的，不能在循环中反复使用
+                FuzzableType::NoFuzzable
+                | FuzzableType::ZeroSized { .. }
+                | FuzzableType::Struct { .. } => false,
+                _ => !call_type._contains_unwrap_call_type(),
+            }
+        })
+    }
+
+    fn _stateful_sequence(
+        &self,
+        sequence: &ApiSequence,
+        methods: &Vec<usize>,
+    ) -> Option<ApiSequence> {
+        let mut new_sequence = sequence.clone();
+        let target_index = new_sequence.len() - 1;
+        let mut stateful_calls = Vec::new();
+        for method in methods {
+            let input_function = &self.api_functions[*method];
+            let mut api_call = ApiCall::_new(*method);
+            api_call._add_param(
+                ParamType::_FunctionReturn,
+                target_index,
+                CallType::_MutBorrowedRef(Box::new(CallType::_DirectCall)),
+            );
+            for input_type in input_function.inputs.iter().skip(1) {
+                let current_fuzzable_index = new_sequence.fuzzable_params.len();
+                let (fuzzable_type, call_type) =
+                    fuzzable_type::fuzzable_call_type(input_type, &self.full_name_map)
+                        .generate_fuzzable_type_and_call_type();
+                if api_util::_need_mut_tag(&call_type) {
+                    new_sequence._insert_fuzzable_mut_tag(current_fuzzable_index);
+                }
+                new_sequence.fuzzable_params.push(fuzzable_type);
+                api_call._add_param(ParamType::_FuzzableType, current_fuzzable_index, call_type);
+            }
+            if input_function._unsafe_tag._is_unsafe() {
+                new_sequence.set_unsafe();
+            }
+            if let Some(ref trait_full_path) = input_function._trait_full_path {
+                new_sequence.add_trait(trait_full_path);
+            }
+            if let Some(ref helper) = input_function._helper {
+                new_sequence.add_helper(helper);
+            }
+            stateful_calls.push(api_call);
+        }
+        new_sequence._set_stateful_calls(target_index, stateful_calls);
+        if new_sequence._contains_multi_dynamic_length_fuzzable() {
+            return None;
+        }
+        Some(new_sequence)
+    }
+
     pub fn generate_all_possoble_sequences(&mut self, algorithm: GraphTraverseAlgorithm) {
         //BFS序列的最大长度：即为函数的数量,或者自定义
         //let bfs_max_len = self.api_functions.len();
@@ -268,6 +1706,10 @@ impl ApiGraph {
                 println!("using random walk end point");
                 self.random_walk(random_walk_max_size, true, random_walk_max_depth);
             }
+            GraphTraverseAlgorithm::_WeightedRandomWalk => {
+                println!("using weighted random walk");
+                self.weighted_random_walk(random_walk_max_size, random_walk_max_depth);
+            }
 
             GraphTraverseAlgorithm::_DirectBackwardSearch => {
                 println!("using backward search");
@@ -495,6 +1937,72 @@ impl ApiGraph {
         }
     }
 
+    //和random_walk相同，但下一个api沿着依赖边选择，边的权重是后继api的复杂度。
+    //空序列或者最后一个api没有后继时，按复杂度在所有api中选择
+    pub fn weighted_random_walk(&mut self, max_size: usize, max_depth: usize) {
+        self.api_sequences.clear();
+        self.reset_visited();
+
+        if self.api_functions.len() <= 0 {
+            return;
+        }
+
+        let complexities: Vec<usize> = self
+            .api_functions
+            .iter()
+            .map(|api_function| self._api_complexity(api_function))
+            .collect();
+        let all_functions: Vec<usize> = (0..self.api_functions.len()).collect();
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); self.api_functions.len()];
+        for api_dependency in &self.api_dependencies {
+            let (_, output_index) = api_dependency.output_fun;
+            let (_, input_index) = api_dependency.input_fun;
+            if !successors[output_index].contains(&input_index) {
+                successors[output_index].push(input_index);
+            }
+        }
+
+        let api_sequence = ApiSequence::new();
+        self.api_sequences.push(api_sequence);
+
+        let mut rng = rand::thread_rng();
+        let mut all_visited = false;
+        for i in 0..max_size {
+            let current_sequence_len = self.api_sequences.len();
+            let chosen_sequence_index = rng.gen_range(0, current_sequence_len);
+            let chosen_sequence = &self.api_sequences[chosen_sequence_index];
+            if max_depth > 0 && chosen_sequence.len() >= max_depth {
+                continue;
+            }
+            let candidates = match chosen_sequence._last_api_func_index() {
+                Some(last_index) if !successors[last_index].is_empty() => &successors[last_index],
+                _ => &all_functions,
+            };
+            let total_weight: usize = candidates.iter().map(|index| complexities[*index]).sum();
+            let mut remaining_weight = rng.gen_range(0, total_weight);
+            let mut chosen_fun_index = candidates[candidates.len() - 1];
+            for candidate in candidates {
+                if remaining_weight < complexities[*candidate] {
+                    chosen_fun_index = *candidate;
+                    break;
+                }
+                remaining_weight -= complexities[*candidate];
+            }
+            let fun_type = ApiType::BareFunction;
+            if let Some(new_sequence) =
+                self.is_fun_satisfied(&fun_type, chosen_fun_index, chosen_sequence)
+            {
+                self.api_sequences.push(new_sequence);
+                self.api_functions_visited[chosen_fun_index] = true;
+
+                if !all_visited && self.check_all_visited() {
+                    println!("weighted random run {} times", i);
+                    all_visited = true;
+                }
+            }
+        }
+    }
+
     pub fn _choose_candidate_sequence_for_merge(&self) -> Vec<usize> {
         let mut res = Vec::new();
         let all_sequence_number = self.api_sequences.len();
@@ -526,8 +2034,75 @@ impl ApiGraph {
         res
     }
 
+    //为一个没有被访问到的节点，在候选序列中寻找能提供所有非fuzzable参数的序列，合并之后调用这个节点
+    fn _reverse_search(
+        &self,
+        unvisited_node: usize,
+        candidate_sequences: &Vec<usize>,
+    ) -> Option<ApiSequence> {
+        let unvisited_api_func = &self.api_functions[unvisited_node];
+        let inputs = &unvisited_api_func.inputs;
+        let mut dependent_sequence_indexes = Vec::new();
+        let mut can_be_covered_flag = true;
+        let input_param_num = inputs.len();
+        for i in 0..input_param_num {
+            let input_type = &inputs[i];
+            if api_util::is_fuzzable_type(input_type, &self.full_name_map) {
+                continue;
+            }
+            let mut can_find_dependency_flag = false;
+            let mut tmp_dependent_index = -1;
+            for candidate_sequence_index in candidate_sequences {
+                let output_type = ApiType::BareFunction;
+                let input_type = ApiType::BareFunction;
+                let candidate_sequence = &self.api_sequences[*candidate_sequence_index];
+                let output_index = candidate_sequence._last_api_func_index().unwrap();
+
+                if let Some(_) = self.check_dependency(
+                    &output_type,
+                    output_index,
+                    &input_type,
+                    unvisited_node,
+                    i,
+                ) {
+                    can_find_dependency_flag = true;
+                    //dependent_sequence_indexes.push(*candidate_sequence_index);
+                    tmp_dependent_index = *candidate_sequence_index as i32;
+
+                    //prefer sequence with fuzzable inputs
+                    if !candidate_sequence._has_no_fuzzables() {
+                        break;
+                    }
+                }
+            }
+            if !can_find_dependency_flag {
+                can_be_covered_flag = false;
+            } else {
+                dependent_sequence_indexes.push(tmp_dependent_index as usize);
+            }
+        }
+        if !can_be_covered_flag {
+            return None;
+        }
+        //println!("{:?} can be covered", unvisited_api_func.full_name);
+        let dependent_sequences: Vec<ApiSequence> = dependent_sequence_indexes
+            .into_iter()
+            .map(|index| self.api_sequences[index].clone())
+            .collect();
+        let merged_sequence = ApiSequence::_merge_sequences(&dependent_sequences);
+        let input_type = ApiType::BareFunction;
+        let generated_sequence =
+            self.is_fun_satisfied(&input_type, unvisited_node, &merged_sequence);
+        if generated_sequence.is_none() {
+            //The possible cause is there is some wrong fuzzable type
+            println!("Should not go to here. Only if algorithm error occurs");
+        }
+        generated_sequence
+    }
+
     pub fn _try_to_cover_unvisited_nodes(&mut self) {
         //println!("try to cover more nodes");
+        let reverse_search_start = Instant::now();
         let mut apis_covered_by_reverse_search = 0;
         let mut unvisited_nodes = HashSet::new();
         let api_fun_number = self.api_functions.len();
@@ -543,69 +2118,23 @@ impl ApiGraph {
             let candidate_sequences = self._choose_candidate_sequence_for_merge();
             //println!("sequence number, {}", self.api_sequences.len());
             //println!("candidate sequence number, {}", candidate_sequences.len());
-            for unvisited_node in &unvisited_nodes {
-                let unvisited_api_func = &self.api_functions[*unvisited_node];
-                let inputs = &unvisited_api_func.inputs;
-                let mut dependent_sequence_indexes = Vec::new();
-                let mut can_be_covered_flag = true;
-                let input_param_num = inputs.len();
-                for i in 0..input_param_num {
-                    let input_type = &inputs[i];
-                    if api_util::is_fuzzable_type(input_type, &self.full_name_map) {
-                        continue;
-                    }
-                    let mut can_find_dependency_flag = false;
-                    let mut tmp_dependent_index = -1;
-                    for candidate_sequence_index in &candidate_sequences {
-                        let output_type = ApiType::BareFunction;
-                        let input_type = ApiType::BareFunction;
-                        let candidate_sequence = &self.api_sequences[*candidate_sequence_index];
-                        let output_index = candidate_sequence._last_api_func_index().unwrap();
-
-                        if let Some(_) = self.check_dependency(
-                            &output_type,
-                            output_index,
-                            &input_type,
-                            *unvisited_node,
-                            i,
-                        ) {
-                            can_find_dependency_flag = true;
-                            //dependent_sequence_indexes.push(*candidate_sequence_index);
-                            tmp_dependent_index = *candidate_sequence_index as i32;
-
-                            //prefer sequence with fuzzable inputs
-                            if !candidate_sequence._has_no_fuzzables() {
-                                break;
-                            }
-                        }
-                    }
-                    if !can_find_dependency_flag {
-                        can_be_covered_flag = false;
-                    } else {
-                        dependent_sequence_indexes.push(tmp_dependent_index as usize);
-                    }
-                }
-                if can_be_covered_flag {
-                    //println!("{:?} can be covered", unvisited_api_func.full_name);
-                    let dependent_sequences: Vec<ApiSequence> = dependent_sequence_indexes
-                        .into_iter()
-                        .map(|index| self.api_sequences[index].clone())
-                        .collect();
-                    let merged_sequence = ApiSequence::_merge_sequences(&dependent_sequences);
-                    let input_type = ApiType::BareFunction;
-                    if let Some(generated_sequence) =
-                        self.is_fun_satisfied(&input_type, *unvisited_node, &merged_sequence)
-                    {
-                        //println!("{}", generated_sequence._to_well_written_function(self, 0, 0));
-
-                        self.api_sequences.push(generated_sequence);
-                        self.api_functions_visited[*unvisited_node] = true;
-                        covered_node_this_iteration.insert(*unvisited_node);
-                        apis_covered_by_reverse_search = apis_covered_by_reverse_search + 1;
-                    } else {
-                        //The possible cause is there is some wrong fuzzable type
-                        println!("Should not go to here. Only if algorithm error occurs");
-                    }
+            //每个未访问节点的搜索互不影响，并行搜索之后按节点编号依次加入，保证结果是确定的。
+            //_reverse_search只读self和candidate_sequences，不修改共享的状态。
+            //ApiGraph中有不能在线程间共享的类型(如Rc、RefCell)时，par_iter要求的ApiGraph: Sync不能通过编译
+            let mut sorted_unvisited_nodes: Vec<usize> = unvisited_nodes.iter().cloned().collect();
+            sorted_unvisited_nodes.sort();
+            let generated_sequences: Vec<(usize, Option<ApiSequence>)> = sorted_unvisited_nodes
+                .par_iter()
+                .map(|unvisited_node| {
+                    (*unvisited_node, self._reverse_search(*unvisited_node, &candidate_sequences))
+                })
+                .collect();
+            for (unvisited_node, generated_sequence) in generated_sequences {
+                if let Some(generated_sequence) = generated_sequence {
+                    self.api_sequences.push(generated_sequence);
+                    self.api_functions_visited[unvisited_node] = true;
+                    covered_node_this_iteration.insert(unvisited_node);
+                    apis_covered_by_reverse_search = apis_covered_by_reverse_search + 1;
                 }
             }
             if covered_node_this_iteration.len() == 0 {
@@ -617,6 +2146,11 @@ impl ApiGraph {
                 }
             }
         }
+        println!(
+            "reverse search took {:?} on {} threads",
+            reverse_search_start.elapsed(),
+            rayon::current_num_threads()
+        );
 
         let mut totol_sequences_number = 0;
         let mut total_length = 0;
@@ -796,6 +2330,18 @@ impl ApiGraph {
         res
     }
 
+    pub fn _sequence_usage_score(&self, sequence: &ApiSequence) -> usize {
+        if self.api_usage.is_empty() {
+            return 0;
+        }
+        let function_names = sequence
+            .functions
+            .iter()
+            .map(|api_call| &self.api_functions[api_call.func.1].full_name)
+            .collect();
+        self.api_usage._score(&function_names)
+    }
+
     pub fn _heuristic_choose(
         &self,
         max_size: usize,
@@ -857,6 +2403,7 @@ impl ApiGraph {
             let mut current_max_covered_nodes = 0;
             let mut current_max_covered_edges = 0;
             let mut current_chosen_sequence_len = 0;
+            let mut current_chosen_usage_score = 0;
 
             for j in 0..total_sequence_number {
                 if already_chosen_sequences.contains(&j) {
@@ -906,17 +2453,24 @@ impl ApiGraph {
                     continue;
                 }
                 let sequence_len = api_sequence.len();
+                //覆盖情况相同时，优先选择更符合反向依赖中实际用法的序列，再选择更短的序列
+                let usage_score = self._sequence_usage_score(api_sequence);
                 if (uncovered_nodes_by_former_sequence_count > current_max_covered_nodes)
                     || (uncovered_nodes_by_former_sequence_count == current_max_covered_nodes
                         && uncovered_edges_by_former_sequence_count > current_max_covered_edges)
                     || (uncovered_nodes_by_former_sequence_count == current_max_covered_nodes
                         && uncovered_edges_by_former_sequence_count == current_max_covered_edges
+                        && usage_score > current_chosen_usage_score)
+                    || (uncovered_nodes_by_former_sequence_count == current_max_covered_nodes
+                        && uncovered_edges_by_former_sequence_count == current_max_covered_edges
+                        && usage_score == current_chosen_usage_score
                         && sequence_len < current_chosen_sequence_len)
                 {
                     current_chosen_sequence_index = j;
                     current_max_covered_nodes = uncovered_nodes_by_former_sequence_count;
                     current_max_covered_edges = uncovered_edges_by_former_sequence_count;
                     current_chosen_sequence_len = sequence_len;
+                    current_chosen_usage_score = usage_score;
                 }
             }
 
@@ -1037,6 +2591,113 @@ impl ApiGraph {
         res
     }
 
+    //把选择序列看作集合覆盖问题：用最少的序列让每个可以到达的api至少被redundancy个序列调用
+    //(包含它的序列不够时，所有包含它的序列)。先贪心地选择满足最多剩余需求的序列，
+    //再按相反的顺序去掉多余的序列
+    pub fn _set_cover_choose(&self, max_size: usize, redundancy: usize) -> Vec<ApiSequence> {
+        let candidates: Vec<(usize, Vec<usize>)> = self
+            .api_sequences
+            .iter()
+            .enumerate()
+            .filter(|(_, sequence)| {
+                !sequence._has_no_fuzzables() && !sequence._contains_dead_code_except_last_one(self)
+            })
+            .map(|(index, sequence)| (index, sequence._get_contained_api_functions()))
+            .collect();
+        //每个api需要被多少个序列调用
+        let mut demands: HashMap<usize, usize> = HashMap::new();
+        for (_, covered_nodes) in &candidates {
+            for covered_node in covered_nodes {
+                *demands.entry(*covered_node).or_insert(0) += 1;
+            }
+        }
+        for demand in demands.values_mut() {
+            *demand = std::cmp::min(*demand, redundancy);
+        }
+        let to_cover_nodes_number = demands.len();
+
+        let mut remaining = demands.clone();
+        let mut chosen: Vec<usize> = Vec::new();
+        let mut chosen_candidates = HashSet::new();
+        while chosen.len() < max_size {
+            //按(满足的需求, 实际用法得分, 序列长度的相反数)选择
+            let mut best: Option<usize> = None;
+            let mut best_key = (0, 0, 0);
+            for (position, (index, covered_nodes)) in candidates.iter().enumerate() {
+                if chosen_candidates.contains(&position) {
+                    continue;
+                }
+                let gain = covered_nodes
+                    .iter()
+                    .filter(|covered_node| {
+                        remaining.get(covered_node).map_or(false, |demand| *demand > 0)
+                    })
+                    .count();
+                if gain == 0 {
+                    continue;
+                }
+                let sequence = &self.api_sequences[*index];
+                let key = (gain, self._sequence_usage_score(sequence), usize::MAX - sequence.len());
+                if best.is_none() || key > best_key {
+                    best = Some(position);
+                    best_key = key;
+                }
+            }
+            let position = match best {
+                Some(position) => position,
+                None => break,
+            };
+            chosen_candidates.insert(position);
+            chosen.push(position);
+            for covered_node in &candidates[position].1 {
+                if let Some(demand) = remaining.get_mut(covered_node) {
+                    *demand = demand.saturating_sub(1);
+                }
+            }
+        }
+
+        //去掉冗余的序列：从最后选中的开始，去掉之后每个api仍然被足够多的序列调用
+        let mut cover_counts: HashMap<usize, usize> = HashMap::new();
+        for position in &chosen {
+            for covered_node in &candidates[*position].1 {
+                *cover_counts.entry(*covered_node).or_insert(0) += 1;
+            }
+        }
+        let mut kept = vec![true; chosen.len()];
+        for chosen_index in (0..chosen.len()).rev() {
+            let covered_nodes = &candidates[chosen[chosen_index]].1;
+            let is_redundant = covered_nodes.iter().all(|covered_node| {
+                let cover_count = cover_counts.get(covered_node).cloned().unwrap_or(0);
+                let demand = demands.get(covered_node).cloned().unwrap_or(0);
+                cover_count > demand
+            });
+            if is_redundant {
+                kept[chosen_index] = false;
+                for covered_node in covered_nodes {
+                    if let Some(cover_count) = cover_counts.get_mut(covered_node) {
+                        *cover_count -= 1;
+                    }
+                }
+            }
+        }
+
+        let res: Vec<ApiSequence> = chosen
+            .iter()
+            .zip(kept.iter())
+            .filter(|(_, kept)| **kept)
+            .map(|(position, _)| self.api_sequences[candidates[*position].0].clone())
+            .collect();
+        let covered_node_num =
+            cover_counts.values().filter(|cover_count| **cover_count > 0).count();
+        println!("-----------SET COVER-----------");
+        println!("redundancy: {}", redundancy);
+        println!("candidate sequences: {}", candidates.len());
+        println!("covered nodes: {}/{}", covered_node_num, to_cover_nodes_number);
+        println!("total targets: {}", res.len());
+        println!("--------------------------------");
+        res
+    }
+
     //判断一个函数能否加入给定的序列中,如果可以加入，返回Some(new_sequence),new_sequence是将新的调用加进去之后的情况，否则返回None
     pub fn is_fun_satisfied(
         &self,
@@ -1063,6 +2724,13 @@ impl ApiGraph {
                     let trait_full_path = input_function._trait_full_path.as_ref().unwrap();
                     new_sequence.add_trait(trait_full_path);
                 }
+                if let Some(ref helper) = input_function._helper {
+                    new_sequence.add_helper(helper);
+                }
+                //async api需要executor
+                if self._is_async_function(&input_function.full_name) {
+                    new_sequence.add_helper(&self.async_helper);
+                }
                 let input_params = &input_function.inputs;
                 let input_params_num = input_params.len();
                 if input_params_num == 0 {
@@ -1115,8 +2783,10 @@ impl ApiGraph {
 
                     for function_index in 0..functions_in_sequence_len {
                         //如果这个sequence里面的该函数返回值已经被move掉了，那么就跳过，不再能被使用了
+                        //被之后的返回值可变借用的值也不能再使用
                         if new_sequence._is_moved(function_index)
                             || _moved_indexes.contains(&function_index)
+                            || new_sequence._is_mut_borrowed(function_index)
                         {
                             continue;
                         }
@@ -1134,6 +2804,7 @@ impl ApiGraph {
                             if api_util::_move_condition(current_ty, &dependency_.call_type) {
                                 if _multi_mut.contains(&function_index)
                                     || _immutable_borrow.contains(&function_index)
+                                    || new_sequence._is_borrowed(function_index)
                                 {
                                     dependency_flag = false;
                                     continue;
@@ -1149,6 +2820,7 @@ impl ApiGraph {
                                 //如果之前已经被借用过了
                                 if _multi_mut.contains(&function_index)
                                     || _immutable_borrow.contains(&function_index)
+                                    || new_sequence._is_borrowed(function_index)
                                 {
                                     dependency_flag = false;
                                     continue;
@@ -1190,6 +2862,23 @@ impl ApiGraph {
                     }
                 }
                 //所有参数都可以找到依赖，那么这个函数就可以加入序列
+                //返回值借用了哪些参数，被借用的返回值在之后不能被move或者可变借用
+                for borrowed_param in input_function._output_borrowed_params() {
+                    if let (ParamType::_FunctionReturn, function_index, call_type) =
+                        &api_call.params[borrowed_param]
+                    {
+                        if !api_util::_move_condition(&input_params[borrowed_param], call_type) {
+                            new_sequence._insert_borrow_index(
+                                *function_index,
+                                api_util::_need_mut_tag(call_type)
+                                    || api_util::_is_mutable_borrow_occurs(
+                                        &input_params[borrowed_param],
+                                        call_type,
+                                    ),
+                            );
+                        }
+                    }
+                }
                 new_sequence._add_fn(api_call);
                 for move_index in _moved_indexes {
                     new_sequence._insert_move_index(move_index);
@@ -1253,3 +2942,129 @@ impl ApiGraph {
         }
     }
 }
+
+//api的参数和返回值中出现的、没有泛型参数的crate类型，包括Result/Option等类型参数中的类型
+//去掉全名中的turbofish，如Pool::<u8>::get -> Pool::get
+fn _name_without_turbofish(full_name: &String) -> String {
+    let mut name_without_turbofish = String::new();
+    let mut depth = 0;
+    for segment in full_name.split("::") {
+        if depth == 0 && !segment.starts_with('<') {
+            if !name_without_turbofish.is_empty() {
+                name_without_turbofish.push_str("::");
+            }
+            name_without_turbofish.push_str(segment);
+        }
+        depth += segment.matches('<').count();
+        depth -= segment.matches('>').count();
+    }
+    name_without_turbofish
+}
+
+//取出impl Stream/impl Iterator的item的helper api
+fn _is_item_consumer(api_function: &ApiFunction) -> bool {
+    match api_function._helper {
+        Some(prelude_type::_PreludeHelper::_StreamNextHelper)
+        | Some(prelude_type::_PreludeHelper::_IterNextHelper) => {
+            api_function.inputs.iter().any(|input| match input {
+                clean::Type::ImplTrait(_) => true,
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+//Box<T>中的T
+fn _boxed_type(type_: &clean::Type) -> Option<&clean::Type> {
+    let segment = match type_ {
+        clean::Type::ResolvedPath { path, .. } => path.segments.last()?,
+        _ => return None,
+    };
+    if segment.name != "Box" {
+        return None;
+    }
+    match &segment.args {
+        clean::GenericArgs::AngleBracketed { args, .. } => match args.as_slice() {
+            [clean::GenericArg::Type(inner_type)] => Some(inner_type),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+//依赖crate中clean出的类型和被测crate中写出的路径不同，把返回值中构造的类型换成api参数中的类型，
+//Option和Result只替换第一个类型参数
+fn _with_produced_type(
+    output: &clean::Type,
+    produced_def_id: DefId,
+    produced_type: &clean::Type,
+) -> Option<clean::Type> {
+    if output.def_id() == Some(produced_def_id) {
+        return Some(produced_type.clone());
+    }
+    let mut output = output.clone();
+    if let clean::Type::ResolvedPath { path, .. } = &mut output {
+        if let Some(clean::GenericArgs::AngleBracketed { args, .. }) =
+            path.segments.last_mut().map(|segment| &mut segment.args)
+        {
+            for arg in args.iter_mut() {
+                if let clean::GenericArg::Type(inner_type) = arg {
+                    if inner_type.def_id() != Some(produced_def_id) {
+                        return None;
+                    }
+                    *inner_type = produced_type.clone();
+                    return Some(output);
+                }
+            }
+        }
+    }
+    None
+}
+
+//实例化泛型参数的类型的代价：基本类型直接由fuzz输入得到，&str需要读入长度不定的字节，
+//crate中的类型需要先调用产生它的api，没有api产生的类型只在没有其他候选时使用
+fn _instance_type_cost(
+    instance_type: &InstanceType,
+    producer_costs: &HashMap<DefId, usize>,
+) -> usize {
+    match instance_type {
+        InstanceType::Primitive(_) => 1,
+        InstanceType::StrRef => 2,
+        InstanceType::Adt(def_id) => match producer_costs.get(def_id) {
+            Some(producer_cost) => 2 + producer_cost,
+            None => UNPRODUCIBLE_TYPE_COST,
+        },
+    }
+}
+
+fn _collect_concrete_types(ty_: &clean::Type, known_types: &mut HashMap<DefId, clean::Type>) {
+    match ty_ {
+        clean::Type::ResolvedPath { path, did, is_generic: false, .. } => {
+            let mut has_type_args = false;
+            for segment in &path.segments {
+                if let clean::GenericArgs::AngleBracketed { args, .. } = &segment.args {
+                    for arg in args {
+                        if let clean::GenericArg::Type(inner_type) = arg {
+                            has_type_args = true;
+                            _collect_concrete_types(inner_type, known_types);
+                        }
+                    }
+                }
+            }
+            if !has_type_args && did.is_local() {
+                known_types.entry(*did).or_insert_with(|| ty_.clone());
+            }
+        }
+        clean::Type::BorrowedRef { type_, .. }
+        | clean::Type::RawPointer(_, type_)
+        | clean::Type::Slice(type_)
+        | clean::Type::Array(type_, _) => _collect_concrete_types(type_, known_types),
+        clean::Type::Tuple(types) => {
+            for inner_type in types {
+                _collect_concrete_types(inner_type, known_types);
+            }
+        }
+        _ => {}
+    }
+}
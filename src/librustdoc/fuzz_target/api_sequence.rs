@@ -1,11 +1,27 @@
+use crate::clean::{self, types::GetDefId, PrimitiveType};
 use crate::fuzz_target::afl_util::{self, _AflHelpers};
+use crate::fuzz_target::api_function::{ApiFunction, ParamRelationKind};
 use crate::fuzz_target::api_graph::{ApiGraph, ApiType};
-use crate::fuzz_target::api_util;
+use crate::fuzz_target::api_util::{self, UnwrapPolicy};
 use crate::fuzz_target::call_type::CallType;
 use crate::fuzz_target::fuzzable_type::FuzzableType;
-use crate::fuzz_target::prelude_type;
+use crate::fuzz_target::impl_util::{self, FullNameMap};
+use crate::fuzz_target::prelude_type::{self, _PreludeHelper, HelperDependency};
 use crate::fuzz_target::replay_util;
-use std::collections::{HashMap, HashSet};
+use crate::fuzz_target::sandbox_util;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+static MAX_PACKED_SEQUENCES: usize = 8;
+//fuzzable参数达到这个数量时，把参数放进一个_Args结构体，由一个解析函数统一生成
+static ARGS_STRUCT_PARAM_NUMBER: usize = 8;
+//--dry-run执行到序列末尾时的输出
+pub static DRY_RUN_TAIL: &'static str = "dry run: reached the end of the sequence";
+//kani harness中长度不固定的参数可以使用的字节数
+static KANI_EXTRA_INPUT_LENGTH: usize = 16;
+//afl target中供外部driver调用的C ABI入口
+pub static C_ENTRY_NAME: &'static str = "rulf_run";
+//stateful序列中&mut self方法调用的最大次数，每次调用消耗一个输入字节
+static MAX_STATEFUL_STEPS: usize = 64;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub enum ParamType {
@@ -44,11 +60,18 @@ pub struct ApiSequence {
     pub functions: Vec<ApiCall>,               //函数调用序列
     pub fuzzable_params: Vec<FuzzableType>,    //需要传入的fuzzable变量
     pub _using_traits: Vec<String>,            //需要use引入的traits的路径
+    pub _using_helpers: Vec<_PreludeHelper>,   //api本身需要的helper函数
     pub _unsafe_tag: bool,                     //标志这个调用序列是否需要加上unsafe标记
     pub _moved: HashSet<usize>,                //表示哪些返回值已经被move掉，不再能被使用
+    pub _borrowed: HashSet<usize>,             //被之后的返回值借用，不能再被move或可变借用
+    pub _mut_borrowed: HashSet<usize>,         //被之后的返回值可变借用，不能再被使用
     pub _fuzzable_mut_tag: HashSet<usize>,     //表示哪些fuzzable的变量需要带上mut标记
     pub _function_mut_tag: HashSet<usize>,     //表示哪些function的返回值需要带上mut标记
     pub _covered_dependencies: HashSet<usize>, //表示用到了哪些dependency,即边覆盖率
+    pub _violate_param_relations: bool,        //故意违反参数之间的长度/下标关系的adversarial变体
+    pub _unwrap_policy: UnwrapPolicy,          //Err和None时的处理方式
+    pub _stateful_calls: Vec<ApiCall>,         //在序列之后由输入字节选择的&mut self方法调用
+    pub _stateful_selector: Option<usize>,     //选择方法的字节所在的fuzzable参数
 }
 
 impl ApiSequence {
@@ -56,20 +79,34 @@ impl ApiSequence {
         let functions = Vec::new();
         let fuzzable_params = Vec::new();
         let _using_traits = Vec::new();
+        let _using_helpers = Vec::new();
         let _unsafe_tag = false;
         let _moved = HashSet::new();
+        let _borrowed = HashSet::new();
+        let _mut_borrowed = HashSet::new();
         let _fuzzable_mut_tag = HashSet::new();
         let _function_mut_tag = HashSet::new();
         let _covered_dependencies = HashSet::new();
+        let _violate_param_relations = false;
+        let _unwrap_policy = UnwrapPolicy::Exit;
+        let _stateful_calls = Vec::new();
+        let _stateful_selector = None;
         ApiSequence {
             functions,
             fuzzable_params,
             _using_traits,
+            _using_helpers,
             _unsafe_tag,
             _moved,
+            _borrowed,
+            _mut_borrowed,
             _fuzzable_mut_tag,
             _function_mut_tag,
             _covered_dependencies,
+            _violate_param_relations,
+            _unwrap_policy,
+            _stateful_calls,
+            _stateful_selector,
         }
     }
 
@@ -129,6 +166,8 @@ impl ApiSequence {
         res.fuzzable_params.append(&mut other_sequence.fuzzable_params);
         //using_trait
         res._using_traits.append(&mut other_sequence._using_traits);
+        //using helper
+        res._using_helpers.append(&mut other_sequence._using_helpers);
         //unsafe tag
         res._unsafe_tag =
             if other_sequence._unsafe_tag { other_sequence._unsafe_tag } else { res._unsafe_tag };
@@ -136,6 +175,13 @@ impl ApiSequence {
         for move_tag in other_sequence._moved {
             res._moved.insert(move_tag + first_func_number);
         }
+        //borrow tag
+        for borrow_tag in other_sequence._borrowed {
+            res._borrowed.insert(borrow_tag + first_func_number);
+        }
+        for mut_borrow_tag in other_sequence._mut_borrowed {
+            res._mut_borrowed.insert(mut_borrow_tag + first_func_number);
+        }
         //fuzzable mut tag
         for fuzzable_mut_tag in other_sequence._fuzzable_mut_tag {
             res._fuzzable_mut_tag.insert(fuzzable_mut_tag + first_fuzzable_number);
@@ -161,6 +207,40 @@ impl ApiSequence {
         basic_sequence
     }
 
+    //把只消耗少量输入的定长序列合并到同一个target中，每个序列读取输入中各自的一段，
+    //总长度不超过byte_budget。变长或者adversarial的序列保持不变
+    pub fn _pack_cheap_sequences(sequences: Vec<ApiSequence>, byte_budget: usize) -> Vec<Self> {
+        let mut res = Vec::new();
+        let mut pack: Vec<ApiSequence> = Vec::new();
+        let mut pack_length = 0;
+        for sequence in sequences {
+            let sequence_length = sequence._fuzzables_min_length();
+            if !sequence._is_fuzzables_fixed_length()
+                || sequence._violate_param_relations
+                || sequence_length > byte_budget
+            {
+                res.push(sequence);
+                continue;
+            }
+            let policy_changed =
+                pack.first().map_or(false, |first| first._unwrap_policy != sequence._unwrap_policy);
+            if pack_length + sequence_length > byte_budget
+                || pack.len() >= MAX_PACKED_SEQUENCES
+                || policy_changed
+            {
+                res.push(ApiSequence::_merge_sequences(&pack));
+                pack.clear();
+                pack_length = 0;
+            }
+            pack_length = pack_length + sequence_length;
+            pack.push(sequence);
+        }
+        if !pack.is_empty() {
+            res.push(ApiSequence::_merge_sequences(&pack));
+        }
+        res
+    }
+
     pub fn _contains_api_function(&self, index: usize) -> bool {
         for api_call in &self.functions {
             let (_, func_index) = api_call.func;
@@ -190,10 +270,37 @@ impl ApiSequence {
         self._moved.insert(index);
     }
 
+    pub fn _is_borrowed(&self, index: usize) -> bool {
+        self._borrowed.contains(&index)
+    }
+
+    pub fn _is_mut_borrowed(&self, index: usize) -> bool {
+        self._mut_borrowed.contains(&index)
+    }
+
+    //返回值带有生命周期的api调用之后，它借用的那些返回值在序列剩下的部分中一直被借用
+    pub fn _insert_borrow_index(&mut self, index: usize, mutable: bool) {
+        if mutable {
+            self._mut_borrowed.insert(index);
+        } else {
+            self._borrowed.insert(index);
+        }
+    }
+
     pub fn _add_fn(&mut self, api_call: ApiCall) {
         self.functions.push(api_call);
     }
 
+    //target_index的返回值之后由一个字节slice驱动，反复调用stateful_calls中的方法
+    pub fn _set_stateful_calls(&mut self, target_index: usize, stateful_calls: Vec<ApiCall>) {
+        let selector_index = self.fuzzable_params.len();
+        self.fuzzable_params
+            .push(FuzzableType::RefSlice(Box::new(FuzzableType::Primitive(PrimitiveType::U8))));
+        self._insert_function_mut_tag(target_index);
+        self._stateful_calls = stateful_calls;
+        self._stateful_selector = Some(selector_index);
+    }
+
     pub fn _insert_fuzzable_mut_tag(&mut self, index: usize) {
         self._fuzzable_mut_tag.insert(index);
     }
@@ -210,6 +317,14 @@ impl ApiSequence {
         if self._function_mut_tag.contains(&index) { true } else { false }
     }
 
+    //序列中有async api时会用到执行它们的helper
+    pub fn _is_async(&self) -> bool {
+        self._using_helpers.iter().any(|helper| match helper {
+            _PreludeHelper::_BlockOnHelper | _PreludeHelper::_TokioBlockOnHelper => true,
+            _ => false,
+        })
+    }
+
     pub fn set_unsafe(&mut self) {
         self._unsafe_tag = true;
     }
@@ -218,6 +333,10 @@ impl ApiSequence {
         self._using_traits.push(trait_full_path.clone());
     }
 
+    pub fn add_helper(&mut self, helper: &_PreludeHelper) {
+        self._using_helpers.push(helper.clone());
+    }
+
     pub fn _is_fuzzables_fixed_length(&self) -> bool {
         for fuzzable_param in &self.fuzzable_params {
             if !fuzzable_param._is_fixed_length() {
@@ -260,6 +379,46 @@ impl ApiSequence {
         total_number
     }
 
+    //多个动态长度的参数分别从带长度前缀的分段中读取，变异时每段的长度可以独立变化
+    pub fn _uses_length_prefixed_sections(&self) -> bool {
+        self._dynamic_length_param_number() >= 2
+    }
+
+    pub fn _uses_args_struct(&self) -> bool {
+        self.fuzzable_params.len() >= ARGS_STRUCT_PARAM_NUMBER
+    }
+
+    //分段输入的初始种子：固定长度部分全0，每个动态长度参数4个字节
+    pub fn _section_seed(&self) -> Option<Vec<u8>> {
+        if !self._uses_length_prefixed_sections() {
+            return None;
+        }
+        let mut seed = vec![0u8; self._fuzzable_fixed_part_length()];
+        for i in 0..self._dynamic_length_param_number() {
+            if i != self._dynamic_length_param_number() - 1 {
+                seed.extend_from_slice(&[4, 0]);
+            }
+            seed.extend_from_slice(b"aaaa");
+        }
+        Some(seed)
+    }
+
+    //dry run用的输入：定长参数取小的正数等最可能有效的值，动态长度的参数取合法的utf-8
+    pub fn _viable_input(&self) -> Vec<u8> {
+        let mut input = Vec::new();
+        for fuzzable_param in &self.fuzzable_params {
+            input.extend(fuzzable_param._viable_fixed_part());
+        }
+        let dynamic_param_number = self._dynamic_length_param_number();
+        for i in 0..dynamic_param_number {
+            if self._uses_length_prefixed_sections() && i != dynamic_param_number - 1 {
+                input.extend_from_slice(&[4, 0]);
+            }
+            input.extend_from_slice(b"fuzz");
+        }
+        input
+    }
+
     pub fn _dead_code(&self, _api_graph: &ApiGraph) -> Vec<bool> {
         let sequence_len = self.len();
         let mut dead_api_call = Vec::new();
@@ -280,6 +439,15 @@ impl ApiSequence {
                 }
             }
         }
+        //stateful方法的self在所有调用之后才被使用
+        for api_call in &self._stateful_calls {
+            for (param_type, index, _) in &api_call.params {
+                if let ParamType::_FunctionReturn = param_type {
+                    dead_api_call[*index] = false;
+                    used_params.insert(*index, api_call_num);
+                }
+            }
+        }
 
         for api_call_index in 0..api_call_num {
             if !dead_api_call[api_call_index] {
@@ -328,40 +496,234 @@ impl ApiSequence {
     }
 
     pub fn _to_replay_crash_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
-        res = res.replace("#[macro_use]\nextern crate afl;\n", "");
+        let mut res = self._to_target_except_main(_api_graph, test_index, "");
         res.push_str(replay_util::_read_crash_file_data());
         res.push('\n');
-        res.push_str(self._reproduce_main_function(test_index).as_str());
-        res
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._reproduce_main_function(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
     }
 
     pub fn _to_afl_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
-        res.push_str(self._afl_main_function(test_index).as_str());
-        res
+        let mut res = self._to_target_except_main(
+            _api_graph,
+            test_index,
+            "#[macro_use]\nextern crate afl;\n",
+        );
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._afl_main_function(test_index, sandbox).as_str());
+        res.push_str(self._c_entry_function(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
     }
 
     pub fn _to_libfuzzer_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
-        res = res.replace(
-            "#[macro_use]\nextern crate afl;\n",
-            format!("#![no_main]\n#[macro_use]\nextern crate libfuzzer_sys;\n").as_str(),
+        let mut res = self._to_target_except_main(
+            _api_graph,
+            test_index,
+            "#![no_main]\n#[macro_use]\nextern crate libfuzzer_sys;\n",
+        );
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._libfuzzer_fuzz_main(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
+    }
+
+    pub fn _to_honggfuzz_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
+        let mut res = self._to_target_except_main(
+            _api_graph,
+            test_index,
+            "#[macro_use]\nextern crate honggfuzz;\n",
         );
-        res.push_str(self._libfuzzer_fuzz_main(test_index).as_str());
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._honggfuzz_main_function(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
+    }
+
+    pub fn _to_bolero_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
+        let mut res = self._to_target_except_main(_api_graph, test_index, "extern crate bolero;\n");
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._bolero_main_function(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
+    }
+
+    pub fn _to_kani_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
+        let mut res = self._to_target_except_main(_api_graph, test_index, "");
+        res.push_str(self._kani_proof_function(test_index).as_str());
         res
     }
 
-    pub fn _libfuzzer_fuzz_main(&self, test_index: usize) -> String {
+    pub fn _to_smoke_test_file(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
+        let mut res = self._to_target_except_main(_api_graph, test_index, "");
+        let sandbox = sandbox_util::need_sandbox(&_api_graph._crate_name);
+        res.push_str(self._smoke_main_function(test_index, sandbox).as_str());
+        self._sandbox_if_needed(sandbox, res)
+    }
+
+    //进入sandbox的语句由各个main函数生成，这里只加上sandbox_util中的函数
+    fn _sandbox_if_needed(&self, sandbox: bool, mut target_file: String) -> String {
+        if sandbox {
+            target_file.push_str(sandbox_util::_sandbox_functions());
+        }
+        target_file
+    }
+
+    pub fn _libfuzzer_fuzz_main(&self, test_index: usize, sandbox: bool) -> String {
         let mut res = String::new();
         res.push_str("fuzz_target!(|data: &[u8]| {\n");
+        if sandbox {
+            res.push_str(sandbox_util::_enter_sandbox_once_statement("    ").as_str());
+        }
         res.push_str(self._afl_closure_body(0, test_index).as_str());
         res.push_str("});\n");
         res
     }
 
-    pub fn _to_afl_except_main(&self, _api_graph: &ApiGraph, test_index: usize) -> String {
+    //honggfuzz的fuzz!每次只运行一个输入，需要在main中循环调用
+    pub fn _honggfuzz_main_function(&self, test_index: usize, sandbox: bool) -> String {
+        let mut res = String::new();
+        res.push_str("fn main() {\n");
+        if sandbox {
+            res.push_str(sandbox_util::_enter_sandbox_statement("    ").as_str());
+        }
+        res.push_str("    loop {\n");
+        res.push_str("        fuzz!(|data: &[u8]| {\n");
+        res.push_str(self._afl_closure_body(8, test_index).as_str());
+        res.push_str("        });\n");
+        res.push_str("    }\n");
+        res.push_str("}\n");
+        res
+    }
+
+    //bolero的check!在cargo test下用随机输入运行，在cargo bolero test下由选择的fuzzer驱动
+    pub fn _bolero_main_function(&self, test_index: usize, sandbox: bool) -> String {
+        let mut res = String::new();
+        res.push_str("fn main() {\n");
+        if sandbox {
+            res.push_str(sandbox_util::_enter_sandbox_statement("    ").as_str());
+        }
+        res.push_str("    bolero::check!().for_each(|data: &[u8]| {\n");
+        res.push_str(self._afl_closure_body(4, test_index).as_str());
+        res.push_str("    });\n");
+        res.push_str("}\n");
+        res
+    }
+
+    //kani把_input中的每个字节当作任意值，解析参数和调用序列与fuzz target相同。
+    //长度不固定的参数只多给KANI_EXTRA_INPUT_LENGTH个字节，避免状态空间过大
+    pub fn _kani_proof_function(&self, test_index: usize) -> String {
+        let min_len = self._fuzzables_min_length();
+        let input_len = if self._is_fuzzables_fixed_length() {
+            min_len
+        } else {
+            min_len + KANI_EXTRA_INPUT_LENGTH
+        };
+        format!(
+            "#[cfg(kani)]
+#[kani::proof]
+fn proof{test_index}() {{
+    let _input: [u8; {input_len}] = kani::any();
+    let data = &_input[..];
+{closure_body}}}
+
+fn main() {{}}\n",
+            test_index = test_index,
+            input_len = input_len,
+            closure_body = self._afl_closure_body(0, test_index)
+        )
+    }
+
+    //参数是一个输入文件或者一个目录，目录中的每个文件作为一个输入运行一遍序列。
+    //panic的输入会被打印出来，不会中止后面的输入，有panic时退出码为1。
+    //输入路径先转成绝对路径，找到所有输入之后才进入sandbox
+    pub fn _smoke_main_function(&self, test_index: usize, sandbox: bool) -> String {
+        let enter_sandbox =
+            if sandbox { sandbox_util::_enter_sandbox_statement("    ") } else { String::new() };
+        format!(
+            "fn main() {{
+    let _args: Vec<String> = std::env::args().collect();
+    if _args.len() < 2 {{
+        println!(\"usage: {{}} <input file or directory>\", _args[0]);
+        std::process::exit(2);
+    }}
+    let _path = std::env::current_dir().unwrap().join(&_args[1]);
+    let mut _inputs = Vec::new();
+    if _path.is_dir() {{
+        for _entry in std::fs::read_dir(&_path).unwrap() {{
+            let _entry_path = _entry.unwrap().path();
+            if _entry_path.is_file() {{
+                _inputs.push(_entry_path);
+            }}
+        }}
+        _inputs.sort();
+    }} else {{
+        _inputs.push(_path);
+    }}
+{enter_sandbox}    let mut _panicked = 0;
+    for _input in &_inputs {{
+        let _content = std::fs::read(_input).unwrap();
+        let data = &_content[..];
+        let _run = std::panic::AssertUnwindSafe(|| {{
+{closure_body}        }});
+        if std::panic::catch_unwind(_run).is_err() {{
+            println!(\"smoke: {{}} panicked\", _input.display());
+            _panicked += 1;
+        }}
+    }}
+    println!(\"smoke: {{}} inputs, {{}} panicked\", _inputs.len(), _panicked);
+    if _panicked > 0 {{
+        std::process::exit(1);
+    }}
+}}\n",
+            enter_sandbox = enter_sandbox,
+            closure_body = self._afl_closure_body(8, test_index)
+        )
+    }
+
+    //除了main以外target的全部内容，engine_header: 引入fuzz引擎的crate的几行，放在被测crate之前
+    pub fn _to_target_except_main(
+        &self,
+        _api_graph: &ApiGraph,
+        test_index: usize,
+        engine_header: &str,
+    ) -> String {
         let mut res = String::new();
+        //记录调用序列，重新生成target时用来和之前的target比较
+        res.push_str(format!("//sequence: {}\n", self._signature(_api_graph)).as_str());
+        //标记序列中用到的只在helper cfg下编译的函数，这类target需要用相同的cfg编译被测crate
+        let cfg_gated_functions = self._cfg_gated_functions(_api_graph);
+        if !cfg_gated_functions.is_empty() {
+            let helper_cfg = api_util::helper_cfg().unwrap_or_default();
+            res.push_str(
+                format!(
+                    "//NOTE: uses functions compiled only with --cfg {}: {}\n",
+                    helper_cfg,
+                    cfg_gated_functions.join(", ")
+                )
+                .as_str(),
+            );
+        }
+        //标记序列需要打开的被测crate的feature，手写的fuzz crate依赖被测crate时需要打开这些feature
+        let required_features = self._required_features(_api_graph);
+        if !required_features.is_empty() {
+            res.push_str(
+                format!(
+                    "//NOTE: needs features of {}: {}\n",
+                    _api_graph._crate_name,
+                    required_features.join(", ")
+                )
+                .as_str(),
+            );
+        }
+        //标记序列中只是转发到外部函数的api，这些api中的crash多半在C库中
+        let ffi_functions = self._ffi_functions(_api_graph);
+        if !ffi_functions.is_empty() {
+            res.push_str(
+                format!(
+                    "//NOTE: calls APIs forwarding to foreign functions: {}\n",
+                    ffi_functions.join(", ")
+                )
+                .as_str(),
+            );
+        }
         //加入可能需要开启的feature gate
         let feature_gates = afl_util::_get_feature_gates_of_sequence(&self.fuzzable_params);
 
@@ -372,35 +734,216 @@ impl ApiSequence {
             }
         }
 
-        res.push_str("#[macro_use]\n");
-        res.push_str("extern crate afl;\n");
+        res.push_str(engine_header);
         res.push_str(format!("extern crate {};\n", _api_graph._crate_name).as_str());
 
         let prelude_helper_functions = self._prelude_helper_functions();
         if let Some(prelude_functions) = prelude_helper_functions {
             res.push_str(prelude_functions.as_str());
         }
+        if self._prelude_helpers().contains(&_PreludeHelper::_SerdeCompatHelper) {
+            res.push_str(self._serde_golden_definition(_api_graph).as_str());
+        }
+        if self._prelude_helpers().iter().any(|helper| match helper {
+            _PreludeHelper::_DerivedHelper | _PreludeHelper::_DerivedEqHelper => true,
+            _ => false,
+        }) {
+            res.push_str(self._derived_nest_definition(_api_graph).as_str());
+        }
 
         let afl_helper_functions = self._afl_helper_functions();
         if let Some(afl_functions) = afl_helper_functions {
             res.push_str(afl_functions.as_str());
         }
+        if self._uses_args_struct() {
+            res.push_str(self._args_struct_definition().as_str());
+        }
         res.push_str(self._to_well_written_function(_api_graph, test_index, 0).as_str());
         res.push('\n');
         res
     }
 
-    pub fn _prelude_helper_functions(&self) -> Option<String> {
+    //参数很多时，把所有fuzzable参数放进_Args结构体，解析逻辑集中在_Args::_decode中，
+    //test function只接收一个_Args
+    pub fn _args_struct_definition(&self) -> String {
+        let type_strings: Vec<String> =
+            self.fuzzable_params.iter().map(|param| param._to_type_string()).collect();
+        let has_reference = type_strings.iter().any(|type_string| type_string.contains('&'));
+        let lifetime = if has_reference { "<'a>" } else { "" };
+        let data_type = if has_reference { "&'a [u8]" } else { "&[u8]" };
+        let mut res = String::new();
+        res.push_str(format!("struct _Args{} {{\n", lifetime).as_str());
+        for (i, type_string) in type_strings.iter().enumerate() {
+            let type_string =
+                if has_reference { type_string.replace("&", "&'a ") } else { type_string.clone() };
+            res.push_str(format!("    _param{}: {},\n", i, type_string).as_str());
+        }
+        res.push_str("}\n\n");
+        res.push_str(format!("impl{lifetime} _Args{lifetime} {{\n", lifetime = lifetime).as_str());
+        res.push_str(format!("    fn _decode(data: {}) -> Option<Self> {{\n", data_type).as_str());
+        res.push_str(self._afl_param_decoding(4, "return None;").as_str());
+        res.push_str(
+            format!("        Some(_Args {{ {} }})\n", self._args_field_list(false)).as_str(),
+        );
+        res.push_str("    }\n");
+        res.push_str("}\n\n");
+        res
+    }
+
+    fn _args_field_list(&self, with_mut_tag: bool) -> String {
+        let mut fields = Vec::new();
+        for i in 0..self.fuzzable_params.len() {
+            let mut_tag =
+                if with_mut_tag && self._is_fuzzable_need_mut_tag(i) { "mut " } else { "" };
+            fields.push(format!("{}_param{}", mut_tag, i));
+        }
+        fields.join(", ")
+    }
+
+    pub fn _cfg_gated_functions(&self, _api_graph: &ApiGraph) -> Vec<String> {
+        let mut res = Vec::new();
+        for api_call in &self.functions {
+            let (_, index) = api_call.func;
+            let full_name = &_api_graph.api_functions[index].full_name;
+            if _api_graph._is_cfg_gated_function(full_name) && !res.contains(full_name) {
+                res.push(full_name.clone());
+            }
+        }
+        res
+    }
+
+    //api的全名，以及括号中它调用的外部函数
+    pub fn _ffi_functions(&self, _api_graph: &ApiGraph) -> Vec<String> {
+        let mut res = Vec::new();
+        for api_call in &self.functions {
+            let (_, index) = api_call.func;
+            let full_name = &_api_graph.api_functions[index].full_name;
+            if let Some(callees) = _api_graph._ffi_callees_of(full_name) {
+                let callees: Vec<&str> = callees.iter().map(|callee| callee.as_str()).collect();
+                let ffi_function = format!("{} ({})", full_name, callees.join(", "));
+                if !res.contains(&ffi_function) {
+                    res.push(ffi_function);
+                }
+            }
+        }
+        res
+    }
+
+    pub fn _required_features(&self, _api_graph: &ApiGraph) -> Vec<String> {
+        let mut res = BTreeSet::new();
+        for api_call in &self.functions {
+            let (_, index) = api_call.func;
+            let full_name = &_api_graph.api_functions[index].full_name;
+            res.extend(_api_graph._feature_gates_of(full_name));
+        }
+        res.into_iter().collect()
+    }
+
+    pub fn _prelude_helpers(&self) -> HashSet<_PreludeHelper> {
         let mut prelude_helpers = HashSet::new();
+        for helper in &self._using_helpers {
+            prelude_helpers.insert(helper.clone());
+        }
         for api_call in &self.functions {
             let params = &api_call.params;
             for (_, _, call_type) in params {
                 let helpers = prelude_type::_PreludeHelper::_from_call_type(call_type);
                 for helper in helpers {
-                    prelude_helpers.insert(helper);
+                    match (self._unwrap_policy, helper) {
+                        //early return时unwrap直接展开成match，不需要helper函数
+                        (UnwrapPolicy::EarlyReturn, _PreludeHelper::_ResultHelper)
+                        | (UnwrapPolicy::EarlyReturn, _PreludeHelper::_OptionHelper) => {}
+                        (UnwrapPolicy::Unwrap, _PreludeHelper::_ResultHelper) => {
+                            prelude_helpers.insert(_PreludeHelper::_PanicResultHelper);
+                        }
+                        (UnwrapPolicy::Unwrap, _PreludeHelper::_OptionHelper) => {
+                            prelude_helpers.insert(_PreludeHelper::_PanicOptionHelper);
+                        }
+                        (_, helper) => {
+                            prelude_helpers.insert(helper);
+                        }
+                    }
                 }
             }
         }
+        prelude_helpers
+    }
+
+    //target中的helper函数需要fuzz crate额外依赖的crate
+    pub fn _required_dependencies(&self) -> Vec<HelperDependency> {
+        let mut res = Vec::new();
+        for helper in self._prelude_helpers() {
+            res.extend(helper._required_dependencies());
+        }
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    //serde compat的helper函数检查的golden形式，只放入序列中用到的类型的
+    fn _serde_golden_definition(&self, _api_graph: &ApiGraph) -> String {
+        let checked_types: Vec<&String> = self
+            .functions
+            .iter()
+            .map(|api_call| &_api_graph.api_functions[api_call.func.1])
+            .filter(|api_function| api_function._helper == Some(_PreludeHelper::_SerdeCompatHelper))
+            .map(|api_function| &api_function.full_name)
+            .collect();
+        let mut res = String::new();
+        res.push_str("static _SERDE_GOLDEN: &[(&str, &str)] = &[\n");
+        for (type_name, form) in &_api_graph.serde_golden {
+            //golden中是定义处的路径，函数名中可能是重新导出的路径，只比较最后一段
+            let type_last_name = type_name.rsplit("::").next().unwrap_or_default();
+            let is_checked = checked_types
+                .iter()
+                .any(|full_name| full_name.ends_with(format!("::{}>", type_last_name).as_str()));
+            if is_checked {
+                res.push_str(format!("    ({:?}, {:?}),\n", type_name, form).as_str());
+            }
+        }
+        res.push_str("];\n");
+        res
+    }
+
+    //derived impl检查的类型实现_DerivedNest，有嵌套api的类型用它加深嵌套
+    fn _derived_nest_definition(&self, _api_graph: &ApiGraph) -> String {
+        let mut checked_types = BTreeMap::new();
+        for api_call in &self.functions {
+            let api_function = &_api_graph.api_functions[api_call.func.1];
+            match api_function._helper {
+                Some(_PreludeHelper::_DerivedHelper) | Some(_PreludeHelper::_DerivedEqHelper) => {}
+                _ => continue,
+            }
+            let def_id = match api_function.inputs.first() {
+                Some(clean::Type::BorrowedRef { type_, .. }) => type_.def_id(),
+                _ => None,
+            };
+            let def_id = match def_id {
+                Some(def_id) => def_id,
+                None => continue,
+            };
+            if let Some(type_name) = _api_graph.full_name_map._get_full_name(&def_id) {
+                checked_types.insert(type_name.clone(), def_id);
+            }
+        }
+        let mut res = String::new();
+        res.push_str(prelude_type::_derived_nest_definition());
+        for (type_name, def_id) in checked_types {
+            let nest_function = match _api_graph._nest_call_string(&def_id, "self") {
+                Some(nest_call) => {
+                    format!("    fn _nest(self) -> Self {{\n        {}\n    }}\n", nest_call)
+                }
+                None => String::new(),
+            };
+            res.push_str(
+                format!("impl _DerivedNest for {} {{\n{}}}\n", type_name, nest_function).as_str(),
+            );
+        }
+        res
+    }
+
+    pub fn _prelude_helper_functions(&self) -> Option<String> {
+        let prelude_helpers = self._prelude_helpers();
         if prelude_helpers.len() == 0 {
             return None;
         }
@@ -422,38 +965,227 @@ impl ApiSequence {
                 for afl_helper in &afl_helpers {
                     res.push_str(format!("{}\n", afl_helper).as_str());
                 }
+                if self._uses_length_prefixed_sections() {
+                    res.push_str(format!("{}\n", afl_util::_split_sections_function()).as_str());
+                }
                 Some(res)
             }
         }
     }
 
-    pub fn _afl_main_function(&self, test_index: usize) -> String {
+    pub fn _afl_main_function(&self, test_index: usize, sandbox: bool) -> String {
         let mut res = String::new();
         let indent = _generate_indent(4);
         res.push_str("fn main() {\n");
+        if sandbox {
+            res.push_str(sandbox_util::_enter_sandbox_statement(indent.as_str()).as_str());
+        }
+        res.push_str(
+            format!(
+                "{indent}if std::env::args().any(|_arg| _arg == \"--check-harness\") {{
+{indent}    _check_harness();
+{indent}    return;
+{indent}}}
+{indent}if std::env::args().any(|_arg| _arg == \"--dry-run\") {{
+{indent}    _dry_run();
+{indent}    return;
+{indent}}}\n",
+                indent = indent
+            )
+            .as_str(),
+        );
+        let deferred_init = afl_util::aflpp_variants_requested();
+        if deferred_init {
+            res.push_str(format!("{}_afl_deferred_init();\n", indent).as_str());
+        }
         res.push_str(indent.as_str());
         res.push_str("fuzz!(|data: &[u8]| {\n");
         res.push_str(self._afl_closure_body(4, test_index).as_str());
         res.push_str(indent.as_str());
         res.push_str("});\n");
         res.push_str("}\n");
+        res.push_str(self._check_harness_function().as_str());
+        res.push_str(self._dry_run_function(test_index).as_str());
+        if deferred_init {
+            res.push_str(afl_util::_deferred_init_function());
+        }
+        res
+    }
+
+    //--dry-run: 用_viable_input运行一次整个序列，执行到序列末尾时打印一行标记。
+    //解析参数或者unwrap失败时会直接退出，没有这行输出的target很可能需要更多的fuzz才能到达末尾
+    pub fn _dry_run_function(&self, test_index: usize) -> String {
+        let viable_input: Vec<String> =
+            self._viable_input().iter().map(|byte| byte.to_string()).collect();
+        format!(
+            "fn _dry_run() {{
+    let _input: Vec<u8> = vec![{viable_input}];
+    let data = &_input[..];
+{closure_body}    println!(\"{dry_run_tail}\");
+}}\n",
+            viable_input = viable_input.join(", "),
+            closure_body = self._afl_closure_body(0, test_index),
+            dry_run_tail = DRY_RUN_TAIL
+        )
+    }
+
+    //--check-harness: 用全0和全0xff的输入解析参数并打印，不调用被测api，
+    //用来在fuzz之前发现harness本身的问题(比如解析参数时panic)
+    pub fn _check_harness_function(&self) -> String {
+        let min_len = self._fuzzables_min_length();
+        let check_len = if self._is_fuzzables_fixed_length() { min_len } else { min_len + 64 };
+        let mut res = String::new();
+        res.push_str("fn _check_harness() {\n");
+        res.push_str("    let _decode = |data: &[u8]| {\n");
+        if self._uses_args_struct() {
+            res.push_str(
+                "        let _args = match _Args::_decode(data) {
+            Some(_args) => _args,
+            None => {
+                println!(\"_args = None\");
+                return;
+            }
+        };\n",
+            );
+            for i in 0..self.fuzzable_params.len() {
+                res.push_str(
+                    self._check_harness_param(8, i, format!("_args._param{}", i)).as_str(),
+                );
+            }
+        } else {
+            res.push_str(self._afl_param_decoding(4, "return;").as_str());
+            for i in 0..self.fuzzable_params.len() {
+                res.push_str(self._check_harness_param(8, i, format!("_param{}", i)).as_str());
+            }
+        }
+        res.push_str("    };\n");
+        res.push_str(
+            format!(
+                "    for _byte in &[0u8, 0xffu8] {{
+        println!(\"check harness with {check_len} bytes of {{:#04x}}\", _byte);
+        _decode(&vec![*_byte; {check_len}]);
+    }}
+    println!(\"harness ok\");
+}}\n",
+                check_len = check_len
+            )
+            .as_str(),
+        );
         res
     }
 
-    pub fn _reproduce_main_function(&self, test_index: usize) -> String {
+    //打印参数的类型，只有std中的类型才打印值，被测crate中的类型不一定实现了Debug
+    fn _check_harness_param(
+        &self,
+        outer_indent: usize,
+        param_index: usize,
+        param: String,
+    ) -> String {
+        let fuzzable_param = &self.fuzzable_params[param_index];
+        let type_string = fuzzable_param._to_type_string().replace("{", "{{").replace("}", "}}");
+        if fuzzable_param._is_debug_printable() {
+            format!(
+                "{indent}println!(\"_param{i}: {type_string} = {{:?}}\", {param});\n",
+                indent = _generate_indent(outer_indent),
+                i = param_index,
+                type_string = type_string,
+                param = param
+            )
+        } else {
+            format!(
+                "{indent}println!(\"_param{i}: {type_string}\");\n",
+                indent = _generate_indent(outer_indent),
+                i = param_index,
+                type_string = type_string
+            )
+        }
+    }
+
+    //crash文件按原来的当前目录读取之后才进入sandbox
+    pub fn _reproduce_main_function(&self, test_index: usize, sandbox: bool) -> String {
+        let enter_sandbox =
+            if sandbox { sandbox_util::_enter_sandbox_statement("    ") } else { String::new() };
         format!(
             "fn main() {{
     let _content = _read_data();
-    let data = &_content;
+{}    let data = &_content;
     println!(\"data = {{:?}}\", data);
     println!(\"data len = {{:?}}\", data.len());
 {}
 }}",
+            enter_sandbox,
             self._afl_closure_body(0, test_index)
         )
     }
 
+    //外部的fuzz driver(自定义调度器、快照fuzzer等)链接target后直接调用rulf_run，不经过afl的main。
+    //返回0表示执行完整个序列，-1表示输入不足以解析出参数；api中的panic会abort，和afl下的行为一致
+    pub fn _c_entry_function(&self, test_index: usize, sandbox: bool) -> String {
+        let enter_sandbox = if sandbox {
+            sandbox_util::_enter_sandbox_once_statement("    ")
+        } else {
+            String::new()
+        };
+        format!(
+            "#[no_mangle]
+pub extern \"C\" fn {c_entry}(data: *const u8, len: usize) -> i32 {{
+    if data.is_null() {{
+        return -1;
+    }}
+    let data = unsafe {{ std::slice::from_raw_parts(data, len) }};
+{enter_sandbox}    let _run = std::panic::AssertUnwindSafe(|| -> i32 {{
+{call_body}        0
+    }});
+    match std::panic::catch_unwind(_run) {{
+        Ok(_code) => _code,
+        Err(_) => std::process::abort(),
+    }}
+}}\n",
+            c_entry = C_ENTRY_NAME,
+            enter_sandbox = enter_sandbox,
+            call_body = self._afl_call_body(4, test_index, "return -1;")
+        )
+    }
+
     pub fn _afl_closure_body(&self, outer_indent: usize, test_index: usize) -> String {
+        self._afl_call_body(outer_indent, test_index, "return;")
+    }
+
+    //解析参数并调用test function，early_return: 输入不足以解析参数时退出的语句
+    fn _afl_call_body(&self, outer_indent: usize, test_index: usize, early_return: &str) -> String {
+        let extra_indent = 4;
+        let indent = _generate_indent(outer_indent + extra_indent);
+        if self._uses_args_struct() {
+            return format!(
+                "{indent}let _args = match _Args::_decode(data) {{
+{indent}    Some(_args) => _args,
+{indent}    None => {{{early_return}}}
+{indent}}};
+{indent}test_function{test_index}(_args);\n",
+                indent = indent,
+                early_return = early_return,
+                test_index = test_index
+            );
+        }
+        let mut res = self._afl_param_decoding(outer_indent, early_return);
+        let fuzzable_param_number = self.fuzzable_params.len();
+        let mut test_function_call =
+            format!("{indent}test_function{test_index}(", indent = indent, test_index = test_index);
+        for i in 0..fuzzable_param_number {
+            if i != 0 {
+                test_function_call.push_str(" ,");
+            }
+            test_function_call.push_str(format!("_param{}", i).as_str());
+        }
+        test_function_call.push_str(");\n");
+        res.push_str(test_function_call.as_str());
+
+        res
+    }
+
+    //从data中解析出所有的fuzzable参数_param0, _param1...
+    //early_return: 输入长度不够时退出的语句
+    pub fn _afl_param_decoding(&self, outer_indent: usize, early_return: &str) -> String {
         let extra_indent = 4;
         let mut res = String::new();
         let indent = _generate_indent(outer_indent + extra_indent);
@@ -463,24 +1195,40 @@ impl ApiSequence {
         let min_len = self._fuzzables_min_length();
         res.push_str(
             format!(
-                "{indent}if data.len() {op} {min_len} {{return;}}\n",
+                "{indent}if data.len() {op} {min_len} {{{early_return}}}\n",
                 indent = indent,
                 op = op,
-                min_len = min_len
+                min_len = min_len,
+                early_return = early_return
             )
             .as_str(),
         );
 
         let dynamic_param_start_index = self._fuzzable_fixed_part_length();
         let dynamic_param_number = self._dynamic_length_param_number();
-        let dynamic_length_name = "dynamic_length";
+        let dynamic_length_name = if self._uses_length_prefixed_sections() {
+            afl_util::_SECTIONS
+        } else {
+            "dynamic_length"
+        };
         let every_dynamic_length = format!(
             "let {dynamic_length_name} = (data.len() - {dynamic_param_start_index}) / {dynamic_param_number}",
             dynamic_length_name = dynamic_length_name,
             dynamic_param_start_index = dynamic_param_start_index,
             dynamic_param_number = dynamic_param_number
         );
-        if !self._is_fuzzables_fixed_length() {
+        if self._uses_length_prefixed_sections() {
+            res.push_str(
+                format!(
+                    "{indent}let {sections} = _split_sections(data, {dynamic_param_start_index}, {dynamic_param_number});\n",
+                    indent = indent,
+                    sections = afl_util::_SECTIONS,
+                    dynamic_param_start_index = dynamic_param_start_index,
+                    dynamic_param_number = dynamic_param_number
+                )
+                .as_str(),
+            );
+        } else if !self._is_fuzzables_fixed_length() {
             res.push_str(
                 format!(
                     "{indent}{every_dynamic_length};\n",
@@ -519,18 +1267,6 @@ impl ApiSequence {
             dynamic_param_index =
                 dynamic_param_index + fuzzable_param._dynamic_length_param_number();
         }
-
-        let mut test_function_call =
-            format!("{indent}test_function{test_index}(", indent = indent, test_index = test_index);
-        for i in 0..fuzzable_param_number {
-            if i != 0 {
-                test_function_call.push_str(" ,");
-            }
-            test_function_call.push_str(format!("_param{}", i).as_str());
-        }
-        test_function_call.push_str(");\n");
-        res.push_str(test_function_call.as_str());
-
         res
     }
 
@@ -560,15 +1296,34 @@ impl ApiSequence {
 
         //加入函数体开头的大括号
         res.push_str("{\n");
+        if self._uses_args_struct() {
+            res.push_str(
+                format!(
+                    "{}let _Args {{ {} }} = _args;\n",
+                    _generate_indent(indent_size + 4),
+                    self._args_field_list(true)
+                )
+                .as_str(),
+            );
+        }
+
+        //调用了async api的话，函数体放在async块中，由_block_on执行
+        let is_async = self._is_async();
+        let body_indent_size = if is_async { indent_size + 4 } else { indent_size };
+        if is_async {
+            let async_indent = _generate_indent(indent_size + 4);
+            res.push_str(async_indent.as_str());
+            res.push_str("_block_on(async move {\n");
+        }
 
         //加入函数体
         if self._unsafe_tag {
-            let unsafe_indent = _generate_indent(indent_size + 4);
+            let unsafe_indent = _generate_indent(body_indent_size + 4);
             res.push_str(unsafe_indent.as_str());
             res.push_str("unsafe {\n");
             let unsafe_function_body = self._generate_function_body_string(
                 _api_graph,
-                indent_size + 4,
+                body_indent_size + 4,
                 param_prefix,
                 local_param_prefix,
             );
@@ -578,13 +1333,19 @@ impl ApiSequence {
         } else {
             let function_body = self._generate_function_body_string(
                 _api_graph,
-                indent_size,
+                body_indent_size,
                 param_prefix,
                 local_param_prefix,
             );
             res.push_str(function_body.as_str());
         }
 
+        if is_async {
+            let async_indent = _generate_indent(indent_size + 4);
+            res.push_str(async_indent.as_str());
+            res.push_str("});\n");
+        }
+
         //加入函数体结尾的大括号
         let braket_indent = _generate_indent(indent_size);
         res.push_str(braket_indent.as_str());
@@ -633,6 +1394,10 @@ impl ApiSequence {
         res.push_str(test_function_title);
         res.push_str(test_index.to_string().as_str());
         res.push_str("(");
+        if self._uses_args_struct() {
+            res.push_str("_args: _Args) ");
+            return res;
+        }
 
         //加入所有的fuzzable变量
         //第一个参数特殊处理
@@ -681,6 +1446,9 @@ impl ApiSequence {
         let full_name_map = &_api_graph.full_name_map;
         for i in 0..api_calls_num {
             let api_call = &self.functions[i];
+            let api_function_index = api_call.func.1;
+            let api_function = &_api_graph.api_functions[api_function_index];
+            let borrowed_params = api_function._output_borrowed_params();
 
             //准备参数
             let param_size = api_call.params.len();
@@ -704,7 +1472,15 @@ impl ApiSequence {
                 let call_type_array_len = call_type_array.len();
                 if call_type_array_len == 1 {
                     let call_type = &call_type_array[0];
-                    let param_string = call_type._to_call_string(&param_name, full_name_map);
+                    let param_string = _borrowed_param_string(
+                        call_type,
+                        &param_name,
+                        borrowed_params.contains(&j),
+                        format!("{}{}_param{}_owner", local_param_prefix, i, j),
+                        &body_indent,
+                        full_name_map,
+                        &mut res,
+                    );
                     param_strings.push(param_string);
                 } else {
                     let mut former_param_name = param_name.clone();
@@ -720,7 +1496,7 @@ impl ApiSequence {
                             "{}let mut {} = {};\n",
                             body_indent,
                             helper_name,
-                            call_type._to_call_string(&former_param_name, full_name_map)
+                            self._unwrap_call_string(call_type, &former_param_name, full_name_map)
                         );
                         if helper_index > 1 {
                             if !api_util::_need_mut_tag(call_type) {
@@ -737,41 +1513,290 @@ impl ApiSequence {
                         former_helper_line = former_helper_line.replace("let mut ", "let ");
                     }
                     res.push_str(former_helper_line.as_str());
-                    let param_string =
-                        last_call_type._to_call_string(&former_param_name, full_name_map);
+                    let param_string = _borrowed_param_string(
+                        last_call_type,
+                        &former_param_name,
+                        borrowed_params.contains(&j),
+                        format!("{}{}_param{}_owner", local_param_prefix, i, j),
+                        &body_indent,
+                        full_name_map,
+                        &mut res,
+                    );
                     param_strings.push(param_string);
                 }
             }
+            //存在长度/下标关系的参数根据buffer的长度生成
+            let relation_lines = self._apply_param_relations(
+                _api_graph,
+                api_call,
+                i,
+                param_prefix,
+                local_param_prefix,
+                &mut param_strings,
+            );
+            for relation_line in &relation_lines {
+                res.push_str(format!("{}{}\n", body_indent, relation_line).as_str());
+            }
             res.push_str(body_indent.as_str());
             //如果不是最后一个调用
-            let api_function_index = api_call.func.1;
-            let api_function = &_api_graph.api_functions[api_function_index];
-            if dead_code[i] || api_function._has_no_output() {
+            let length_checks = self._returned_length_checks(
+                api_function,
+                api_call,
+                i,
+                param_prefix,
+                local_param_prefix,
+            );
+            if length_checks.is_empty() && (dead_code[i] || api_function._has_no_output()) {
                 res.push_str("let _ = ");
             } else {
                 let mut_tag = if self._is_function_need_mut_tag(i) { "mut " } else { "" };
                 res.push_str(format!("let {}{}{} = ", mut_tag, local_param_prefix, i).as_str());
             }
-            let (api_type, function_index) = &api_call.func;
-            match api_type {
-                ApiType::BareFunction => {
-                    let api_function_full_name =
-                        &_api_graph.api_functions[*function_index].full_name;
-                    res.push_str(api_function_full_name.as_str());
-                }
+            res.push_str(
+                _call_string(&api_function.full_name, &param_strings, &_api_graph.full_name_map)
+                    .as_str(),
+            );
+            if _api_graph._is_async_function(&api_function.full_name) {
+                res.push_str(".await");
+            }
+            res.push_str(";\n");
+            for length_check in &length_checks {
+                res.push_str(format!("{}{}\n", body_indent, length_check).as_str());
             }
-            res.push('(');
+        }
+        if let Some(selector_index) = self._stateful_selector {
+            res.push_str(
+                self._stateful_loop(
+                    _api_graph,
+                    &body_indent,
+                    selector_index,
+                    param_prefix,
+                    local_param_prefix,
+                )
+                .as_str(),
+            );
+        }
+        res
+    }
+
+    //每个输入字节选择一个&mut self方法，在序列中最后一个返回值上依次调用
+    fn _stateful_loop(
+        &self,
+        _api_graph: &ApiGraph,
+        body_indent: &str,
+        selector_index: usize,
+        param_prefix: &str,
+        local_param_prefix: &str,
+    ) -> String {
+        let full_name_map = &_api_graph.full_name_map;
+        let mut res = String::new();
+        res.push_str(
+            format!(
+                "{}for _op in {}{}.iter().take({}) {{\n",
+                body_indent, param_prefix, selector_index, MAX_STATEFUL_STEPS
+            )
+            .as_str(),
+        );
+        let stateful_calls_num = self._stateful_calls.len();
+        res.push_str(
+            format!("{}    match *_op as usize % {} {{\n", body_indent, stateful_calls_num)
+                .as_str(),
+        );
+        for (i, api_call) in self._stateful_calls.iter().enumerate() {
+            let api_function = &_api_graph.api_functions[api_call.func.1];
+            let param_strings: Vec<String> = api_call
+                .params
+                .iter()
+                .map(|(param_type, index, call_type)| {
+                    let param_name = match param_type {
+                        ParamType::_FuzzableType => format!("{}{}", param_prefix, index),
+                        ParamType::_FunctionReturn => format!("{}{}", local_param_prefix, index),
+                    };
+                    call_type._to_call_string(&param_name, full_name_map)
+                })
+                .collect();
+            let arm = if i == stateful_calls_num - 1 { "_".to_string() } else { i.to_string() };
+            res.push_str(
+                format!(
+                    "{}        {} => {{\n{}            let _ = {};\n{}        }}\n",
+                    body_indent,
+                    arm,
+                    body_indent,
+                    _call_string(&api_function.full_name, &param_strings, full_name_map),
+                    body_indent
+                )
+                .as_str(),
+            );
+        }
+        res.push_str(format!("{}    }}\n", body_indent).as_str());
+        res.push_str(format!("{}}}\n", body_indent).as_str());
+        res
+    }
 
-            let param_size = param_strings.len();
-            for k in 0..param_size {
-                if k != 0 {
-                    res.push_str(" ,");
+    //名字表明返回写入的长度(见api_util::_returns_written_length)并且只有一个输出buffer的api，
+    //返回的长度不应该超过buffer的长度。
+    //undersized的buffer不检查，很多api在buffer不够时会返回所需的长度
+    fn _returned_length_checks(
+        &self,
+        api_function: &ApiFunction,
+        api_call: &ApiCall,
+        call_index: usize,
+        param_prefix: &str,
+        local_param_prefix: &str,
+    ) -> Vec<String> {
+        if api_function.output != Some(clean::Type::Primitive(PrimitiveType::Usize))
+            || !api_util::_returns_written_length(&api_function.full_name)
+        {
+            return Vec::new();
+        }
+        let mut buffers = Vec::new();
+        for (param_type, index, _) in &api_call.params {
+            if let ParamType::_FuzzableType = param_type {
+                if let FuzzableType::MutBuffer { undersized, .. } = &self.fuzzable_params[*index] {
+                    buffers.push((*undersized, *index));
                 }
+            }
+        }
+        //有多个输出buffer时不知道返回值对应哪一个
+        match buffers.as_slice() {
+            [(false, index)] => vec![format!(
+                "assert!({}{} <= {}{}.len());",
+                local_param_prefix, call_index, param_prefix, index
+            )],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn _signature(&self, _api_graph: &ApiGraph) -> String {
+        let function_names: Vec<&str> = self
+            .functions
+            .iter()
+            .map(|api_call| _api_graph.api_functions[api_call.func.1].full_name.as_str())
+            .collect();
+        let mut signature = function_names.join(" -> ");
+        if !self._stateful_calls.is_empty() {
+            let stateful_names: Vec<&str> = self
+                ._stateful_calls
+                .iter()
+                .map(|api_call| _api_graph.api_functions[api_call.func.1].full_name.as_str())
+                .collect();
+            signature.push_str(format!(" -> ({})*", stateful_names.join(" | ")).as_str());
+        }
+        if self._violate_param_relations {
+            signature.push_str(" (adversarial)");
+        }
+        match self._unwrap_policy {
+            UnwrapPolicy::Exit => {}
+            UnwrapPolicy::Unwrap => signature.push_str(" (unwrap)"),
+            UnwrapPolicy::EarlyReturn => signature.push_str(" (early-return)"),
+        }
+        signature
+    }
+
+    //unsafe函数的参数关系是调用者必须满足的前提，违反时是调用者的UB而不是bug，adversarial变体不违反它们
+    pub fn _uses_param_relations(&self, _api_graph: &ApiGraph) -> bool {
+        self.functions.iter().any(|api_call| {
+            let (_, index) = api_call.func;
+            let api_function = &_api_graph.api_functions[index];
+            !api_function._param_relations.is_empty() && !api_function._unsafe_tag._is_unsafe()
+        })
+    }
+
+    pub fn _uses_mut_buffers(&self) -> bool {
+        self.fuzzable_params
+            .iter()
+            .any(|fuzzable_param| matches!(fuzzable_param, FuzzableType::MutBuffer { .. }))
+    }
+
+    //adversarial变体：违反参数之间的关系，并且把输出buffer换成过小的buffer
+    pub fn _adversarial_variant(&self) -> Self {
+        let mut adversarial_sequence = self.clone();
+        adversarial_sequence._violate_param_relations = true;
+        for fuzzable_param in &mut adversarial_sequence.fuzzable_params {
+            if let FuzzableType::MutBuffer { undersized, .. } = fuzzable_param {
+                *undersized = true;
+            }
+        }
+        adversarial_sequence
+    }
+
+    pub fn _uses_unwrap(&self) -> bool {
+        self.functions.iter().any(|api_call| {
+            api_call.params.iter().any(|(_, _, call_type)| call_type._contains_unwrap_call_type())
+        })
+    }
+
+    pub fn _unwrap_policy_variant(&self, unwrap_policy: UnwrapPolicy) -> Self {
+        let mut policy_sequence = self.clone();
+        policy_sequence._unwrap_policy = unwrap_policy;
+        policy_sequence
+    }
+
+    //拆分之后unwrap总是helper行最外层的调用。early return时直接展开成match，
+    //Err或None时结束test_function，跳过后面的调用
+    fn _unwrap_call_string(
+        &self,
+        call_type: &CallType,
+        param_name: &String,
+        full_name_map: &FullNameMap,
+    ) -> String {
+        match (self._unwrap_policy, call_type) {
+            (UnwrapPolicy::EarlyReturn, CallType::_UnwrapResult(inner_call_type)) => format!(
+                "match {} {{ Ok(_t) => _t, Err(_) => return }}",
+                inner_call_type._to_call_string(param_name, full_name_map)
+            ),
+            (UnwrapPolicy::EarlyReturn, CallType::_UnwrapOption(inner_call_type)) => format!(
+                "match {} {{ Some(_t) => _t, None => return }}",
+                inner_call_type._to_call_string(param_name, full_name_map)
+            ),
+            _ => call_type._to_call_string(param_name, full_name_map),
+        }
+    }
 
-                let param_string = &param_strings[k];
-                res.push_str(param_string.as_str());
+    //只处理两个参数都是fuzzable变量的情况，先用let算出buffer的长度，避免和可变借用冲突。
+    //buffer为空时没有合法的下标，跳过后面的调用(unsafe api用越界的下标是UB)
+    fn _apply_param_relations(
+        &self,
+        _api_graph: &ApiGraph,
+        api_call: &ApiCall,
+        call_index: usize,
+        param_prefix: &str,
+        local_param_prefix: &str,
+        param_strings: &mut Vec<String>,
+    ) -> Vec<String> {
+        let mut res = Vec::new();
+        let (_, function_index) = api_call.func;
+        let api_function = &_api_graph.api_functions[function_index];
+        let violate_param_relations =
+            self._violate_param_relations && !api_function._unsafe_tag._is_unsafe();
+        for relation in &api_function._param_relations {
+            let (param_type, param_index, _) = &api_call.params[relation.param];
+            let (buffer_type, buffer_index, _) = &api_call.params[relation.buffer];
+            match (param_type, buffer_type) {
+                (ParamType::_FuzzableType, ParamType::_FuzzableType) => {}
+                _ => continue,
+            }
+            let len_name =
+                format!("{}{}_param{}_len", local_param_prefix, call_index, relation.buffer);
+            let len_line = format!("let {} = {}{}.len();", len_name, param_prefix, buffer_index);
+            if !res.contains(&len_line) {
+                res.push(len_line);
             }
-            res.push_str(");\n");
+            let fuzzed_value = format!("{}{}", param_prefix, param_index);
+            if let (ParamRelationKind::_Index, false) = (relation.kind, violate_param_relations) {
+                let empty_line = format!("if {} == 0 {{ return; }}", len_name);
+                if !res.contains(&empty_line) {
+                    res.push(empty_line);
+                }
+            }
+            param_strings[relation.param] = match (relation.kind, violate_param_relations) {
+                (ParamRelationKind::_Len, false) => len_name,
+                (ParamRelationKind::_Len, true) => format!("{} + 1", len_name),
+                (ParamRelationKind::_Index, false) => format!("{} % {}", fuzzed_value, len_name),
+                (ParamRelationKind::_Index, true) => {
+                    format!("{} + {} % 8", len_name, fuzzed_value)
+                }
+            };
         }
         res
     }
@@ -784,3 +1809,47 @@ pub fn _generate_indent(indent_size: usize) -> String {
     }
     indent
 }
+
+//返回值借用的参数如果是参数表达式中的临时值(如&(x as u32))，调用结束时就会被释放，
+//运算符trait的方法写成运算符，其他api写成函数调用
+fn _call_string(
+    full_name: &String,
+    param_strings: &Vec<String>,
+    full_name_map: &FullNameMap,
+) -> String {
+    if let Some(operator_call) = impl_util::_operator_call_string(full_name, param_strings) {
+        return operator_call;
+    }
+    if let Some(variant_call) = full_name_map._variant_call_string(full_name, param_strings) {
+        return variant_call;
+    }
+    format!("{}({})", full_name, param_strings.join(" ,"))
+}
+
+//先把临时值绑定到一个变量上，让它和借用它的返回值活得一样长
+fn _borrowed_param_string(
+    call_type: &CallType,
+    param_name: &String,
+    is_borrowed: bool,
+    owner_name: String,
+    body_indent: &str,
+    full_name_map: &FullNameMap,
+    res: &mut String,
+) -> String {
+    let (mutable, inner_call_type) = match call_type._borrowed_temporary() {
+        Some(borrowed_temporary) if is_borrowed => borrowed_temporary,
+        _ => return call_type._to_call_string(param_name, full_name_map),
+    };
+    let mut_tag = if mutable { "mut " } else { "" };
+    res.push_str(
+        format!(
+            "{}let {}{} = {};\n",
+            body_indent,
+            mut_tag,
+            owner_name,
+            inner_call_type._to_call_string(param_name, full_name_map)
+        )
+        .as_str(),
+    );
+    format!("&{}{}", mut_tag, owner_name)
+}
@@ -1,9 +1,15 @@
 use crate::clean::{self, GetDefId, PrimitiveType};
+use crate::fuzz_target::api_function::{ParamRelation, ParamRelationKind};
 use crate::fuzz_target::call_type::CallType;
+use crate::fuzz_target::file_util;
 use crate::fuzz_target::fuzzable_type::{self, FuzzableCallType};
 use crate::fuzz_target::impl_util::FullNameMap;
 use crate::fuzz_target::prelude_type::{self, PreludeType};
+use rustc_ast::ast;
 use rustc_hir::{self, Mutability};
+use rustc_span::symbol::{sym, Symbol};
+use std::collections::BTreeSet;
+use std::path::Path;
 
 pub fn _extract_input_types(inputs: &clean::Arguments) -> Vec<clean::Type> {
     let mut input_types = Vec::new();
@@ -21,6 +27,454 @@ pub fn _extract_output_type(output: &clean::FnRetTy) -> Option<clean::Type> {
     }
 }
 
+//async fn在clean中的返回类型是impl Future<Output = T>，当作返回T的api，调用时加上.await
+pub fn _extract_async_output_type(decl: &clean::FnDecl) -> Option<clean::Type> {
+    match decl.sugared_async_return_type() {
+        clean::FnRetTy::Return(clean::Type::Tuple(types)) if types.is_empty() => None,
+        output => _extract_output_type(&output),
+    }
+}
+
+pub fn _is_async(header: &rustc_hir::FnHeader) -> bool {
+    header.asyncness == rustc_hir::IsAsync::Async
+}
+
+//根据参数名推测usize参数是哪个切片或字符串参数的长度/下标，如(buf: &[u8], len: usize)。
+//buf_len只对应名为buf的参数，len、length和index对应前面最近的一个切片或字符串参数。
+//只看参数名，不分析函数体的MIR，名字不符合的长度/下标参数仍然是任意的usize
+pub fn _extract_param_relations(inputs: &clean::Arguments) -> Vec<ParamRelation> {
+    let mut res = Vec::new();
+    let arguments = &inputs.values;
+    for (param, argument) in arguments.iter().enumerate() {
+        if argument.type_ != clean::Type::Primitive(PrimitiveType::Usize) {
+            continue;
+        }
+        let name = argument.name.trim_start_matches('_');
+        let (prefix, kind) = match _split_relation_name(name) {
+            Some(prefix_and_kind) => prefix_and_kind,
+            None => continue,
+        };
+        let is_buffer = |index: &usize| _is_buffer_type(&arguments[*index].type_);
+        let buffer = if prefix.is_empty() {
+            (0..param).rev().find(is_buffer)
+        } else {
+            (0..arguments.len()).filter(is_buffer).find(|index| arguments[*index].name == prefix)
+        };
+        if let Some(buffer) = buffer {
+            res.push(ParamRelation { param, buffer, kind });
+        }
+    }
+    res
+}
+
+//size、count、offset、n这样的名字常常不是某个buffer的长度或下标，不推测关系
+fn _split_relation_name(name: &str) -> Option<(&str, ParamRelationKind)> {
+    match name {
+        "len" | "length" => Some(("", ParamRelationKind::_Len)),
+        "index" => Some(("", ParamRelationKind::_Index)),
+        _ if name.len() > "_len".len() && name.ends_with("_len") => {
+            Some((&name[..name.len() - "_len".len()], ParamRelationKind::_Len))
+        }
+        _ => None,
+    }
+}
+
+//返回写入或读出的元素个数的api，名字以这些词开头或者以_into结尾，如encode_into。
+//其他返回usize的api可能返回需要的长度或者别的数，不能和输出buffer的长度比较
+static WRITTEN_LENGTH_PREFIXES: [&str; 6] = ["read", "write", "encode", "decode", "fill", "copy"];
+
+pub fn _returns_written_length(full_name: &str) -> bool {
+    let name = full_name.rsplit("::").next().unwrap_or(full_name).trim_start_matches('_');
+    WRITTEN_LENGTH_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) || name.ends_with("_into")
+}
+
+fn _is_buffer_type(ty_: &clean::Type) -> bool {
+    match ty_ {
+        clean::Type::BorrowedRef { type_, .. } => match &**type_ {
+            clean::Type::Slice(..) | clean::Type::Primitive(PrimitiveType::Str) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+//库作者在源码中用工具属性给出的提示。crate需要注册rulf工具属性，生成时传入--cfg rulf：
+//#![cfg_attr(rulf, feature(register_tool), register_tool(rulf))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulfAnnotation {
+    //#[rulf::fuzz_entry]，优先为这个api选择序列
+    FuzzEntry,
+    //#[rulf::skip]，不为这个api生成target
+    Skip,
+    //#[rulf::construct_with(path)]，被标注的类型只通过path构造
+    ConstructWith(String),
+}
+
+pub fn _rulf_annotations(attrs: &clean::Attributes) -> Vec<RulfAnnotation> {
+    let mut res = Vec::new();
+    for attr in &attrs.other_attrs {
+        let segments = match &attr.kind {
+            ast::AttrKind::Normal(item) => &item.path.segments,
+            ast::AttrKind::DocComment(..) => continue,
+        };
+        if segments.len() != 2 || &*segments[0].ident.as_str() != "rulf" {
+            continue;
+        }
+        match &*segments[1].ident.as_str() {
+            "fuzz_entry" => res.push(RulfAnnotation::FuzzEntry),
+            "skip" => res.push(RulfAnnotation::Skip),
+            "construct_with" => {
+                let constructor = attr
+                    .meta_item_list()
+                    .and_then(|list| list.first().and_then(|nested| nested.meta_item().cloned()));
+                match constructor {
+                    Some(constructor) => {
+                        let path: Vec<String> = constructor
+                            .path
+                            .segments
+                            .iter()
+                            .map(|segment| segment.ident.to_string())
+                            .collect();
+                        res.push(RulfAnnotation::ConstructWith(path.join("::")));
+                    }
+                    None => println!("rulf::construct_with needs a path to the constructor"),
+                }
+            }
+            name => println!("unknown annotation rulf::{}", name),
+        }
+    }
+    res
+}
+
+//const泛型参数默认的实例化值
+static DEFAULT_CONST_GENERIC_VALUES: [u128; 3] = [1, 4, 16];
+
+//通过环境变量打开crate中只在测试时编译的helper函数，如FUZZ_TARGET_HELPER_CFG=test，
+//或者crate专门为fuzzing提供的FUZZ_TARGET_HELPER_CFG=fuzzing
+pub fn helper_cfg() -> Option<String> {
+    match std::env::var("FUZZ_TARGET_HELPER_CFG") {
+        Ok(cfg) if !cfg.is_empty() => Some(cfg),
+        _ => None,
+    }
+}
+
+//选中的被测crate的feature，FUZZ_TARGET_FEATURES=std,serde，用逗号分隔。
+//分析之前由resolve_features把FUZZ_TARGET_ALL_FEATURES和cargo传入的feature合并进来
+pub fn selected_features() -> Vec<String> {
+    let mut features = Vec::new();
+    if let Ok(env_features) = std::env::var("FUZZ_TARGET_FEATURES") {
+        for feature in env_features.split(',') {
+            let feature = feature.trim().to_string();
+            if !feature.is_empty() && !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+    }
+    features
+}
+
+//分析时打开的feature：FUZZ_TARGET_FEATURES中的feature，cargo doc --features通过--cfg feature="x"传入的feature，
+//设置了FUZZ_TARGET_ALL_FEATURES时还有Cargo.toml中声明的所有feature。
+//结果写回FUZZ_TARGET_FEATURES，生成target和Cargo.toml时使用同一组feature
+pub fn resolve_features(input: &Path, cfgs: &[String]) -> Vec<String> {
+    let mut features = selected_features();
+    for cfg in cfgs {
+        let cfg = cfg.replace(' ', "");
+        if cfg.starts_with("feature=\"") && cfg.ends_with('"') {
+            let feature = cfg["feature=\"".len()..cfg.len() - 1].to_string();
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+    }
+    if std::env::var_os("FUZZ_TARGET_ALL_FEATURES").is_some() {
+        let src_root = input.parent().unwrap_or(Path::new(""));
+        match file_util::manifest_features(src_root) {
+            Some(manifest_features) => {
+                for feature in manifest_features {
+                    if !features.contains(&feature) {
+                        features.push(feature);
+                    }
+                }
+            }
+            None => println!(
+                "no Cargo.toml found for {}, only using the selected features",
+                input.display()
+            ),
+        }
+    }
+    if !features.is_empty() {
+        std::env::set_var("FUZZ_TARGET_FEATURES", features.join(","));
+    }
+    features
+}
+
+//默认为pub unsafe fn生成target，调用写在unsafe块中。
+//FUZZ_TARGET_NO_UNSAFE=1时跳过unsafe函数，target中没有unsafe代码
+pub fn skip_unsafe() -> bool {
+    std::env::var_os("FUZZ_TARGET_NO_UNSAFE").is_some()
+}
+
+//实例化const泛型参数时使用的值，FUZZ_TARGET_CONST_GENERIC_VALUES=1,4,16可以改变这些值
+pub fn const_generic_values() -> Vec<u128> {
+    let mut values = Vec::new();
+    if let Ok(env_values) = std::env::var("FUZZ_TARGET_CONST_GENERIC_VALUES") {
+        for value in env_values.split(',') {
+            match value.trim().parse::<u128>() {
+                Ok(value) if !values.contains(&value) => values.push(value),
+                Ok(_) => {}
+                Err(_) => println!("invalid const generic value {}", value),
+            }
+        }
+    }
+    if values.is_empty() { DEFAULT_CONST_GENERIC_VALUES.to_vec() } else { values }
+}
+
+//Result和Option的返回值是Err或None时harness的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnwrapPolicy {
+    Exit,        //直接退出进程，不算作crash
+    Unwrap,      //panic，使预期之外的错误也表现为crash
+    EarlyReturn, //跳过序列中剩下的调用
+}
+
+//FUZZ_TARGET_UNWRAP_POLICY=exit|unwrap|ok-early-return|both，both为每个target生成两种变体
+pub fn unwrap_policies() -> Vec<UnwrapPolicy> {
+    match std::env::var("FUZZ_TARGET_UNWRAP_POLICY") {
+        Ok(policy) => match policy.trim() {
+            "" | "exit" => vec![UnwrapPolicy::Exit],
+            "unwrap" => vec![UnwrapPolicy::Unwrap],
+            "ok-early-return" => vec![UnwrapPolicy::EarlyReturn],
+            "both" => vec![UnwrapPolicy::Unwrap, UnwrapPolicy::EarlyReturn],
+            _ => {
+                println!("invalid unwrap policy {}", policy);
+                vec![UnwrapPolicy::Exit]
+            }
+        },
+        Err(_) => vec![UnwrapPolicy::Exit],
+    }
+}
+
+//item是否只在打开helper cfg时才会被编译
+pub fn _is_helper_cfg_gated(attrs: &clean::Attributes) -> bool {
+    let helper_cfg = match helper_cfg() {
+        Some(cfg) => Symbol::intern(cfg.as_str()),
+        None => return false,
+    };
+    attrs.other_attrs.iter().any(|attr| {
+        attr.has_name(sym::cfg)
+            && attr.meta_item_list().map_or(false, |list| _mentions_cfg(&list, helper_cfg))
+    })
+}
+
+//cfg(not(..))里面的不算
+fn _mentions_cfg(list: &[ast::NestedMetaItem], cfg: Symbol) -> bool {
+    list.iter().any(|item| {
+        if item.check_name(sym::not) {
+            return false;
+        }
+        item.check_name(cfg)
+            || item.meta_item_list().map_or(false, |inner| _mentions_cfg(inner, cfg))
+    })
+}
+
+//cfg中要求的feature，cfg(not(..))里面的不算。如cfg(any(feature = "std", doc))要求std
+pub fn _required_features(attrs: &clean::Attributes) -> BTreeSet<String> {
+    let mut features = BTreeSet::new();
+    for attr in &attrs.other_attrs {
+        if attr.has_name(sym::cfg) {
+            if let Some(list) = attr.meta_item_list() {
+                _collect_features(&list, &mut features);
+            }
+        }
+    }
+    features
+}
+
+fn _collect_features(list: &[ast::NestedMetaItem], features: &mut BTreeSet<String>) {
+    for item in list {
+        if item.check_name(sym::not) {
+            continue;
+        }
+        if item.check_name(sym::feature) {
+            if let Some(feature) = item.value_str() {
+                features.insert(feature.to_string());
+            }
+        } else if let Some(inner) = item.meta_item_list() {
+            _collect_features(inner, features);
+        }
+    }
+}
+
+//item在选中的feature下是否会被编译。分析时打开了cfg(doc)，cfg(any(feature = "x", doc))的item
+//即使没有打开x也会出现，但编译target时不存在。只看feature和doc，其他的cfg已经由rustc判断过了
+pub fn _is_enabled_by_features(attrs: &clean::Attributes) -> bool {
+    let features = selected_features();
+    attrs.other_attrs.iter().all(|attr| {
+        if !attr.has_name(sym::cfg) {
+            return true;
+        }
+        match attr.meta_item_list() {
+            Some(list) if list.len() == 1 => _eval_feature_cfg(&list[0], &features) != Some(false),
+            _ => true,
+        }
+    })
+}
+
+//None表示结果取决于feature和doc以外的cfg
+fn _eval_feature_cfg(item: &ast::NestedMetaItem, features: &Vec<String>) -> Option<bool> {
+    let inner = item.meta_item_list().unwrap_or(&[]);
+    if item.check_name(sym::feature) {
+        let feature = item.value_str()?;
+        Some(features.contains(&feature.to_string()))
+    } else if item.check_name(sym::doc) {
+        Some(false)
+    } else if item.check_name(sym::not) {
+        inner.first().and_then(|inner| _eval_feature_cfg(inner, features)).map(|value| !value)
+    } else if item.check_name(sym::any) {
+        let values: Vec<Option<bool>> =
+            inner.iter().map(|inner| _eval_feature_cfg(inner, features)).collect();
+        if values.contains(&Some(true)) {
+            Some(true)
+        } else if values.contains(&None) {
+            None
+        } else {
+            Some(false)
+        }
+    } else if item.check_name(sym::all) {
+        let values: Vec<Option<bool>> =
+            inner.iter().map(|inner| _eval_feature_cfg(inner, features)).collect();
+        if values.contains(&Some(false)) {
+            Some(false)
+        } else if values.contains(&None) {
+            None
+        } else {
+            Some(true)
+        }
+    } else {
+        None
+    }
+}
+
+//impl Stream<Item = T>中的T，T本身是泛型的话不考虑
+pub fn _impl_stream_item_type(ty: &clean::Type) -> Option<clean::Type> {
+    _impl_trait_item_type(ty, "Stream")
+}
+
+//impl Iterator<Item = T>中的T
+pub fn _impl_iterator_item_type(ty: &clean::Type) -> Option<clean::Type> {
+    _impl_trait_item_type(ty, "Iterator")
+}
+
+fn _impl_trait_item_type(ty: &clean::Type, trait_name: &str) -> Option<clean::Type> {
+    let bounds = match ty {
+        clean::Type::ImplTrait(bounds) => bounds,
+        _ => return None,
+    };
+    for bound in bounds {
+        let trait_path = match bound {
+            clean::GenericBound::TraitBound(
+                clean::PolyTrait { trait_: clean::Type::ResolvedPath { path, .. }, .. },
+                _,
+            ) => path,
+            _ => continue,
+        };
+        let segment = match trait_path.segments.last() {
+            Some(segment) if segment.name == trait_name => segment,
+            _ => continue,
+        };
+        if let clean::GenericArgs::AngleBracketed { bindings, .. } = &segment.args {
+            for binding in bindings {
+                if let clean::TypeBindingKind::Equality { ty: item_type } = &binding.kind {
+                    if binding.name == "Item" && !_is_generic_type(item_type) {
+                        return Some(item_type.clone());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+//enum的一个变体，当作返回这个enum的构造函数。元组变体的字段没有名字
+#[derive(Debug, Clone)]
+pub struct EnumVariant {
+    pub path: String,
+    pub fields: Vec<(Option<String>, clean::Type)>,
+    //String类型的字段改为由&str参数得到，调用时转换成String
+    pub string_fields: Vec<usize>,
+}
+
+impl EnumVariant {
+    pub fn _to_call_string(&self, param_strings: &[String]) -> String {
+        if self.fields.is_empty() {
+            return self.path.clone();
+        }
+        let param_strings: Vec<String> = param_strings
+            .iter()
+            .enumerate()
+            .map(|(i, param_string)| {
+                if self.string_fields.contains(&i) {
+                    format!("{}.to_string()", param_string)
+                } else {
+                    param_string.clone()
+                }
+            })
+            .collect();
+        let named = self.fields.iter().all(|(field_name, _)| field_name.is_some());
+        if !named {
+            return format!("{}({})", self.path, param_strings.join(", "));
+        }
+        let field_strings: Vec<String> = self
+            .fields
+            .iter()
+            .zip(param_strings.iter())
+            .map(|((field_name, _), param_string)| {
+                format!("{}: {}", field_name.as_ref().unwrap(), param_string)
+            })
+            .collect();
+        format!("{} {{ {} }}", self.path, field_strings.join(", "))
+    }
+}
+
+//没有泛型参数的enum中可以在crate外构造的变体，#[non_exhaustive]的变体不能构造
+pub fn _enum_variants(enum_path: &String, enum_: &clean::Enum) -> Option<Vec<EnumVariant>> {
+    if !enum_.generics.params.is_empty() {
+        return None;
+    }
+    let mut variants = Vec::new();
+    for variant_item in &enum_.variants {
+        if variant_item.is_non_exhaustive() {
+            continue;
+        }
+        let (variant_name, variant) = match (&variant_item.name, &variant_item.inner) {
+            (Some(variant_name), clean::VariantItem(variant)) => (variant_name, variant),
+            _ => continue,
+        };
+        let fields = match &variant.kind {
+            clean::VariantKind::CLike => Vec::new(),
+            clean::VariantKind::Tuple(types) => {
+                types.iter().map(|field_type| (None, field_type.clone())).collect()
+            }
+            clean::VariantKind::Struct(variant_struct) => {
+                if variant_struct.fields_stripped {
+                    continue;
+                }
+                let mut fields = Vec::new();
+                for field in &variant_struct.fields {
+                    if let clean::StructFieldItem(field_type) = &field.inner {
+                        fields.push((field.name.clone(), field_type.clone()));
+                    }
+                }
+                fields
+            }
+        };
+        let path = format!("{}::{}", enum_path, variant_name);
+        variants.push(EnumVariant { path, fields, string_fields: Vec::new() });
+    }
+    if variants.is_empty() { None } else { Some(variants) }
+}
+
 pub fn _is_generic_type(ty: &clean::Type) -> bool {
     //TODO：self不需要考虑，因为在产生api function的时候就已经完成转换，但需要考虑类型嵌套的情况
     match ty {
@@ -81,6 +535,32 @@ pub fn _is_generic_type(ty: &clean::Type) -> bool {
     }
 }
 
+//类型中是否出现了关联类型，如<T as Trait>::Name或Self::Name
+pub fn _contains_qpath(ty: &clean::Type) -> bool {
+    match ty {
+        clean::Type::QPath { .. } => true,
+        clean::Type::ResolvedPath { path, .. } => {
+            path.segments.iter().any(|segment| match &segment.args {
+                clean::GenericArgs::AngleBracketed { args, .. } => {
+                    args.iter().any(|arg| match arg {
+                        clean::GenericArg::Type(inner_type) => _contains_qpath(inner_type),
+                        _ => false,
+                    })
+                }
+                clean::GenericArgs::Parenthesized { inputs, output } => {
+                    inputs.iter().any(_contains_qpath) || output.iter().any(_contains_qpath)
+                }
+            })
+        }
+        clean::Type::Tuple(types) => types.iter().any(_contains_qpath),
+        clean::Type::Slice(type_)
+        | clean::Type::Array(type_, ..)
+        | clean::Type::RawPointer(_, type_)
+        | clean::Type::BorrowedRef { type_, .. } => _contains_qpath(type_),
+        _ => false,
+    }
+}
+
 pub fn _is_end_type(ty: &clean::Type, full_name_map: &FullNameMap) -> bool {
     match ty {
         clean::Type::ResolvedPath { .. } => {
@@ -201,6 +681,15 @@ pub fn _same_type_hard_mode(
         _ => {}
     }
 
+    //输入类型是Box<dyn Trait>时，把实现了trait的值装箱
+    if let Some(trait_object) = _boxed_trait_object(input_type, full_name_map) {
+        let inner_call_type = _same_type_hard_mode(output_type, trait_object, full_name_map);
+        return match inner_call_type {
+            CallType::_NotCompatible => CallType::_NotCompatible,
+            _ => CallType::_ToBox(Box::new(inner_call_type)),
+        };
+    }
+
     //考虑输入类型是prelude type的情况，后面就不再考虑
     if prelude_type::_prelude_type_need_special_dealing(input_type, full_name_map) {
         let input_prelude_type = PreludeType::from_type(input_type, full_name_map);
@@ -285,6 +774,12 @@ fn _same_type_resolved_path(
                 return CallType::_DirectCall;
             } else if _resolved_path_equal_without_lifetime(output_type, input_type) {
                 return CallType::_DirectCall;
+            } else if _same_type_without_type_args(output_type, input_type) {
+                //规范化关联类型得到的类型的path和源码中写出的path不同，只比较did
+                return CallType::_DirectCall;
+            } else if _implements_trait_object(output_type, input_type, full_name_map) {
+                //&T和Box<T>在传参时会被强制转换为&dyn Trait和Box<dyn Trait>
+                return CallType::_DirectCall;
             } else {
                 return CallType::_NotCompatible;
             }
@@ -293,6 +788,74 @@ fn _same_type_resolved_path(
     }
 }
 
+//did相同并且都没有类型参数，如Foo和crate::module::Foo
+fn _same_type_without_type_args(ltype: &clean::Type, rtype: &clean::Type) -> bool {
+    match (ltype, rtype) {
+        (
+            clean::Type::ResolvedPath { did: ldid, is_generic: false, param_names: None, .. },
+            clean::Type::ResolvedPath { did: rdid, is_generic: false, param_names: None, .. },
+        ) => ldid == rdid && !_has_type_args(ltype) && !_has_type_args(rtype),
+        _ => false,
+    }
+}
+
+fn _has_type_args(ty_: &clean::Type) -> bool {
+    match ty_ {
+        clean::Type::ResolvedPath { path, .. } => {
+            path.segments.iter().any(|segment| match &segment.args {
+                clean::GenericArgs::AngleBracketed { args, .. } => {
+                    args.iter().any(|arg| matches!(arg, clean::GenericArg::Type(..)))
+                }
+                clean::GenericArgs::Parenthesized { .. } => true,
+            })
+        }
+        _ => false,
+    }
+}
+
+//输入类型是dyn Trait，输出类型是实现了这个trait的crate类型
+fn _implements_trait_object(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    full_name_map: &FullNameMap,
+) -> bool {
+    let (output_def_id, trait_def_id) = match (output_type.def_id(), input_type.def_id()) {
+        (Some(output_def_id), Some(trait_def_id)) => (output_def_id, trait_def_id),
+        _ => return false,
+    };
+    if _is_generic_type(output_type) || !full_name_map._is_trait(&trait_def_id) {
+        return false;
+    }
+    full_name_map._implements_trait(&output_def_id, &trait_def_id)
+}
+
+//Box<dyn Trait>中的dyn Trait
+fn _boxed_trait_object<'a>(
+    input_type: &'a clean::Type,
+    full_name_map: &FullNameMap,
+) -> Option<&'a clean::Type> {
+    let path = match input_type {
+        clean::Type::ResolvedPath { path, .. } => path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.name != "Box" {
+        return None;
+    }
+    let args = match &segment.args {
+        clean::GenericArgs::AngleBracketed { args, .. } => args,
+        _ => return None,
+    };
+    let inner_type = match args.as_slice() {
+        [clean::GenericArg::Type(inner_type)] => inner_type,
+        _ => return None,
+    };
+    match inner_type.def_id() {
+        Some(def_id) if full_name_map._is_trait(&def_id) => Some(inner_type),
+        _ => None,
+    }
+}
+
 //输出类型是Primitive的情况
 fn _same_type_primitive(primitive_type: &PrimitiveType, input_type: &clean::Type) -> CallType {
     match primitive_type {
@@ -575,7 +1138,8 @@ pub fn _move_condition(input_type: &clean::Type, call_type: &CallType) -> bool {
             | CallType::_Deref(..)
             | CallType::_UnsafeDeref(..)
             | CallType::_UnwrapOption(..)
-            | CallType::_UnwrapResult(..) => {
+            | CallType::_UnwrapResult(..)
+            | CallType::_ToBox(..) => {
                 return true;
             }
             _ => {}
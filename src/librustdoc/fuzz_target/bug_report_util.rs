@@ -0,0 +1,137 @@
+use crate::clean;
+use rustc_span::FileName;
+use std::any::Any;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
+static _BUG_REPORT_DIR: &'static str = "generator_bug_reports";
+
+//提取api时遇到的元数据缺失，用其他版本的toolchain生成的元数据可能缺少某些信息。
+//出错的item会被跳过，所有错误在最后汇总成兼容性报告
+#[derive(Clone, Debug)]
+pub enum MetadataError {
+    //item没有名字
+    MissingName { parent: String },
+    //mod item中没有mod的内容
+    MissingModule { mod_path: String },
+    //impl的trait没有def id
+    MissingTraitDefId { impl_name: String },
+    //分析item时generator panic，已经生成了bug report
+    AnalysePanic { item_path: String, message: String },
+}
+
+impl MetadataError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MetadataError::MissingName { .. } => "missing item name",
+            MetadataError::MissingModule { .. } => "missing module content",
+            MetadataError::MissingTraitDefId { .. } => "missing trait def id",
+            MetadataError::AnalysePanic { .. } => "generator panic",
+        }
+    }
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataError::MissingName { parent } => write!(f, "unnamed item in {}", parent),
+            MetadataError::MissingModule { mod_path } => write!(f, "module {}", mod_path),
+            MetadataError::MissingTraitDefId { impl_name } => write!(f, "{}", impl_name),
+            MetadataError::AnalysePanic { item_path, message } => {
+                write!(f, "{}: {}", item_path, message.replace('\n', " "))
+            }
+        }
+    }
+}
+
+//分析某个item时generator本身panic的话，跳过这个item，
+//并把item的源码写到generator_bug_reports/中，作为可以单独复现问题的测试用例。
+//f通常会修改ApiGraph，调用者在f之前取ApiGraph::_checkpoint，返回Err时用_rollback恢复
+pub fn _guard<R>(
+    item_path: &String,
+    span: Option<&clean::Span>,
+    f: impl FnOnce() -> R,
+) -> Result<R, MetadataError> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => Ok(res),
+        Err(payload) => {
+            let message = _panic_message(payload);
+            _write_bug_report(item_path, span, &message);
+            Err(MetadataError::AnalysePanic { item_path: item_path.clone(), message })
+        }
+    }
+}
+
+fn _panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn _write_bug_report(item_path: &String, span: Option<&clean::Span>, message: &String) {
+    let mut report = format!("// fuzz target generator panicked while analysing `{}`\n", item_path);
+    report.push_str(format!("// panic: {}\n", message.replace('\n', " ")).as_str());
+    match span.and_then(|span| _source_of_span(span)) {
+        Some((location, source)) => {
+            report.push_str(format!("// source: {}\n", location).as_str());
+            //item通常用到crate中的其他类型，单独放进空crate不一定能编译
+            report.push_str(
+                "// the item below may use other items of the crate. To reproduce the panic, run\n",
+            );
+            report.push_str(
+                "// the generator on the crate, or on a copy reduced to this item and the items it uses\n\n",
+            );
+            report.push_str(source.as_str());
+            report.push('\n');
+        }
+        None => {
+            report.push_str("// source of the item is not available\n");
+        }
+    }
+
+    let report_dir = PathBuf::from(_BUG_REPORT_DIR);
+    fs::create_dir_all(&report_dir).unwrap();
+    let file_name: String = item_path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    let report_path = report_dir.join(format!("{}.rs", file_name));
+    let mut report_file = fs::File::create(&report_path).unwrap();
+    report_file.write_all(report.as_bytes()).unwrap();
+    println!("generator panicked on {}, bug report written to {:?}", item_path, report_path);
+}
+
+fn _source_of_span(span: &clean::Span) -> Option<(String, String)> {
+    let path = match &span.filename {
+        FileName::Real(path) => path.local_path().to_path_buf(),
+        _ => return None,
+    };
+    let content = fs::read_to_string(&path).ok()?;
+    if span.loline == 0 || span.hiline < span.loline {
+        return None;
+    }
+    let lines: Vec<&str> =
+        content.lines().skip(span.loline - 1).take(span.hiline - span.loline + 1).collect();
+    let location = format!("{}:{}-{}", path.display(), span.loline, span.hiline);
+    Some((location, lines.join("\n")))
+}
+
+//impl块本身的位置没有被记录，用其中第一个和最后一个item的位置代替
+pub fn _impl_span(impl_: &clean::Impl) -> Option<clean::Span> {
+    let first = impl_.items.first()?;
+    let last = impl_.items.last()?;
+    if first.source.filename != last.source.filename {
+        return Some(first.source.clone());
+    }
+    let mut span = first.source.clone();
+    span.hiline = last.source.hiline;
+    span.hicol = last.source.hicol;
+    Some(span)
+}
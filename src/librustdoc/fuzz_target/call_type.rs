@@ -18,6 +18,9 @@ pub enum CallType {
     _ToResult(Box<CallType>),                     //产生一个result类型, never used
     _UnwrapOption(Box<CallType>),                 //获得option变量的值
     _ToOption(Box<CallType>),                     //产生一个option类型
+    _ToBox(Box<CallType>),                        //装箱，Box<具体类型>再转换为Box<dyn Trait>
+    _Into(Box<CallType>, clean::Type),            //通过crate中的From impl转换为目标类型
+    _TryInto(Box<CallType>, clean::Type),         //通过crate中的TryFrom impl转换，返回Result
 }
 
 impl CallType {
@@ -50,7 +53,8 @@ impl CallType {
             }
             CallType::_MutRawPointer(inner_, ty_) => {
                 //TODO:需要转换之后的类型名
-                let mut call_string = "&(".to_string();
+                //&T不能直接转换为*mut T，从可变引用转换
+                let mut call_string = "&mut (".to_string();
                 let inner_call_string = inner_._to_call_string(variable_name, full_name_map);
                 call_string.push_str(inner_call_string.as_str());
                 call_string.push_str(") as *mut ");
@@ -90,6 +94,26 @@ impl CallType {
                 let inner_call_string = inner_._to_call_string(variable_name, full_name_map);
                 format!("Ok({})", inner_call_string)
             }
+            CallType::_ToBox(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, full_name_map);
+                format!("Box::new({})", inner_call_string)
+            }
+            CallType::_Into(inner_, ty_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, full_name_map);
+                format!(
+                    "std::convert::Into::<{}>::into({})",
+                    _type_name(ty_, full_name_map),
+                    inner_call_string
+                )
+            }
+            CallType::_TryInto(inner_, ty_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, full_name_map);
+                format!(
+                    "std::convert::TryInto::<{}>::try_into({})",
+                    _type_name(ty_, full_name_map),
+                    inner_call_string
+                )
+            }
         }
     }
 
@@ -100,8 +124,35 @@ impl CallType {
         }
     }
 
+    //unwrap和into都会消耗掉原来的值
     pub fn _contains_move_call_type(&self) -> bool {
-        self._contains_unwrap_call_type()
+        self._contains_unwrap_call_type() || self._contains_into_call_type()
+    }
+
+    pub fn _contains_into_call_type(&self) -> bool {
+        match self {
+            CallType::_Into(..) | CallType::_TryInto(..) => true,
+            CallType::_NotCompatible | CallType::_DirectCall | CallType::_AsConvert(..) => false,
+            CallType::_BorrowedRef(call_type)
+            | CallType::_MutBorrowedRef(call_type)
+            | CallType::_ConstRawPointer(call_type, _)
+            | CallType::_MutRawPointer(call_type, _)
+            | CallType::_UnsafeDeref(call_type)
+            | CallType::_Deref(call_type)
+            | CallType::_UnwrapOption(call_type)
+            | CallType::_UnwrapResult(call_type)
+            | CallType::_ToOption(call_type)
+            | CallType::_ToResult(call_type)
+            | CallType::_ToBox(call_type) => call_type._contains_into_call_type(),
+        }
+    }
+
+    //把最内层的直接调用换成inner_call_type，如&(x)和into(x)组合成&(into(x))
+    pub fn _with_inner_call_type(&self, inner_call_type: CallType) -> CallType {
+        let mut call_type_array = self._call_type_to_array();
+        call_type_array.pop();
+        call_type_array.append(&mut inner_call_type._call_type_to_array());
+        CallType::_array_to_call_type(&call_type_array)
     }
 
     pub fn _is_unwrap_call_type(&self) -> bool {
@@ -110,6 +161,23 @@ impl CallType {
             _ => false,
         }
     }
+    //引用的对象是在参数表达式中产生的临时值时，返回引用是否可变，以及产生临时值的调用方式
+    pub fn _borrowed_temporary(&self) -> Option<(bool, &CallType)> {
+        let (mutable, inner_) = match self {
+            CallType::_BorrowedRef(inner_) => (false, &**inner_),
+            CallType::_MutBorrowedRef(inner_) => (true, &**inner_),
+            _ => return None,
+        };
+        match inner_ {
+            CallType::_AsConvert(..)
+            | CallType::_ToOption(..)
+            | CallType::_ToResult(..)
+            | CallType::_ToBox(..)
+            | CallType::_Into(..) => Some((mutable, inner_)),
+            _ => None,
+        }
+    }
+
     pub fn _contains_unwrap_call_type(&self) -> bool {
         match self {
             CallType::_NotCompatible | CallType::_DirectCall | CallType::_AsConvert(..) => false,
@@ -121,7 +189,10 @@ impl CallType {
             | CallType::_UnsafeDeref(call_type)
             | CallType::_Deref(call_type)
             | CallType::_ToOption(call_type)
-            | CallType::_ToResult(call_type) => call_type._contains_move_call_type(),
+            | CallType::_ToResult(call_type)
+            | CallType::_ToBox(call_type)
+            | CallType::_Into(call_type, _)
+            | CallType::_TryInto(call_type, _) => call_type._contains_unwrap_call_type(),
         }
     }
 
@@ -139,7 +210,10 @@ impl CallType {
             | CallType::_UnsafeDeref(call_type)
             | CallType::_Deref(call_type)
             | CallType::_ToOption(call_type)
-            | CallType::_ToResult(call_type) => {
+            | CallType::_ToResult(call_type)
+            | CallType::_ToBox(call_type)
+            | CallType::_Into(call_type, _)
+            | CallType::_TryInto(call_type, _) => {
                 let mut call_types = vec![self.clone()];
                 let mut inner_call_types = call_type._call_type_to_array();
                 call_types.append(&mut inner_call_types);
@@ -226,6 +300,11 @@ impl CallType {
             CallType::_ToOption(..) => CallType::_ToOption(Box::new(inner_type)),
             CallType::_UnwrapResult(..) => CallType::_UnwrapResult(Box::new(inner_type)),
             CallType::_ToResult(..) => CallType::_ToResult(Box::new(inner_type)),
+            CallType::_ToBox(..) => CallType::_ToBox(Box::new(inner_type)),
+            CallType::_Into(_, ref type_) => CallType::_Into(Box::new(inner_type), type_.clone()),
+            CallType::_TryInto(_, ref type_) => {
+                CallType::_TryInto(Box::new(inner_type), type_.clone())
+            }
         }
     }
 }
@@ -0,0 +1,187 @@
+use crate::fuzz_target::api_function::ApiFunction;
+use crate::fuzz_target::generator::FunctionFilter;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+//生成器和afl_scripts共用的配置文件，放在被测crate的根目录(或其上层目录)中，
+//或者用FUZZ_TARGET_CONFIG指定路径
+static CONFIG_FILE: &'static str = "fuzz-target-gen.toml";
+//生成器只读[generator]表，其他表(如[afl])留给afl_scripts
+static GENERATOR_TABLE: &'static str = "generator";
+
+//[generator]中的选项 -> 对应的环境变量。环境变量中已经设置的值优先于配置文件
+static CONFIG_ENV_VARS: &'static [(&'static str, &'static str)] = &[
+    ("output_dir", "FUZZ_TARGET_OUTPUT_DIR"),
+    ("fuzzer", "FUZZ_TARGET_FUZZER"),
+    ("max_targets", "FUZZ_TARGET_MAX_TARGETS"),
+    ("pack_bytes", "FUZZ_TARGET_PACK_BYTES"),
+    ("helper_cfg", "FUZZ_TARGET_HELPER_CFG"),
+    ("focus_apis", "FUZZ_TARGET_FOCUS_APIS"),
+    ("usage_corpus", "FUZZ_TARGET_USAGE_CORPUS"),
+    ("serde_compat", "FUZZ_TARGET_SERDE_COMPAT"),
+    ("emit_env", "FUZZ_TARGET_EMIT_ENV"),
+    ("trait_coverage", "FUZZ_TARGET_TRAIT_COVERAGE"),
+    ("format_sweep", "FUZZ_TARGET_FORMAT_SWEEP"),
+    ("custom_mutators", "FUZZ_TARGET_CUSTOM_MUTATORS"),
+    ("adversarial_relations", "FUZZ_TARGET_ADVERSARIAL_RELATIONS"),
+    ("unsafe_report", "FUZZ_TARGET_UNSAFE_REPORT"),
+    ("aflpp_variants", "FUZZ_TARGET_AFLPP_VARIANTS"),
+    ("cargo_fuzz_in_crate", "FUZZ_TARGET_CARGO_FUZZ_IN_CRATE"),
+    ("const_generic_values", "FUZZ_TARGET_CONST_GENERIC_VALUES"),
+    ("unwrap_policy", "FUZZ_TARGET_UNWRAP_POLICY"),
+    ("stateful", "FUZZ_TARGET_STATEFUL"),
+    ("features", "FUZZ_TARGET_FEATURES"),
+    ("all_features", "FUZZ_TARGET_ALL_FEATURES"),
+    ("no_unsafe", "FUZZ_TARGET_NO_UNSAFE"),
+    ("ffi_apis", "FUZZ_TARGET_FFI_APIS"),
+    ("producer_crates", "FUZZ_TARGET_PRODUCER_CRATES"),
+    ("set_cover", "FUZZ_TARGET_SET_COVER"),
+    ("strategy", "FUZZ_TARGET_STRATEGY"),
+    ("sandbox", "FUZZ_TARGET_SANDBOX"),
+    ("no_generic_instances", "FUZZ_TARGET_NO_GENERIC_INSTANCES"),
+    ("hasher_stream", "FUZZ_TARGET_HASHER_STREAM"),
+    ("mir_relations", "FUZZ_TARGET_MIR_RELATIONS"),
+];
+//值是路径的选项，相对路径相对于配置文件所在的目录
+static PATH_OPTIONS: [&'static str; 4] =
+    ["output_dir", "focus_apis", "usage_corpus", "serde_compat"];
+//api的全限定名或者路径前缀的列表，只在配置文件中设置
+static ALLOW_APIS: &'static str = "allow_apis";
+static DENY_APIS: &'static str = "deny_apis";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    Array(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorConfig {
+    pub path: Option<PathBuf>,
+    //[generator]中的所有选项
+    pub options: BTreeMap<String, ConfigValue>,
+}
+
+impl GeneratorConfig {
+    //FUZZ_TARGET_CONFIG，或者从当前目录向上找到的第一个fuzz-target-gen.toml。
+    //没有配置文件时返回空的配置，配置文件读不出来或者有错误时返回错误，不能忽略用户的配置继续生成
+    pub fn load() -> Result<Self, String> {
+        let path = match std::env::var_os("FUZZ_TARGET_CONFIG") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => std::env::current_dir().ok().and_then(|current_dir| {
+                current_dir
+                    .ancestors()
+                    .map(|ancestor| ancestor.join(CONFIG_FILE))
+                    .find(|path| path.is_file())
+            }),
+        };
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(GeneratorConfig::default()),
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let options = parse_table(&content, GENERATOR_TABLE)
+            .map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+        println!("using generator options from {}", path.display());
+        Ok(GeneratorConfig { path: Some(path), options })
+    }
+
+    //把选项写到对应的环境变量中，生成器的其他部分只读环境变量
+    pub fn apply_to_env(&self) {
+        let config_dir = self.path.as_ref().and_then(|path| path.parent()).unwrap_or(Path::new(""));
+        for (name, value) in &self.options {
+            if name == ALLOW_APIS || name == DENY_APIS {
+                continue;
+            }
+            let env_var = match CONFIG_ENV_VARS.iter().find(|(option, _)| *option == name.as_str())
+            {
+                Some((_, env_var)) => *env_var,
+                None => {
+                    println!("unknown option {} in [{}]", name, GENERATOR_TABLE);
+                    continue;
+                }
+            };
+            if std::env::var_os(env_var).is_some() {
+                continue;
+            }
+            let env_value = match value {
+                ConfigValue::Bool(false) => continue,
+                ConfigValue::Bool(true) => "1".to_string(),
+                ConfigValue::Int(value) => value.to_string(),
+                ConfigValue::Array(values) => values.join(","),
+                ConfigValue::Str(value) if PATH_OPTIONS.contains(&name.as_str()) => {
+                    config_dir.join(value).display().to_string()
+                }
+                ConfigValue::Str(value) => value.clone(),
+            };
+            std::env::set_var(env_var, env_value);
+        }
+    }
+
+    //allow_apis不为空时只保留其中列出的api，deny_apis中的api总是去掉
+    pub fn function_filter(&self) -> Option<FunctionFilter> {
+        let allow_apis = self.api_list(ALLOW_APIS);
+        let deny_apis = self.api_list(DENY_APIS);
+        if allow_apis.is_empty() && deny_apis.is_empty() {
+            return None;
+        }
+        Some(Box::new(move |api_function: &ApiFunction| {
+            let full_name = api_function.full_name.as_str();
+            (allow_apis.is_empty() || allow_apis.iter().any(|api| full_name.starts_with(api)))
+                && !deny_apis.iter().any(|api| full_name.starts_with(api))
+        }))
+    }
+
+    fn api_list(&self, name: &str) -> Vec<String> {
+        match self.options.get(name) {
+            Some(ConfigValue::Array(apis)) => apis.clone(),
+            Some(ConfigValue::Str(api)) => vec![api.clone()],
+            Some(_) => {
+                println!("{} should be a list of api paths", name);
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+//读出一个表中的字符串、整数、布尔值和数组，数组中的整数也当作字符串。没有这个表时返回空的选项
+pub fn parse_table(content: &str, table: &str) -> Result<BTreeMap<String, ConfigValue>, String> {
+    let document: toml::Value = content.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    let options = match document.get(table) {
+        Some(toml::Value::Table(options)) => options,
+        Some(_) => return Err(format!("{} is not a table", table)),
+        None => return Ok(BTreeMap::new()),
+    };
+    let mut res = BTreeMap::new();
+    for (key, value) in options {
+        let config_value = parse_value(value)
+            .ok_or_else(|| format!("unsupported value of {}.{}: {}", table, key, value))?;
+        res.insert(key.clone(), config_value);
+    }
+    Ok(res)
+}
+
+fn parse_value(value: &toml::Value) -> Option<ConfigValue> {
+    match value {
+        toml::Value::String(value) => Some(ConfigValue::Str(value.clone())),
+        toml::Value::Integer(value) => Some(ConfigValue::Int(*value)),
+        toml::Value::Boolean(value) => Some(ConfigValue::Bool(*value)),
+        toml::Value::Array(items) => {
+            let mut res = Vec::new();
+            for item in items {
+                match item {
+                    toml::Value::String(item) => res.push(item.clone()),
+                    toml::Value::Integer(item) => res.push(item.to_string()),
+                    _ => return None,
+                }
+            }
+            Some(ConfigValue::Array(res))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,122 @@
+//生成可以在别的机器或CI上复现fuzz环境的构建脚本：固定生成target的toolchain版本、afl.rs版本，
+//并包含输出目录中生成的所有文件
+static GENERATOR_REPO: &'static str = "https://github.com/hyeonminmo/RULF_modify";
+//和scripts/install-and-test-afl中安装的版本一致
+static AFL_VERSION: &'static str = "0.7.0";
+static NIXPKGS_TARBALL: &'static str = "https://github.com/NixOS/nixpkgs/archive/20.09.tar.gz";
+static DOCKER_RECIPE: &'static str = "Dockerfile";
+static NIX_RECIPE: &'static str = "shell.nix";
+
+//x.py编译时会设置CFG_COMMIT_HASH，直接用cargo编译的话没有，只能使用最新的master
+fn _generator_revision() -> &'static str {
+    match option_env!("CFG_COMMIT_HASH") {
+        Some(revision) => revision,
+        None => {
+            println!("generator revision is unknown, the environment recipe uses master");
+            "master"
+        }
+    }
+}
+
+//返回(文件名, 内容)，kind是docker或者nix
+pub fn _env_recipe(kind: &str, crate_name: &String) -> Option<(&'static str, String)> {
+    match kind {
+        "docker" => Some((DOCKER_RECIPE, _docker_recipe(crate_name))),
+        "nix" => Some((NIX_RECIPE, _nix_recipe(crate_name))),
+        _ => None,
+    }
+}
+
+fn _docker_recipe(crate_name: &String) -> String {
+    format!(
+        "# Fuzzing environment for the {crate_name} targets, generated by fuzz-target-generator.
+# Build it from this directory: docker build -t {crate_name}-fuzz .
+FROM ubuntu:20.04
+
+ARG DEBIAN_FRONTEND=noninteractive
+RUN apt-get --yes update \\
+  && apt-get install --no-install-recommends --yes \\
+  build-essential \\
+  ca-certificates \\
+  clang-10 \\
+  cmake \\
+  curl \\
+  git \\
+  libssl-dev \\
+  llvm-10 \\
+  ninja-build \\
+  pkg-config \\
+  python3 \\
+  && apt-get clean
+
+RUN curl --location https://sh.rustup.rs > /tmp/rustup \\
+  && sh /tmp/rustup -y --default-toolchain=none \\
+  && rm /tmp/rustup
+ENV PATH=\"/root/.cargo/bin:${{PATH}}\"
+
+# the toolchain that generated these targets
+RUN git clone {generator_repo} /opt/fuzz-target-generator \\
+  && cd /opt/fuzz-target-generator \\
+  && git checkout {revision} \\
+  && python3 ./x.py build --stage 2 \\
+  && rustup toolchain link stage2 /opt/fuzz-target-generator/build/x86_64-unknown-linux-gnu/stage2 \\
+  && rustup default stage2
+
+RUN cargo install --version {afl_version} afl
+
+COPY . /fuzz/{crate_name}
+WORKDIR /fuzz/{crate_name}
+",
+        crate_name = crate_name,
+        generator_repo = GENERATOR_REPO,
+        revision = _generator_revision(),
+        afl_version = AFL_VERSION
+    )
+}
+
+//nix的沙箱中不能联网，x.py需要下载stage0，所以toolchain在shellHook中第一次进入时编译
+fn _nix_recipe(crate_name: &String) -> String {
+    format!(
+        "# Fuzzing environment for the {crate_name} targets, generated by fuzz-target-generator.
+# Enter it from this directory: nix-shell
+# The first run builds the generator toolchain into ./.fuzz-env, which takes a long time.
+{{ pkgs ? import (fetchTarball \"{nixpkgs_tarball}\") {{ }} }}:
+
+pkgs.mkShell {{
+  name = \"{crate_name}-fuzz\";
+  buildInputs = with pkgs; [
+    cacert
+    clang_10
+    cmake
+    curl
+    git
+    llvm_10
+    ninja
+    openssl
+    pkg-config
+    python3
+    rustup
+  ];
+  shellHook = ''
+    export RUSTUP_HOME=\"$PWD/.fuzz-env/rustup\"
+    export CARGO_HOME=\"$PWD/.fuzz-env/cargo\"
+    export PATH=\"$CARGO_HOME/bin:$PATH\"
+    generator=\"$PWD/.fuzz-env/fuzz-target-generator\"
+    if [ ! -d \"$generator\" ]; then
+      git clone {generator_repo} \"$generator\"
+      git -C \"$generator\" checkout {revision}
+      (cd \"$generator\" && python3 ./x.py build --stage 2)
+      rustup toolchain link stage2 \"$generator/build/x86_64-unknown-linux-gnu/stage2\"
+      rustup default stage2
+      cargo install --version {afl_version} afl
+    fi
+  '';
+}}
+",
+        crate_name = crate_name,
+        nixpkgs_tarball = NIXPKGS_TARBALL,
+        generator_repo = GENERATOR_REPO,
+        revision = _generator_revision(),
+        afl_version = AFL_VERSION
+    )
+}
@@ -0,0 +1,86 @@
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_hir::BodyOwnerKind;
+use rustc_middle::mir::TerminatorKind;
+use rustc_middle::ty::{self, TyCtxt};
+use std::collections::BTreeSet;
+
+//只是转发到外部(C)库的api。用improper_ctypes lint的rustc_lint::is_ffi_function区分FFI函数：
+//extern块中声明的foreign item，以及ABI不是Rust内部ABI的函数(如extern "C" fn)
+#[derive(Clone, Debug, Default)]
+pub struct FfiClassification {
+    //函数 -> 它(以及它创建的闭包)直接调用的FFI函数
+    pub foreign_callees: FxHashMap<DefId, BTreeSet<String>>,
+}
+
+//FUZZ_TARGET_FFI_APIS=keep|deprioritize|exclude
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FfiPolicy {
+    Keep,         //只标记，在target开头注明调用的FFI函数
+    Deprioritize, //调用FFI api的target放在最后，数量达到上限时先被丢掉
+    Exclude,      //不为FFI api生成target
+}
+
+//只在设置了FUZZ_TARGET_FFI_APIS时计算，需要为crate中的每个函数生成optimized MIR。
+//和unsafe_util一样在core::fuzz_target_body_analyses的普通rustc session中计算
+pub fn ffi_classification_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_FFI_APIS").is_some()
+}
+
+pub fn ffi_policy() -> FfiPolicy {
+    match std::env::var("FUZZ_TARGET_FFI_APIS") {
+        Ok(policy) => match policy.trim() {
+            "" | "keep" => FfiPolicy::Keep,
+            "deprioritize" => FfiPolicy::Deprioritize,
+            "exclude" => FfiPolicy::Exclude,
+            _ => {
+                println!("invalid ffi policy {}", policy);
+                FfiPolicy::Keep
+            }
+        },
+        Err(_) => FfiPolicy::Keep,
+    }
+}
+
+pub fn ffi_classification(tcx: TyCtxt<'_>) -> FfiClassification {
+    let mut foreign_callees: FxHashMap<DefId, BTreeSet<String>> = FxHashMap::default();
+    for local_def_id in tcx.body_owners() {
+        let hir_id = tcx.hir().as_local_hir_id(local_def_id);
+        let body_owner_kind = tcx.hir().body_owner_kind(hir_id);
+        if !body_owner_kind.is_fn_or_closure() {
+            continue;
+        }
+        let def_id = local_def_id.to_def_id();
+        let mut callees = _foreign_callees(tcx, def_id);
+        //本身是extern "C" fn的api是给C代码调用的入口
+        if let BodyOwnerKind::Fn = body_owner_kind {
+            if rustc_lint::is_ffi_function(tcx, def_id) {
+                callees.insert(format!("extern {} fn", tcx.fn_sig(def_id).abi()));
+            }
+        }
+        if callees.is_empty() {
+            continue;
+        }
+        //闭包中的调用记在所在的函数上
+        foreign_callees.entry(tcx.closure_base_def_id(def_id)).or_default().extend(callees);
+    }
+    FfiClassification { foreign_callees }
+}
+
+//MIR中直接调用的FFI函数，用函数的路径表示
+fn _foreign_callees(tcx: TyCtxt<'_>, def_id: DefId) -> BTreeSet<String> {
+    let mut res = BTreeSet::new();
+    let body = tcx.optimized_mir(def_id);
+    for basic_block in body.basic_blocks() {
+        let func = match &basic_block.terminator().kind {
+            TerminatorKind::Call { func, .. } => func,
+            _ => continue,
+        };
+        if let ty::FnDef(callee_def_id, _) = func.ty(body, tcx).kind {
+            if rustc_lint::is_ffi_function(tcx, callee_def_id) {
+                res.insert(tcx.def_path_str(callee_def_id));
+            }
+        }
+    }
+    res
+}
@@ -1,8 +1,17 @@
+use crate::fuzz_target::afl_util;
 use crate::fuzz_target::api_graph::ApiGraph;
-use std::collections::HashMap;
+use crate::fuzz_target::api_sequence::ApiSequence;
+use crate::fuzz_target::api_util;
+use crate::fuzz_target::env_util;
+use crate::fuzz_target::ffi_util::{self, FfiPolicy};
+use crate::fuzz_target::generator::{self, FuzzBackend, LibFuzzerBackend};
+use crate::fuzz_target::mutator_util;
+use crate::fuzz_target::usage_util;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 lazy_static! {
     static ref CRATE_TEST_DIR: HashMap<&'static str, &'static str> = {
@@ -69,10 +78,153 @@ lazy_static! {
 static _TEST_FILE_DIR: &'static str = "test_files";
 static _REPRODUCE_FILE_DIR: &'static str = "replay_files";
 static _LIBFUZZER_DIR_NAME: &'static str = "libfuzzer_files";
+//cargo fuzz init生成的布局，初始种子在corpus/<target>/中
+pub static CARGO_FUZZ_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "cargo fuzz",
+    dir: "fuzz",
+    target_dir: "fuzz_targets",
+    target_prefix: "fuzz_target",
+    seed_dir: "corpus/{target}",
+    dependency: "libfuzzer-sys = \"0.4\"",
+    metadata: "[package.metadata]\ncargo-fuzz = true\n",
+    gitignore: "target\ncorpus\nartifacts\n",
+    target_table: "[[bin]]",
+    target_options: "test = false\ndoc = false\n",
+};
+//cargo hfuzz run <target>的默认输入目录是hfuzz_workspace/<target>/input
+pub static HONGGFUZZ_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "cargo hfuzz",
+    dir: "hfuzz",
+    target_dir: "hfuzz_targets",
+    target_prefix: "hfuzz_target",
+    seed_dir: "hfuzz_workspace/{target}/input",
+    dependency: "honggfuzz = \"0.5\"",
+    metadata: "",
+    gitignore: "target\nhfuzz_target\nhfuzz_workspace\n",
+    target_table: "[[bin]]",
+    target_options: "test = false\ndoc = false\n",
+};
+//bolero的target是harness = false的测试，cargo test把它们当作属性测试运行，
+//cargo bolero test从tests/__fuzz__/<target>/corpus中读初始种子
+pub static BOLERO_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "cargo bolero",
+    dir: "bolero",
+    target_dir: "tests",
+    target_prefix: "bolero_target",
+    seed_dir: "tests/__fuzz__/{target}/corpus",
+    dependency: "bolero = \"0.6\"",
+    metadata: "",
+    gitignore: "target\ntests/__fuzz__/*/crashes\n",
+    target_table: "[[test]]",
+    target_options: "harness = false\n",
+};
+//kani的harness不需要种子，kani由cargo kani提供，不需要加入依赖
+pub static KANI_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "cargo kani",
+    dir: "kani",
+    target_dir: "proofs",
+    target_prefix: "kani_target",
+    seed_dir: "",
+    dependency: "",
+    metadata: "",
+    gitignore: "target\n",
+    target_table: "[[bin]]",
+    target_options: "test = false\ndoc = false\n",
+};
+//smoke target是普通的二进制，不需要fuzzer，用seeds/<target>/中的输入运行一遍
+pub static SMOKE_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "smoke",
+    dir: "smoke",
+    target_dir: "smoke_targets",
+    target_prefix: "smoke_target",
+    seed_dir: "seeds/{target}",
+    dependency: "",
+    metadata: "",
+    gitignore: "target\n",
+    target_table: "[[bin]]",
+    target_options: "test = false\ndoc = false\n",
+};
+//afl target所在的fuzz crate由afl_scripts创建，这里只用它的依赖为afl target解析Cargo.lock
+static _AFL_LOCK_PROJECT: FuzzerProject = FuzzerProject {
+    tool: "cargo afl",
+    dir: "",
+    target_dir: "",
+    target_prefix: "test",
+    seed_dir: "",
+    dependency: "afl = \"0.7.0\"",
+    metadata: "",
+    gitignore: "",
+    target_table: "[[bin]]",
+    target_options: "",
+};
+static _CARGO_LOCK: &'static str = "Cargo.lock";
+static _CARGO_TOML: &'static str = "Cargo.toml";
+static _CRATE_PATH: &'static str = "crate_path.txt";
+static _COVERAGE_DIFF: &'static str = "coverage_diff.txt";
+static _HAND_WRITTEN_FUZZ_DIR: &'static str = "fuzz";
+static _FUZZ_DEPENDENCIES: &'static str = "fuzz_dependencies.toml";
+static _FUZZ_PROFILE: &'static str = "fuzz_profile.toml";
+//依赖很多的crate生成的target可能超过200MB，用lto去掉用不到的代码。
+//toolchain的cargo还不支持strip，需要的话用scripts/target-sizes去掉符号
+static _SIZE_PROFILE: &'static str =
+    "# Release profile that keeps afl targets small, append it to the Cargo.toml of the fuzz crate.
+# Run scripts/target-sizes after building to check the sizes of the targets.
+[profile.release]
+lto = true
+codegen-units = 1
+debug = false
+incremental = false
+";
+static _REGENERATION_DIFF: &'static str = "regeneration_diff.txt";
+static _TARGETS_MANIFEST: &'static str = "targets_manifest.txt";
+static _SECTION_SEED_DIR: &'static str = "section_seeds";
+static _VIABLE_SEED_DIR: &'static str = "viable_seeds";
+static _CUSTOM_MUTATOR_DIR: &'static str = "custom_mutators";
+static _CMPLOG_FILE_DIR: &'static str = "cmplog_files";
+static _CMPLOG_TARGETS: &'static str = "fuzz_cmplog.toml";
+static _COVERED_APIS: &'static str = "covered_apis.txt";
+static _UNSAFE_COVERAGE: &'static str = "unsafe_coverage.txt";
+static _SEQUENCE_LINE_PREFIX: &'static str = "//sequence: ";
+static _TOOLCHAIN_FILES: [&'static str; 2] = ["rust-toolchain.toml", "rust-toolchain"];
 static MAX_TEST_FILE_NUMBER: usize = 300;
 static DEFAULT_RANDOM_FILE_NUMBER: usize = 100;
 
+//FUZZ_TARGET_MAX_TARGETS可以改变生成的target数量的上限
+fn max_test_file_number() -> usize {
+    std::env::var("FUZZ_TARGET_MAX_TARGETS")
+        .ok()
+        .and_then(|max_targets| max_targets.parse::<usize>().ok())
+        .unwrap_or(MAX_TEST_FILE_NUMBER)
+}
+
+//设置了FUZZ_TARGET_SET_COVER的话，用集合覆盖选择序列，值是每个api至少被多少个序列调用，默认为1
+fn set_cover_redundancy() -> Option<usize> {
+    let redundancy = std::env::var("FUZZ_TARGET_SET_COVER").ok()?;
+    match redundancy.trim() {
+        "" => Some(1),
+        redundancy => match redundancy.parse::<usize>() {
+            Ok(redundancy) if redundancy > 0 => Some(redundancy),
+            _ => {
+                println!("invalid set cover redundancy {}", redundancy);
+                Some(1)
+            }
+        },
+    }
+}
+
+//设置了FUZZ_TARGET_OUTPUT_DIR的话，任何crate的target都写到这个目录，不使用下面预设的目录
+fn output_dir_override() -> Option<String> {
+    match std::env::var("FUZZ_TARGET_OUTPUT_DIR") {
+        Ok(output_dir) if !output_dir.is_empty() => Some(output_dir),
+        _ => None,
+    }
+}
+
 pub fn can_write_to_file(crate_name: &String, random_strategy: bool) -> bool {
+    if output_dir_override().is_some() {
+        return true;
+    }
+
     if !random_strategy && CRATE_TEST_DIR.contains_key(crate_name.as_str()) {
         return true;
     }
@@ -84,6 +236,52 @@ pub fn can_write_to_file(crate_name: &String, random_strategy: bool) -> bool {
     return false;
 }
 
+//生成的fuzzer项目的Cargo.toml的第一行，用来区分生成的项目和手写的fuzz目录
+static _GENERATED_PROJECT_MARKER: &'static str =
+    "# generated by fuzz-target-generator, regenerating the targets overwrites this file";
+
+//除了afl target，还可以为其他fuzzer生成可以直接运行的项目
+pub struct FuzzerProject {
+    pub tool: &'static str,
+    pub dir: &'static str,
+    pub target_dir: &'static str,
+    pub target_prefix: &'static str,
+    //相对于项目目录，{target}替换为target名，为空时不写初始种子
+    pub seed_dir: &'static str,
+    pub dependency: &'static str,
+    pub metadata: &'static str,
+    pub gitignore: &'static str,
+    //Cargo.toml中声明每个target的表，以及表中除name和path以外的设置
+    pub target_table: &'static str,
+    pub target_options: &'static str,
+}
+
+//FUZZ_TARGET_FUZZER中逗号分隔的fuzzer，如libfuzzer,honggfuzz。除了afl target，
+//每个fuzzer在输出目录中各自生成一个项目
+pub fn requested_fuzz_backends() -> Vec<&'static dyn FuzzBackend> {
+    let fuzzers = match std::env::var("FUZZ_TARGET_FUZZER") {
+        Ok(fuzzers) => fuzzers,
+        Err(_) => return Vec::new(),
+    };
+    let mut res: Vec<&'static dyn FuzzBackend> = Vec::new();
+    for fuzzer in fuzzers.split(',').map(str::trim).filter(|fuzzer| !fuzzer.is_empty()) {
+        match generator::fuzz_backend(fuzzer) {
+            Some(backend) if backend.project().is_none() => {}
+            Some(backend) => {
+                if !res.iter().any(|requested| requested.name() == backend.name()) {
+                    res.push(backend);
+                }
+            }
+            None => {
+                let names: Vec<&str> =
+                    generator::FUZZ_BACKENDS.iter().map(|backend| backend.name()).collect();
+                println!("unknown FUZZ_TARGET_FUZZER {}, expected {}", fuzzer, names.join(", "));
+            }
+        }
+    }
+    res
+}
+
 pub fn can_generate_libfuzzer_target(crate_name: &String) -> bool {
     if LIBFUZZER_FUZZ_TARGET_DIR.contains_key(crate_name.as_str()) {
         return true;
@@ -99,12 +297,30 @@ pub struct FileHelper {
     pub test_files: Vec<String>,
     pub reproduce_files: Vec<String>,
     pub libfuzzer_files: Vec<String>,
+    //FUZZ_TARGET_FUZZER中每个fuzzer的名字 -> 为它生成的target
+    pub project_files: BTreeMap<&'static str, Vec<String>>,
+    pub current_functions: HashSet<String>,
+    //输入分为多个带长度前缀的分段的target的初始种子
+    pub section_seeds: Vec<(usize, Vec<u8>)>,
+    //每个target的dry run输入，同时作为初始种子
+    pub viable_inputs: Vec<Vec<u8>>,
+    //每个target对应的AFL++ custom mutator源码
+    pub custom_mutators: Vec<String>,
+    //生成的target中调用到的crate的api，不包括helper函数
+    pub covered_functions: BTreeSet<String>,
+    //helper函数需要的依赖(crate名, 版本, feature) -> 用到的target编号
+    pub required_dependencies: BTreeMap<(String, String, Vec<String>), Vec<usize>>,
+    //每个target可以到达的unsafe位置，以及crate中所有的unsafe位置
+    pub target_unsafe_sites: Vec<BTreeSet<String>>,
+    pub all_unsafe_sites: BTreeSet<String>,
 }
 
 impl FileHelper {
     pub fn new(api_graph: &ApiGraph, random_strategy: bool) -> Self {
         let crate_name = api_graph._crate_name.clone();
-        let test_dir = if !random_strategy {
+        let test_dir = if let Some(output_dir) = output_dir_override() {
+            output_dir
+        } else if !random_strategy {
             CRATE_TEST_DIR.get(crate_name.as_str()).unwrap().to_string()
         } else {
             RANDOM_TEST_DIR.get(crate_name.as_str()).unwrap().to_string()
@@ -113,32 +329,206 @@ impl FileHelper {
         let mut test_files = Vec::new();
         let mut reproduce_files = Vec::new();
         let mut libfuzzer_files = Vec::new();
+        let fuzz_backends = requested_fuzz_backends();
+        let mut project_files: BTreeMap<&'static str, Vec<String>> = BTreeMap::new();
+        let mut section_seeds = Vec::new();
+        let mut viable_inputs = Vec::new();
+        let mut custom_mutators = Vec::new();
+        let mut covered_functions = BTreeSet::new();
+        let mut required_dependencies: BTreeMap<(String, String, Vec<String>), Vec<usize>> =
+            BTreeMap::new();
+        let mut target_unsafe_sites = Vec::new();
+        //设置了FUZZ_TARGET_CUSTOM_MUTATORS的话，为每个target生成按参数布局变异的custom mutator
+        let generate_custom_mutators = std::env::var_os("FUZZ_TARGET_CUSTOM_MUTATORS").is_some();
+        let max_test_file_number = max_test_file_number();
+        let choose_limit = if !random_strategy {
+            max_test_file_number
+        } else if RANDOM_TEST_FILE_NUMBERS.contains_key(crate_name.as_str()) {
+            (RANDOM_TEST_FILE_NUMBERS.get(crate_name.as_str()).unwrap()).clone()
+        } else {
+            DEFAULT_RANDOM_FILE_NUMBER
+        };
+        //放在最前面的序列(format sweep、focus、stateful、trait coverage)也计入数量上限，
+        //选择序列时先去掉它们占用的数量
+        let reserved_number = api_graph.trait_coverage_sequences.len()
+            + api_graph.stateful_sequences.len()
+            + api_graph.focus_sequences.len()
+            + api_graph.format_sweep_sequence.iter().count();
+        let choose_number = choose_limit.saturating_sub(reserved_number);
         //let chosen_sequences = api_graph._naive_choose_sequence(MAX_TEST_FILE_NUMBER);
-        let chosen_sequences = if !random_strategy {
-            api_graph._heuristic_choose(MAX_TEST_FILE_NUMBER, true)
+        let mut chosen_sequences = if choose_number == 0 {
+            Vec::new()
+        } else if !random_strategy {
+            match set_cover_redundancy() {
+                Some(redundancy) => api_graph._set_cover_choose(choose_number, redundancy),
+                None => api_graph._heuristic_choose(choose_number, true),
+            }
         } else {
-            let random_size = if RANDOM_TEST_FILE_NUMBERS.contains_key(crate_name.as_str()) {
-                (RANDOM_TEST_FILE_NUMBERS.get(crate_name.as_str()).unwrap()).clone()
-            } else {
-                DEFAULT_RANDOM_FILE_NUMBER
-            };
-            api_graph._first_choose(random_size)
+            api_graph._first_choose(choose_number)
         };
+        //FUZZ_TARGET_FFI_APIS=deprioritize时调用FFI api的序列放在最后，数量达到上限时先被丢掉
+        if ffi_util::ffi_policy() == FfiPolicy::Deprioritize {
+            let (ffi_sequences, mut other_sequences): (Vec<ApiSequence>, Vec<ApiSequence>) =
+                chosen_sequences
+                    .into_iter()
+                    .partition(|sequence| !sequence._ffi_functions(api_graph).is_empty());
+            println!("deprioritized {} sequences calling ffi functions", ffi_sequences.len());
+            other_sequences.extend(ffi_sequences);
+            chosen_sequences = other_sequences;
+        }
+        //trait coverage模式生成的序列放在最前面，保证不会因为数量上限被丢掉
+        if !api_graph.trait_coverage_sequences.is_empty() {
+            let mut trait_coverage_sequences = api_graph.trait_coverage_sequences.clone();
+            trait_coverage_sequences.append(&mut chosen_sequences);
+            chosen_sequences = trait_coverage_sequences;
+        }
+        //stateful序列同样放在最前面
+        if !api_graph.stateful_sequences.is_empty() {
+            let mut stateful_sequences = api_graph.stateful_sequences.clone();
+            stateful_sequences.append(&mut chosen_sequences);
+            chosen_sequences = stateful_sequences;
+        }
+        //需要更多fuzz的api的序列放在前面，去掉已经被选中的相同序列
+        if !api_graph.focus_sequences.is_empty() {
+            chosen_sequences.retain(|sequence| !api_graph.focus_sequences.contains(sequence));
+            let mut focus_sequences = api_graph.focus_sequences.clone();
+            focus_sequences.append(&mut chosen_sequences);
+            chosen_sequences = focus_sequences;
+        }
+        //format sweep序列只有一个，放在最前面
+        if let Some(format_sweep_sequence) = &api_graph.format_sweep_sequence {
+            chosen_sequences.insert(0, format_sweep_sequence.clone());
+        }
+        //放在最前面的序列本身超过上限时，丢掉多出来的序列
+        if chosen_sequences.len() > max_test_file_number {
+            println!(
+                "{} sequences exceed the limit of {} targets, dropped",
+                chosen_sequences.len() - max_test_file_number,
+                max_test_file_number
+            );
+            chosen_sequences.truncate(max_test_file_number);
+        }
+        //设置了FUZZ_TARGET_ADVERSARIAL_RELATIONS的话，为用到参数关系或输出buffer的序列额外生成
+        //故意违反关系、使用过小buffer的变体
+        if std::env::var_os("FUZZ_TARGET_ADVERSARIAL_RELATIONS").is_some() {
+            let mut adversarial_sequences: Vec<ApiSequence> = chosen_sequences
+                .iter()
+                .filter(|sequence| {
+                    sequence._uses_param_relations(api_graph) || sequence._uses_mut_buffers()
+                })
+                .map(|sequence| sequence._adversarial_variant())
+                .collect();
+            chosen_sequences.append(&mut adversarial_sequences);
+        }
+        //FUZZ_TARGET_UNWRAP_POLICY决定Err和None的处理方式，设置为both时用到unwrap的序列
+        //分别生成panic和early return两种变体
+        let unwrap_policies = api_util::unwrap_policies();
+        chosen_sequences = chosen_sequences
+            .into_iter()
+            .flat_map(|sequence| {
+                if sequence._uses_unwrap() {
+                    unwrap_policies
+                        .iter()
+                        .map(|unwrap_policy| sequence._unwrap_policy_variant(*unwrap_policy))
+                        .collect()
+                } else {
+                    vec![sequence]
+                }
+            })
+            .collect();
+        //设置了FUZZ_TARGET_PACK_BYTES的话，把消耗输入不超过这个字节数的短序列打包到同一个target中
+        if let Some(byte_budget) = std::env::var("FUZZ_TARGET_PACK_BYTES")
+            .ok()
+            .and_then(|byte_budget| byte_budget.parse::<usize>().ok())
+        {
+            let sequence_number = chosen_sequences.len();
+            chosen_sequences = ApiSequence::_pack_cheap_sequences(chosen_sequences, byte_budget);
+            println!(
+                "packed {} sequences into {} targets",
+                sequence_number,
+                chosen_sequences.len()
+            );
+        }
         //println!("chosen sequences number: {}", chosen_sequences.len());
 
         for sequence in &chosen_sequences {
-            if sequence_count >= MAX_TEST_FILE_NUMBER {
+            if sequence_count >= max_test_file_number {
                 break;
             }
             let test_file = sequence._to_afl_test_file(api_graph, sequence_count);
             test_files.push(test_file);
             let reproduce_file = sequence._to_replay_crash_file(api_graph, sequence_count);
             reproduce_files.push(reproduce_file);
-            let libfuzzer_file = sequence._to_libfuzzer_test_file(api_graph, sequence_count);
+            let libfuzzer_file = LibFuzzerBackend.render(sequence, api_graph, sequence_count);
             libfuzzer_files.push(libfuzzer_file);
+            for backend in &fuzz_backends {
+                let project_file = backend.render(sequence, api_graph, sequence_count);
+                project_files.entry(backend.name()).or_default().push(project_file);
+            }
+            if let Some(section_seed) = sequence._section_seed() {
+                section_seeds.push((sequence_count, section_seed));
+            }
+            viable_inputs.push(sequence._viable_input());
+            if generate_custom_mutators {
+                let target_name = format!("test_{}{}", crate_name, sequence_count);
+                custom_mutators.push(mutator_util::_custom_mutator_source(sequence, &target_name));
+            }
+            for (crate_name, version, features) in sequence._required_dependencies() {
+                let features = features.iter().map(|feature| feature.to_string()).collect();
+                required_dependencies
+                    .entry((crate_name.to_string(), version.to_string(), features))
+                    .or_default()
+                    .push(sequence_count);
+            }
+            let mut unsafe_sites = BTreeSet::new();
+            for api_call in sequence.functions.iter().chain(sequence._stateful_calls.iter()) {
+                let (_, index) = api_call.func;
+                let api_function = &api_graph.api_functions[index];
+                let full_name = &api_function.full_name;
+                //依赖crate的构造函数不算被测crate的api，它所在的crate加入fuzz crate的依赖。
+                //版本由write_pinning_files复制的Cargo.lock固定
+                if let Some(producer_crate) = api_graph.api_producer_crates.get(full_name) {
+                    let test_indexes = required_dependencies
+                        .entry((producer_crate.clone(), "*".to_string(), Vec::new()))
+                        .or_default();
+                    if test_indexes.last() != Some(&sequence_count) {
+                        test_indexes.push(sequence_count);
+                    }
+                    continue;
+                }
+                //helper函数不属于crate的api
+                if api_function._helper.is_none() {
+                    covered_functions.insert(full_name.clone());
+                }
+                if let Some(sites) = api_graph.api_unsafe_sites.get(full_name) {
+                    unsafe_sites.extend(sites.iter().cloned());
+                }
+            }
+            target_unsafe_sites.push(unsafe_sites);
             sequence_count = sequence_count + 1;
         }
-        FileHelper { crate_name, test_dir, test_files, reproduce_files, libfuzzer_files }
+        let current_functions = api_graph
+            .api_functions
+            .iter()
+            .filter(|api_function| !api_graph._is_producer_function(&api_function.full_name))
+            .map(|api_function| api_function.full_name.clone())
+            .collect();
+        FileHelper {
+            crate_name,
+            test_dir,
+            test_files,
+            reproduce_files,
+            libfuzzer_files,
+            project_files,
+            current_functions,
+            section_seeds,
+            viable_inputs,
+            custom_mutators,
+            covered_functions,
+            required_dependencies,
+            target_unsafe_sites,
+            all_unsafe_sites: api_graph.unsafe_reachability.all_sites.clone(),
+        }
     }
 
     pub fn write_files(&self) {
@@ -147,6 +537,16 @@ impl FileHelper {
             fs::remove_file(&test_path).unwrap();
         }
         let test_file_path = test_path.clone().join(_TEST_FILE_DIR);
+        //删除之前生成的target之前，先和新的target进行比较
+        let old_sequences = read_sequences_in_dir(&test_file_path);
+        if !old_sequences.is_empty() {
+            let new_sequences =
+                self.test_files.iter().filter_map(|file| read_sequence(file)).collect();
+            let diff = self.regeneration_diff(&old_sequences, &new_sequences);
+            print!("{}", diff);
+            let mut diff_file = fs::File::create(test_path.join(_REGENERATION_DIFF)).unwrap();
+            diff_file.write_all(diff.as_bytes()).unwrap();
+        }
         ensure_empty_dir(&test_file_path);
         let reproduce_file_path = test_path.clone().join(_REPRODUCE_FILE_DIR);
         ensure_empty_dir(&reproduce_file_path);
@@ -154,6 +554,332 @@ impl FileHelper {
         write_to_files(&self.crate_name, &test_file_path, &self.test_files, "test");
         //暂时用test file代替一下，后续改成真正的reproduce file
         write_to_files(&self.crate_name, &reproduce_file_path, &self.reproduce_files, "replay");
+        self.write_dependencies(&test_path, "test");
+        if afl_util::aflpp_variants_requested() {
+            self.write_cmplog_files(&test_path);
+        }
+        let mut profile_file = fs::File::create(test_path.join(_FUZZ_PROFILE)).unwrap();
+        profile_file.write_all(_SIZE_PROFILE.as_bytes()).unwrap();
+        let mut manifest_file = fs::File::create(test_path.join(_TARGETS_MANIFEST)).unwrap();
+        manifest_file.write_all(self.targets_manifest().as_bytes()).unwrap();
+        //target中调用到的crate的api，每行一个，已排序
+        let mut covered_apis_file = fs::File::create(test_path.join(_COVERED_APIS)).unwrap();
+        for covered_api in self.covered_functions.iter() {
+            covered_apis_file.write_all(format!("{}\n", covered_api).as_bytes()).unwrap();
+        }
+        if !self.all_unsafe_sites.is_empty() {
+            let unsafe_coverage = self.unsafe_coverage();
+            println!("{}", unsafe_coverage.lines().next().unwrap());
+            let mut unsafe_file = fs::File::create(test_path.join(_UNSAFE_COVERAGE)).unwrap();
+            unsafe_file.write_all(unsafe_coverage.as_bytes()).unwrap();
+        }
+        //每个target一个种子目录，可以直接作为afl的-i参数
+        let section_seed_path = test_path.join(_SECTION_SEED_DIR);
+        ensure_empty_dir(&section_seed_path);
+        for (test_index, section_seed) in &self.section_seeds {
+            let target_seed_path =
+                section_seed_path.join(format!("test_{}{}", self.crate_name, test_index));
+            ensure_empty_dir(&target_seed_path);
+            let mut seed_file = fs::File::create(target_seed_path.join("seed0")).unwrap();
+            seed_file.write_all(section_seed).unwrap();
+        }
+        let viable_seed_path = test_path.join(_VIABLE_SEED_DIR);
+        ensure_empty_dir(&viable_seed_path);
+        for (i, viable_input) in self.viable_inputs.iter().enumerate() {
+            let target_seed_path = viable_seed_path.join(format!("test_{}{}", self.crate_name, i));
+            ensure_empty_dir(&target_seed_path);
+            let mut seed_file = fs::File::create(target_seed_path.join("seed0")).unwrap();
+            seed_file.write_all(viable_input).unwrap();
+        }
+        if !self.custom_mutators.is_empty() {
+            let custom_mutator_path = test_path.join(_CUSTOM_MUTATOR_DIR);
+            ensure_empty_dir(&custom_mutator_path);
+            for (i, custom_mutator) in self.custom_mutators.iter().enumerate() {
+                let mutator_file_path =
+                    custom_mutator_path.join(format!("test_{}{}.c", self.crate_name, i));
+                let mut mutator_file = fs::File::create(mutator_file_path).unwrap();
+                mutator_file.write_all(custom_mutator.as_bytes()).unwrap();
+            }
+        }
+    }
+
+    //cmplog版本和afl target的代码相同，只是编译时需要设置AFL_LLVM_CMPLOG=1，
+    //单独声明成cmplog_<crate><n>，和普通target放在同一个fuzz crate中
+    fn write_cmplog_files(&self, test_path: &PathBuf) {
+        let cmplog_file_path = test_path.join(_CMPLOG_FILE_DIR);
+        ensure_empty_dir(&cmplog_file_path);
+        write_to_files(&self.crate_name, &cmplog_file_path, &self.test_files, "cmplog");
+        let mut res = String::new();
+        res.push_str(
+            "# cmplog variants of the afl targets, append to Cargo.toml of the fuzz crate.\n",
+        );
+        res.push_str("# Build them with AFL_LLVM_CMPLOG=1 cargo afl build --release ");
+        res.push_str("--target-dir target-cmplog, then run\n");
+        res.push_str("# scripts/afl-fuzz-cmplog to pass each one to afl-fuzz -c.\n");
+        for i in 0..self.test_files.len() {
+            let target_name = format!("cmplog_{}{}", self.crate_name, i);
+            res.push_str(
+                format!(
+                    "\n[[bin]]\nname = \"{}\"\npath = \"{}/{}.rs\"\n",
+                    target_name, _CMPLOG_FILE_DIR, target_name
+                )
+                .as_str(),
+            );
+        }
+        let mut cmplog_file = fs::File::create(test_path.join(_CMPLOG_TARGETS)).unwrap();
+        cmplog_file.write_all(res.as_bytes()).unwrap();
+    }
+
+    //所有target一共可以到达多少unsafe位置，每个target可以到达哪些，以及没有target可以到达的位置。
+    //只是MIR调用图上的可达性，不代表fuzz时一定会执行到
+    fn unsafe_coverage(&self) -> String {
+        let reachable_sites: BTreeSet<&String> =
+            self.target_unsafe_sites.iter().flatten().collect();
+        let mut res = format!(
+            "unsafe sites reachable from generated targets of {}: {} of {} ({:.1}%)\n",
+            self.crate_name,
+            reachable_sites.len(),
+            self.all_unsafe_sites.len(),
+            reachable_sites.len() as f64 * 100.0 / self.all_unsafe_sites.len() as f64
+        );
+        for (i, sites) in self.target_unsafe_sites.iter().enumerate() {
+            res.push_str(
+                format!("test_{}{}: {} unsafe sites\n", self.crate_name, i, sites.len()).as_str(),
+            );
+            for site in sites {
+                res.push_str(format!("    {}\n", site).as_str());
+            }
+        }
+        res.push_str("unreachable:\n");
+        for site in &self.all_unsafe_sites {
+            if !reachable_sites.contains(site) {
+                res.push_str(format!("    {}\n", site).as_str());
+            }
+        }
+        res
+    }
+
+    //按最后调用的api所在的mod(方法则是所在的类型)对target分组，
+    //不同mod中同名的api可以通过分组区分开。target文件和二进制按编号命名，不会因为api同名而冲突，
+    //所以不需要按mod路径的hash命名或者分目录
+    fn targets_manifest(&self) -> String {
+        let mut namespaces: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        for (i, test_file) in self.test_files.iter().enumerate() {
+            let sequence = read_sequence(test_file).unwrap_or_default();
+            let last_function = sequence.rsplit(" -> ").next().unwrap_or_default();
+            //helper函数的泛型参数中的路径不算
+            let last_function = last_function.split("::<").next().unwrap_or_default();
+            let namespace = match last_function.rfind("::") {
+                Some(position) => last_function[..position].to_string(),
+                None => self.crate_name.clone(),
+            };
+            let target_name = format!("test_{}{}", self.crate_name, i);
+            namespaces.entry(namespace).or_default().push((target_name, sequence));
+        }
+        let mut res = String::new();
+        for (namespace, targets) in namespaces {
+            res.push_str(format!("{}\n", namespace).as_str());
+            for (target_name, sequence) in targets {
+                res.push_str(format!("    {}: {}\n", target_name, sequence).as_str());
+            }
+        }
+        res
+    }
+
+    //新增、删除以及改变了的序列。最后调用的函数相同的序列视为改变；
+    //删除的序列如果调用了已经不存在的函数，说明是api发生了变化，否则只是这次没有被选中
+    fn regeneration_diff(&self, old: &BTreeSet<String>, new: &BTreeSet<String>) -> String {
+        let last_function = |sequence: &String| sequence.rsplit(" -> ").next().unwrap().to_string();
+        let added: Vec<&String> = new.difference(old).collect();
+        let removed: Vec<&String> = old.difference(new).collect();
+        let mut res = format!(
+            "regenerated targets for {}: {} unchanged, {} added, {} removed\n",
+            self.crate_name,
+            old.intersection(new).count(),
+            added.len(),
+            removed.len()
+        );
+        for sequence in &removed {
+            let changed_to = added
+                .iter()
+                .find(|added_sequence| last_function(added_sequence) == last_function(sequence));
+            if let Some(new_sequence) = changed_to {
+                res.push_str(
+                    format!("changed: {}\n      => {}\n", sequence, new_sequence).as_str(),
+                );
+                continue;
+            }
+            let missing_functions: Vec<&str> = sequence
+                .split(" -> ")
+                .filter(|function| !self.current_functions.contains(*function))
+                .collect();
+            let reason = if missing_functions.is_empty() {
+                "not chosen".to_string()
+            } else {
+                format!("api removed: {}", missing_functions.join(", "))
+            };
+            res.push_str(format!("removed: {} ({})\n", sequence, reason).as_str());
+        }
+        for sequence in &added {
+            let is_changed = removed
+                .iter()
+                .any(|removed_sequence| last_function(removed_sequence) == last_function(sequence));
+            if !is_changed {
+                res.push_str(format!("added: {}\n", sequence).as_str());
+            }
+        }
+        res
+    }
+
+    //为afl target写出fuzz crate的Cargo.lock和toolchain文件，使fuzz crate可以用--locked复现构建。
+    //Cargo.lock从被测crate(或者它所在workspace)的Cargo.lock出发解析，fuzz crate自己的依赖由cargo补上
+    pub fn write_pinning_files(&self, src_root: &Path) {
+        let test_path = PathBuf::from(&self.test_dir);
+        let crate_path = crate_path(src_root);
+        match self.resolve_afl_lock(crate_path.clone()) {
+            Ok(lock) => {
+                if let Err(e) = fs::write(test_path.join(_CARGO_LOCK), lock) {
+                    println!("can not write {}: {}, dependencies are not pinned", _CARGO_LOCK, e);
+                }
+            }
+            Err(e) => println!("{}, dependencies are not pinned", e),
+        }
+        if let Some(crate_path) = &crate_path {
+            for toolchain_file in _TOOLCHAIN_FILES.iter() {
+                if let Some(toolchain_path) = find_in_crate(crate_path, toolchain_file) {
+                    match fs::copy(&toolchain_path, test_path.join(toolchain_file)) {
+                        Ok(_) => return,
+                        Err(e) => println!("can not copy {}: {}", toolchain_path.display(), e),
+                    }
+                }
+            }
+        }
+        //crate没有指定toolchain的话，记录运行时rustup选择的toolchain
+        let toolchain = match std::env::var("RUSTUP_TOOLCHAIN") {
+            Ok(toolchain) => toolchain,
+            Err(_) => return,
+        };
+        let channel = match toolchain_channel(&toolchain) {
+            Some(channel) => channel,
+            None => {
+                println!(
+                    "RUSTUP_TOOLCHAIN {} is not a toolchain name, toolchain is not pinned",
+                    toolchain
+                );
+                return;
+            }
+        };
+        let content = format!("[toolchain]\nchannel = \"{}\"\n", channel);
+        if let Err(e) = fs::write(test_path.join(_TOOLCHAIN_FILES[0]), content) {
+            println!("can not write {}: {}, toolchain is not pinned", _TOOLCHAIN_FILES[0], e);
+        }
+    }
+
+    //在临时目录中写一个和afl fuzz crate依赖相同的manifest，解析出它的Cargo.lock
+    fn resolve_afl_lock(&self, crate_path: Option<PathBuf>) -> Result<String, String> {
+        let lock_dir =
+            std::env::temp_dir().join(format!("fuzz_target_lock_{}", std::process::id()));
+        ensure_empty_dir(&lock_dir);
+        let package_name = crate_path
+            .as_ref()
+            .and_then(|crate_path| package_name(&crate_path.join(_CARGO_TOML)))
+            .unwrap_or_else(|| self.crate_name.clone());
+        let seed_lock =
+            crate_path.as_ref().and_then(|crate_path| find_in_crate(crate_path, _CARGO_LOCK));
+        let manifest =
+            self.fuzzer_project_manifest(&_AFL_LOCK_PROJECT, 0, &package_name, crate_path);
+        let res = fs::write(lock_dir.join(_CARGO_TOML), manifest)
+            .and_then(|_| fs::create_dir_all(lock_dir.join("src")))
+            .and_then(|_| fs::write(lock_dir.join("src").join("lib.rs"), ""))
+            .map_err(|e| format!("can not write the manifest to resolve {}: {}", _CARGO_LOCK, e))
+            .and_then(|_| resolve_lock(&lock_dir, seed_lock))
+            .and_then(|_| {
+                fs::read_to_string(lock_dir.join(_CARGO_LOCK))
+                    .map_err(|e| format!("can not read the resolved {}: {}", _CARGO_LOCK, e))
+            });
+        let _ = fs::remove_dir_all(&lock_dir);
+        res
+    }
+
+    //记录被测crate的目录，fuzz crate通过path依赖指向这个目录。
+    //registry中的源码可能被cargo清理或者不可写，建议先用scripts/vendor-crate复制到单独的目录
+    pub fn write_crate_path(&self, src_root: &Path) {
+        let crate_path = match crate_path(src_root) {
+            Some(crate_path) => crate_path,
+            None => {
+                println!(
+                    "no {} found for {}, crate path is not recorded",
+                    _CARGO_TOML, self.crate_name
+                );
+                return;
+            }
+        };
+        if crate_path.components().any(|component| component.as_os_str() == "registry") {
+            println!(
+                "{} is generated from the cargo registry ({}), run scripts/vendor-crate to get a writable copy",
+                self.crate_name,
+                crate_path.display()
+            );
+        }
+        let test_path = PathBuf::from(&self.test_dir);
+        let mut file = fs::File::create(test_path.join(_CRATE_PATH)).unwrap();
+        file.write_all(format!("{}\n", crate_path.display()).as_bytes()).unwrap();
+    }
+
+    //crate中已经有手写的fuzz target(fuzz/目录)的话，比较两者调用到的api，
+    //报告生成的target额外覆盖了哪些api，以及遗漏了哪些手写target覆盖的api
+    pub fn write_coverage_diff(&self, api_graph: &ApiGraph, src_root: &Path) {
+        let fuzz_dir = match find_in_ancestors(src_root, _CARGO_TOML) {
+            Some(cargo_toml) => cargo_toml.parent().unwrap().join(_HAND_WRITTEN_FUZZ_DIR),
+            None => return,
+        };
+        //FUZZ_TARGET_CARGO_FUZZ_IN_CRATE写入的fuzz目录不是手写的
+        if !fuzz_dir.is_dir() || is_generated_project(&fuzz_dir) {
+            return;
+        }
+        let hand_written_usage = usage_util::mine_api_usage(&fuzz_dir, &api_graph.api_functions);
+        let hand_written_functions: BTreeSet<&String> =
+            hand_written_usage.call_counts.keys().collect();
+        let generated_functions: BTreeSet<&String> = self.covered_functions.iter().collect();
+        let mut res = String::new();
+        res.push_str(
+            format!(
+                "hand-written targets in {}: {} apis, generated targets: {} apis, both: {}\n",
+                fuzz_dir.display(),
+                hand_written_functions.len(),
+                generated_functions.len(),
+                hand_written_functions.intersection(&generated_functions).count()
+            )
+            .as_str(),
+        );
+        for function in generated_functions.difference(&hand_written_functions) {
+            res.push_str(format!("added: {}\n", function).as_str());
+        }
+        for function in hand_written_functions.difference(&generated_functions) {
+            res.push_str(format!("missed: {}\n", function).as_str());
+        }
+        println!("{}", res.lines().next().unwrap());
+        let test_path = PathBuf::from(&self.test_dir);
+        let mut file = fs::File::create(test_path.join(_COVERAGE_DIFF)).unwrap();
+        file.write_all(res.as_bytes()).unwrap();
+    }
+
+    //设置了FUZZ_TARGET_EMIT_ENV=docker|nix的话，在输出目录中写入可以复现fuzz环境的Dockerfile或shell.nix
+    pub fn write_env_recipe(&self) {
+        let kind = match std::env::var("FUZZ_TARGET_EMIT_ENV") {
+            Ok(kind) => kind,
+            Err(_) => return,
+        };
+        let (file_name, recipe) = match env_util::_env_recipe(kind.as_str(), &self.crate_name) {
+            Some(file_name_and_recipe) => file_name_and_recipe,
+            None => {
+                println!("unknown FUZZ_TARGET_EMIT_ENV {}, expected docker or nix", kind);
+                return;
+            }
+        };
+        let test_path = PathBuf::from(&self.test_dir);
+        let mut file = fs::File::create(test_path.join(file_name)).unwrap();
+        file.write_all(recipe.as_bytes()).unwrap();
     }
 
     pub fn write_libfuzzer_files(&self) {
@@ -170,7 +896,214 @@ impl FileHelper {
             &self.libfuzzer_files,
             "fuzz_target",
         );
+        self.write_dependencies(&libfuzzer_path, "fuzz_target");
+    }
+
+    //fuzzer项目的布局：<dir>/Cargo.toml，<dir>/<target_dir>/中每个target一个文件，
+    //dry run输入作为每个target的初始种子。生成之后在<dir>中用对应的cargo子命令运行target。
+    //重新生成时只替换target文件和manifest，fuzz积累的corpus和crash保留
+    pub fn write_fuzzer_projects(&self, src_root: &Path) {
+        for (name, target_files) in &self.project_files {
+            let backend = generator::fuzz_backend(name).unwrap();
+            if let Some(project) = backend.project() {
+                self.write_fuzzer_project(backend, project, target_files, src_root);
+            }
+        }
+    }
+
+    fn write_fuzzer_project(
+        &self,
+        backend: &dyn FuzzBackend,
+        project: &FuzzerProject,
+        target_files: &Vec<String>,
+        src_root: &Path,
+    ) {
+        let crate_path = crate_path(src_root);
+        //cargo fuzz list/run从当前目录所在的package中找fuzz目录，输出目录不是package，
+        //设置了FUZZ_TARGET_CARGO_FUZZ_IN_CRATE的话像cargo fuzz init一样写到被测crate的根目录中
+        let in_crate_path = match &crate_path {
+            Some(crate_path)
+                if backend.name() == LibFuzzerBackend.name()
+                    && std::env::var_os("FUZZ_TARGET_CARGO_FUZZ_IN_CRATE").is_some() =>
+            {
+                let in_crate_path = crate_path.join(project.dir);
+                if in_crate_path.exists() && !is_generated_project(&in_crate_path) {
+                    println!(
+                        "{} has a hand-written {} directory, keeping it",
+                        crate_path.display(),
+                        project.dir
+                    );
+                    None
+                } else {
+                    Some(in_crate_path)
+                }
+            }
+            _ => None,
+        };
+        let (project_path, crate_dependency_path) = match in_crate_path {
+            Some(in_crate_path) => {
+                (in_crate_path, crate_path.as_ref().map(|_| PathBuf::from("..")))
+            }
+            None => (PathBuf::from(&self.test_dir).join(project.dir), crate_path.clone()),
+        };
+        if project_path.is_file() {
+            fs::remove_file(&project_path).unwrap();
+        }
+        let target_path = project_path.join(project.target_dir);
+        ensure_empty_dir(&target_path);
+        write_to_files(&self.crate_name, &target_path, target_files, project.target_prefix);
+        let viable_inputs =
+            if project.seed_dir.is_empty() { &[][..] } else { &self.viable_inputs[..] };
+        for (i, viable_input) in viable_inputs.iter().enumerate() {
+            let target_name = format!("{}_{}{}", project.target_prefix, self.crate_name, i);
+            let seed_path =
+                project_path.join(project.seed_dir.replace("{target}", target_name.as_str()));
+            fs::create_dir_all(&seed_path).unwrap();
+            let mut seed_file = fs::File::create(seed_path.join("seed0")).unwrap();
+            seed_file.write_all(viable_input).unwrap();
+        }
+        let package_name = crate_path
+            .as_ref()
+            .and_then(|crate_path| package_name(&crate_path.join(_CARGO_TOML)))
+            .unwrap_or_else(|| self.crate_name.clone());
+        let manifest = self.fuzzer_project_manifest(
+            project,
+            target_files.len(),
+            &package_name,
+            crate_dependency_path,
+        );
+        let mut manifest_file = fs::File::create(project_path.join(_CARGO_TOML)).unwrap();
+        manifest_file.write_all(manifest.as_bytes()).unwrap();
+        let mut gitignore_file = fs::File::create(project_path.join(".gitignore")).unwrap();
+        gitignore_file.write_all(project.gitignore.as_bytes()).unwrap();
+        let seed_lock = crate_path.and_then(|crate_path| find_in_crate(&crate_path, _CARGO_LOCK));
+        if let Err(e) = resolve_lock(&project_path, seed_lock) {
+            println!("{}, dependencies of the {} project are not pinned", e, project.tool);
+        }
+        println!(
+            "{} project for {} written to {}",
+            project.tool,
+            self.crate_name,
+            project_path.display()
+        );
+    }
+
+    fn fuzzer_project_manifest(
+        &self,
+        project: &FuzzerProject,
+        target_number: usize,
+        package_name: &String,
+        crate_dependency_path: Option<PathBuf>,
+    ) -> String {
+        let mut res = String::new();
+        res.push_str(
+            format!(
+                "{}\n[package]\nname = \"{}-fuzz\"\nversion = \"0.0.0\"\npublish = false\nedition = \"2018\"\n\n",
+                _GENERATED_PROJECT_MARKER, self.crate_name
+            )
+            .as_str(),
+        );
+        if !project.metadata.is_empty() {
+            res.push_str(format!("{}\n", project.metadata).as_str());
+        }
+        res.push_str("[dependencies]\n");
+        if !project.dependency.is_empty() {
+            res.push_str(format!("{}\n", project.dependency).as_str());
+        }
+        //crate名中的-在代码中是_，依赖的key使用代码中的名字，package指向真正的包名。
+        //分析时打开的feature也在依赖中打开，否则target调用的feature gated api不存在
+        let features = api_util::selected_features();
+        let features = if features.is_empty() {
+            String::new()
+        } else {
+            let features: Vec<String> =
+                features.iter().map(|feature| format!("\"{}\"", feature)).collect();
+            format!(", features = [{}]", features.join(", "))
+        };
+        match crate_dependency_path {
+            Some(crate_dependency_path) => {
+                res.push_str(
+                    format!(
+                        "{} = {{ package = \"{}\", path = \"{}\"{} }}\n",
+                        self.crate_name,
+                        package_name,
+                        crate_dependency_path.display(),
+                        features
+                    )
+                    .as_str(),
+                );
+            }
+            None => {
+                println!(
+                    "no {} found for {}, add the crate to the dependencies of the {} project",
+                    _CARGO_TOML, self.crate_name, project.tool
+                );
+            }
+        }
+        for (crate_name, version, features) in self.required_dependencies.keys() {
+            res.push_str(
+                format!("{} = {}\n", crate_name, dependency_value(version, features)).as_str(),
+            );
+        }
+        //不加入被测crate的workspace
+        res.push_str("\n[workspace]\nmembers = [\".\"]\n");
+        for i in 0..target_number {
+            let target_name = format!("{}_{}{}", project.target_prefix, self.crate_name, i);
+            res.push_str(
+                format!(
+                    "\n{}\nname = \"{}\"\npath = \"{}/{}.rs\"\n{}",
+                    project.target_table,
+                    target_name,
+                    project.target_dir,
+                    target_name,
+                    project.target_options
+                )
+                .as_str(),
+            );
+        }
+        res
+    }
+
+    //把helper函数需要的crate写成Cargo.toml的[dependencies]片段，没有target需要额外依赖时不生成
+    fn write_dependencies(&self, path: &PathBuf, prefix: &str) {
+        let dependencies_path = path.join(_FUZZ_DEPENDENCIES);
+        if self.required_dependencies.is_empty() {
+            if dependencies_path.is_file() {
+                fs::remove_file(&dependencies_path).unwrap();
+            }
+            return;
+        }
+        let mut res = String::new();
+        res.push_str("# dependencies of helper functions in the generated targets\n");
+        res.push_str("# append to Cargo.toml of the fuzz crate\n");
+        res.push_str("[dependencies]\n");
+        for ((crate_name, version, features), test_indexes) in &self.required_dependencies {
+            let targets: Vec<String> = test_indexes
+                .iter()
+                .map(|test_index| format!("{}_{}{}", prefix, self.crate_name, test_index))
+                .collect();
+            res.push_str(
+                format!(
+                    "{} = {} # {}\n",
+                    crate_name,
+                    dependency_value(version, features),
+                    targets.join(", ")
+                )
+                .as_str(),
+            );
+        }
+        let mut file = fs::File::create(dependencies_path).unwrap();
+        file.write_all(res.as_bytes()).unwrap();
+    }
+}
+
+//Cargo.toml中依赖的值，需要feature时写成inline table
+fn dependency_value(version: &str, features: &Vec<String>) -> String {
+    if features.is_empty() {
+        return format!("\"{}\"", version);
     }
+    let features: Vec<String> = features.iter().map(|feature| format!("\"{}\"", feature)).collect();
+    format!("{{ version = \"{}\", features = [{}] }}", version, features.join(", "))
 }
 
 fn write_to_files(crate_name: &String, path: &PathBuf, contents: &Vec<String>, prefix: &str) {
@@ -183,6 +1116,163 @@ fn write_to_files(crate_name: &String, path: &PathBuf, contents: &Vec<String>, p
     }
 }
 
+fn read_sequence(target_file: &String) -> Option<String> {
+    target_file
+        .lines()
+        .find(|line| line.starts_with(_SEQUENCE_LINE_PREFIX))
+        .map(|line| line[_SEQUENCE_LINE_PREFIX.len()..].to_string())
+}
+
+fn read_sequences_in_dir(path: &PathBuf) -> BTreeSet<String> {
+    let mut res = BTreeSet::new();
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                if let Some(sequence) = read_sequence(&content) {
+                    res.insert(sequence);
+                }
+            }
+        }
+    }
+    res
+}
+
+fn is_generated_project(project_path: &Path) -> bool {
+    match fs::read_to_string(project_path.join(_CARGO_TOML)) {
+        Ok(manifest) => manifest.starts_with(_GENERATED_PROJECT_MARKER),
+        Err(_) => false,
+    }
+}
+
+//被测crate的Cargo.toml所在的目录
+fn crate_path(src_root: &Path) -> Option<PathBuf> {
+    let crate_path = find_in_ancestors(src_root, _CARGO_TOML)?.parent()?.to_path_buf();
+    Some(fs::canonicalize(&crate_path).unwrap_or(crate_path))
+}
+
+//Cargo.toml中[package]的name，不完整地解析toml，只处理name = "..."这种常见写法
+fn package_name(cargo_toml: &Path) -> Option<String> {
+    let content = fs::read_to_string(cargo_toml).ok()?;
+    let mut in_package = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        let mut key_value = line.splitn(2, '=');
+        let key = key_value.next().unwrap_or_default().trim();
+        if in_package && key == "name" {
+            let value = key_value.next()?.trim();
+            return Some(value.trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+//Cargo.toml中声明的feature，包括optional依赖隐含的feature。和package_name一样不完整地解析toml，
+//只处理[features]中的key和依赖表中写在一行内的optional = true
+pub fn manifest_features(src_root: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(find_in_ancestors(src_root, _CARGO_TOML)?).ok()?;
+    let mut res = Vec::new();
+    let mut table = "";
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            table = line;
+            continue;
+        }
+        let mut key_value = line.splitn(2, '=');
+        let key = key_value.next().unwrap_or_default().trim().trim_matches('"');
+        let value = match key_value.next() {
+            Some(value) => value.replace(' ', ""),
+            None => continue,
+        };
+        let is_feature = match table {
+            "[features]" => key != "default",
+            "[dependencies]" | "[build-dependencies]" => value.contains("optional=true"),
+            _ => false,
+        };
+        if is_feature && !key.is_empty() && !res.contains(&key.to_string()) {
+            res.push(key.to_string());
+        }
+    }
+    Some(res)
+}
+
+//crate的目录中的文件，没有的话找crate所在workspace的根目录(Cargo.toml中有[workspace]的最近的上级目录)。
+//不继续向上查找，避免用到无关的Cargo.lock或toolchain文件
+fn find_in_crate(crate_path: &Path, file_name: &str) -> Option<PathBuf> {
+    let path = crate_path.join(file_name);
+    if path.is_file() {
+        return Some(path);
+    }
+    let workspace_root =
+        crate_path.ancestors().skip(1).find(|ancestor| is_workspace_root(ancestor))?;
+    let path = workspace_root.join(file_name);
+    if path.is_file() { Some(path) } else { None }
+}
+
+fn is_workspace_root(dir: &Path) -> bool {
+    match fs::read_to_string(dir.join(_CARGO_TOML)) {
+        Ok(content) => match content.parse::<toml::Value>() {
+            Ok(manifest) => manifest.get("workspace").is_some(),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+//从被测crate的Cargo.lock出发为manifest_dir中的fuzz crate解析依赖：cargo update --workspace保持
+//已经固定的版本不变，只加入fuzz crate新增的依赖(afl、helper函数需要的crate)，删掉用不到的
+fn resolve_lock(manifest_dir: &Path, seed_lock: Option<PathBuf>) -> Result<(), String> {
+    if let Some(seed_lock) = seed_lock {
+        fs::copy(&seed_lock, manifest_dir.join(_CARGO_LOCK))
+            .map_err(|e| format!("can not copy {}: {}", seed_lock.display(), e))?;
+    }
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = Command::new(cargo)
+        .arg("update")
+        .arg("--workspace")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join(_CARGO_TOML))
+        .output()
+        .map_err(|e| format!("can not run cargo to resolve {}: {}", _CARGO_LOCK, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "cargo can not resolve {}: {}",
+            _CARGO_LOCK,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+//RUSTUP_TOOLCHAIN可能是toolchain的路径，或者带host triple的名字(如stable-x86_64-unknown-linux-gnu)。
+//rust-toolchain.toml的channel是不带triple的名字，如stable、nightly-2020-08-03、1.45.2，或者自定义toolchain的名字
+fn toolchain_channel(toolchain: &str) -> Option<String> {
+    if toolchain.is_empty() || toolchain.contains('/') || toolchain.contains('\\') {
+        return None;
+    }
+    let parts: Vec<&str> = toolchain.split('-').collect();
+    let is_release = match parts[0] {
+        "stable" | "beta" | "nightly" => true,
+        part => part.starts_with(|c: char| c.is_ascii_digit()),
+    };
+    if !is_release {
+        return Some(toolchain.to_string());
+    }
+    let is_dated = parts.len() >= 4
+        && parts[1].len() == 4
+        && parts[1..4].iter().all(|part| part.chars().all(|c| c.is_ascii_digit()));
+    let channel_len = if is_dated { 4 } else { 1 };
+    Some(parts[..channel_len].join("-"))
+}
+
+fn find_in_ancestors(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    dir.ancestors().map(|ancestor| ancestor.join(file_name)).find(|path| path.is_file())
+}
+
 fn ensure_empty_dir(path: &PathBuf) {
     if path.is_file() {
         fs::remove_file(path).unwrap();
@@ -1,9 +1,10 @@
 use crate::clean::{self, PrimitiveType};
 use rustc_hir::Mutability;
 
+use crate::fuzz_target::api_util;
 use crate::fuzz_target::call_type::CallType;
 use crate::fuzz_target::impl_util::FullNameMap;
-use crate::fuzz_target::prelude_type::PreludeType;
+use crate::fuzz_target::prelude_type::{self, PreludeType};
 
 //如果构造一个fuzzable的变量
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -19,6 +20,8 @@ pub enum FuzzableCallType {
     BorrowedRef(Box<FuzzableCallType>),
     MutBorrowedRef(Box<FuzzableCallType>),
     ToOption(Box<FuzzableCallType>),
+    ZeroSized { type_name: String, value: String },
+    Struct { type_name: String, fields: Vec<(String, Box<FuzzableCallType>)> },
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -28,6 +31,13 @@ pub enum FuzzableType {
     RefSlice(Box<FuzzableType>),
     RefStr,
     Tuple(Vec<Box<FuzzableType>>),
+    //PhantomData and unit structs, which consume no input bytes
+    ZeroSized { type_name: String, value: String },
+    //所有字段都是pub的primitive类型的struct，按字段顺序从输入中读取，构造成字面量
+    Struct { type_name: String, fields: Vec<(String, Box<FuzzableType>)> },
+    //&mut [T]这样由调用者提供的输出buffer，只从输入读一个字节决定长度。
+    //undersized的buffer故意给得很小，用于adversarial变体
+    MutBuffer { inner: Box<FuzzableType>, undersized: bool },
 }
 
 impl FuzzableCallType {
@@ -109,6 +119,17 @@ impl FuzzableCallType {
             }
             FuzzableCallType::MutBorrowedRef(type_) => {
                 let inner_type = &**type_;
+                //输出buffer，用Vec构造，调用时&mut Vec<T>会自动转换成&mut [T]
+                if let FuzzableCallType::Slice(slice_inner) = inner_type {
+                    if let FuzzableCallType::Primitive(primitive) = &**slice_inner {
+                        let inner = Box::new(FuzzableType::Primitive(primitive.clone()));
+                        return (
+                            FuzzableType::MutBuffer { inner, undersized: false },
+                            CallType::_MutBorrowedRef(Box::new(CallType::_DirectCall)),
+                        );
+                    }
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
                 let (fuzzable_type, inner_call_type) =
                     inner_type.generate_fuzzable_type_and_call_type();
                 if let FuzzableType::NoFuzzable = fuzzable_type {
@@ -131,6 +152,33 @@ impl FuzzableCallType {
                 }
                 return (fuzzable_type, CallType::_ToOption(Box::new(inner_call_type)));
             }
+            FuzzableCallType::ZeroSized { type_name, value } => {
+                return (
+                    FuzzableType::ZeroSized { type_name: type_name.clone(), value: value.clone() },
+                    CallType::_DirectCall,
+                );
+            }
+            FuzzableCallType::Struct { type_name, fields } => {
+                let mut fuzzable_fields = Vec::new();
+                for (field_name, field_type) in fields {
+                    let (fuzzable_type, call_type) =
+                        field_type.generate_fuzzable_type_and_call_type();
+                    if let FuzzableType::NoFuzzable = fuzzable_type {
+                        return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                    }
+                    match call_type {
+                        CallType::_DirectCall => {}
+                        _ => {
+                            return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                        }
+                    }
+                    fuzzable_fields.push((field_name.clone(), Box::new(fuzzable_type)));
+                }
+                return (
+                    FuzzableType::Struct { type_name: type_name.clone(), fields: fuzzable_fields },
+                    CallType::_DirectCall,
+                );
+            }
             FuzzableCallType::Array(_) | FuzzableCallType::Slice(_) => {
                 return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
             } //_ => {
@@ -145,6 +193,11 @@ impl FuzzableType {
         match self {
             FuzzableType::NoFuzzable => true,
             FuzzableType::Primitive(_) => true,
+            FuzzableType::ZeroSized { .. } => true,
+            FuzzableType::MutBuffer { .. } => true,
+            FuzzableType::Struct { fields, .. } => {
+                fields.iter().all(|(_, field_fuzzable)| field_fuzzable._is_fixed_length())
+            }
             FuzzableType::RefSlice(_) => false,
             FuzzableType::RefStr => false,
             FuzzableType::Tuple(inner_fuzzables) => {
@@ -162,6 +215,9 @@ impl FuzzableType {
     pub fn _min_length(&self) -> usize {
         match self {
             FuzzableType::NoFuzzable => 0,
+            FuzzableType::ZeroSized { .. } => 0,
+            //一个字节的长度
+            FuzzableType::MutBuffer { .. } => 1,
             FuzzableType::Primitive(primitive_type) => {
                 match primitive_type {
                     //TODO:Bool变量的长度是多少
@@ -192,6 +248,9 @@ impl FuzzableType {
                 }
                 total_length
             }
+            FuzzableType::Struct { fields, .. } => {
+                fields.iter().map(|(_, field_fuzzable)| field_fuzzable._min_length()).sum()
+            }
         }
     }
 
@@ -216,6 +275,37 @@ impl FuzzableType {
         }
     }
 
+    //"最可能有效"的输入中参数定长部分的字节：整数取1，浮点数取1.0，char取'a'，bool取true
+    pub fn _viable_fixed_part(&self) -> Vec<u8> {
+        match self {
+            FuzzableType::Primitive(primitive) => {
+                let length = self._min_length();
+                match primitive {
+                    PrimitiveType::Bool => vec![0u8; length],
+                    PrimitiveType::F32 => 1.0f32.to_le_bytes().to_vec(),
+                    PrimitiveType::F64 => 1.0f64.to_le_bytes().to_vec(),
+                    _ => {
+                        //整数和char都按大端序解析
+                        let mut bytes = vec![0u8; length];
+                        if let Some(last_byte) = bytes.last_mut() {
+                            *last_byte = if *primitive == PrimitiveType::Char { b'a' } else { 1 };
+                        }
+                        bytes
+                    }
+                }
+            }
+            FuzzableType::Tuple(inner_fuzzables) => inner_fuzzables
+                .iter()
+                .flat_map(|inner_fuzzable| inner_fuzzable._viable_fixed_part())
+                .collect(),
+            FuzzableType::Struct { fields, .. } => fields
+                .iter()
+                .flat_map(|(_, field_fuzzable)| field_fuzzable._viable_fixed_part())
+                .collect(),
+            _ => vec![0u8; self._fixed_part_length()],
+        }
+    }
+
     //计算长度不固定的参数的个数，主要是需要迭代考虑元组的内部
     pub fn _dynamic_length_param_number(&self) -> usize {
         if self._is_fixed_length() {
@@ -259,6 +349,26 @@ impl FuzzableType {
         }
     }
 
+    //--check-harness能否用{:?}打印这个参数。ZeroSized和Struct是被测crate中的类型，不一定实现了Debug；
+    //std只给最多12个元素的tuple实现了Debug
+    pub fn _is_debug_printable(&self) -> bool {
+        match self {
+            FuzzableType::NoFuzzable => false,
+            FuzzableType::Primitive(_) => true,
+            FuzzableType::RefStr => true,
+            FuzzableType::RefSlice(inner_fuzzable) => inner_fuzzable._is_debug_printable(),
+            FuzzableType::MutBuffer { inner, .. } => inner._is_debug_printable(),
+            FuzzableType::ZeroSized { .. } => false,
+            FuzzableType::Struct { .. } => false,
+            FuzzableType::Tuple(inner_fuzzables) => {
+                inner_fuzzables.len() <= 12
+                    && inner_fuzzables
+                        .iter()
+                        .all(|inner_fuzzable| inner_fuzzable._is_debug_printable())
+            }
+        }
+    }
+
     pub fn _to_type_string(&self) -> String {
         match self {
             FuzzableType::NoFuzzable => "nofuzzable".to_string(),
@@ -271,6 +381,9 @@ impl FuzzableType {
                 res
             }
             FuzzableType::RefStr => "&str".to_string(),
+            FuzzableType::ZeroSized { type_name, .. } => type_name.clone(),
+            FuzzableType::Struct { type_name, .. } => type_name.clone(),
+            FuzzableType::MutBuffer { inner, .. } => format!("Vec<{}>", inner._to_type_string()),
             FuzzableType::Tuple(inner_types) => {
                 let mut res = "(".to_string();
                 let first_type = inner_types.first();
@@ -297,7 +410,13 @@ impl FuzzableType {
 //判断一个类型是不是fuzzable的，以及如何调用相应的fuzzable变量
 pub fn fuzzable_call_type(ty_: &clean::Type, full_name_map: &FullNameMap) -> FuzzableCallType {
     match ty_ {
-        clean::Type::ResolvedPath { .. } => {
+        clean::Type::ResolvedPath { path, did, .. } => {
+            if let Some(zero_sized) = zero_sized_call_type(ty_, path, did, full_name_map) {
+                return zero_sized;
+            }
+            if let Some(literal_struct) = literal_struct_call_type(ty_, did, full_name_map) {
+                return literal_struct;
+            }
             let prelude_type = PreludeType::from_type(ty_, full_name_map);
             //result类型的变量不应该作为fuzzable的变量。只考虑作为别的函数的返回值
             match &prelude_type {
@@ -428,3 +547,106 @@ pub fn fuzzable_call_type(ty_: &clean::Type, full_name_map: &FullNameMap) -> Fuz
         }
     }
 }
+
+//PhantomData和unit struct不需要消耗任何输入，直接构造即可
+fn zero_sized_call_type(
+    ty_: &clean::Type,
+    path: &clean::Path,
+    did: &rustc_hir::def_id::DefId,
+    full_name_map: &FullNameMap,
+) -> Option<FuzzableCallType> {
+    if full_name_map._is_zero_sized_type(did) {
+        let type_name = api_util::_type_name(ty_, full_name_map);
+        let value = type_name.clone();
+        return Some(FuzzableCallType::ZeroSized { type_name, value });
+    }
+    if !prelude_type::is_phantom_data(ty_, full_name_map) {
+        return None;
+    }
+    let inner_type = path.segments.last().and_then(|segment| match &segment.args {
+        clean::GenericArgs::AngleBracketed { args, .. } => args.iter().find_map(|arg| match arg {
+            clean::GenericArg::Type(inner_type) => Some(inner_type.clone()),
+            _ => None,
+        }),
+        clean::GenericArgs::Parenthesized { .. } => None,
+    });
+    let inner_type_name = match inner_type {
+        Some(inner_type) => api_util::_type_name(&inner_type, full_name_map),
+        None => "()".to_string(),
+    };
+    //the marker type is not visible in current crate
+    if inner_type_name == "Currently not supported" {
+        return Some(FuzzableCallType::NoFuzzable);
+    }
+    let type_name = format!("std::marker::PhantomData<{}>", inner_type_name);
+    let value = "std::marker::PhantomData".to_string();
+    Some(FuzzableCallType::ZeroSized { type_name, value })
+}
+
+//所有字段都是pub的primitive类型的struct，不需要构造函数，直接用输入构造字面量
+fn literal_struct_call_type(
+    ty_: &clean::Type,
+    did: &rustc_hir::def_id::DefId,
+    full_name_map: &FullNameMap,
+) -> Option<FuzzableCallType> {
+    let struct_fields = full_name_map._literal_struct_fields(did)?;
+    let type_name = api_util::_type_name(ty_, full_name_map);
+    if type_name == "Currently not supported" {
+        return None;
+    }
+    let mut fields = Vec::new();
+    for (field_name, field_type) in struct_fields {
+        match fuzzable_call_type(field_type, full_name_map) {
+            FuzzableCallType::NoFuzzable => return None,
+            field_fuzzable => fields.push((field_name.clone(), Box::new(field_fuzzable))),
+        }
+    }
+    Some(FuzzableCallType::Struct { type_name, fields })
+}
+
+//在render时判断一个struct能否用字面量构造：没有泛型参数，没有被隐藏的字段，
+//并且所有字段都是pub的、可以由afl helper直接读出来的primitive类型
+pub fn literal_struct_fields(struct_: &clean::Struct) -> Option<Vec<(String, clean::Type)>> {
+    if !struct_.generics.params.is_empty() || struct_.fields_stripped || struct_.fields.is_empty() {
+        return None;
+    }
+    let mut fields = Vec::new();
+    for field in &struct_.fields {
+        if field.visibility != clean::Visibility::Public {
+            return None;
+        }
+        let field_type = match &field.inner {
+            clean::StructFieldItem(field_type) => field_type,
+            _ => return None,
+        };
+        match field_type {
+            clean::Type::Primitive(primitive) if is_literal_field_primitive(primitive) => {}
+            _ => return None,
+        }
+        let field_name = field.name.clone()?;
+        fields.push((field_name, field_type.clone()));
+    }
+    Some(fields)
+}
+
+fn is_literal_field_primitive(primitive: &PrimitiveType) -> bool {
+    match primitive {
+        PrimitiveType::Isize
+        | PrimitiveType::I8
+        | PrimitiveType::I16
+        | PrimitiveType::I32
+        | PrimitiveType::I64
+        | PrimitiveType::I128
+        | PrimitiveType::Usize
+        | PrimitiveType::U8
+        | PrimitiveType::U16
+        | PrimitiveType::U32
+        | PrimitiveType::U64
+        | PrimitiveType::U128
+        | PrimitiveType::F32
+        | PrimitiveType::F64
+        | PrimitiveType::Char
+        | PrimitiveType::Bool => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,344 @@
+//! Programmatic entry points of the fuzz target generator.
+//!
+//! The `fuzz-target-generator` binary only calls `rustdoc::fuzz_target_generator_main`
+//! through `rulf_core::run`. Tools that want to plug in their own function filter or traversal
+//! strategy depend on the `rulf-core` crate and call `rulf_core::run_with` with their options,
+//! which adjusts the `SequenceGenerator` through `rustdoc::fuzz_target_generator_run`.
+
+use crate::fuzz_target::api_function::ApiFunction;
+use crate::fuzz_target::api_graph::{ApiGraph, GraphTraverseAlgorithm};
+use crate::fuzz_target::api_sequence::ApiSequence;
+use crate::fuzz_target::file_util::{self, FuzzerProject};
+use crate::fuzz_target::usage_util;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+
+/// A user supplied predicate deciding which API functions are kept in the graph.
+pub type FunctionFilter = Box<dyn Fn(&ApiFunction) -> bool + Send + Sync>;
+
+/// Drives sequence generation over an `ApiGraph` whose functions have already been collected.
+pub struct SequenceGenerator {
+    /// Traversal algorithm. `None` is the default strategy: BFS stopping at end functions,
+    /// followed by backward search for the functions BFS could not reach. Defaults to the
+    /// strategy named by `FUZZ_TARGET_STRATEGY`, where `random-walk` selects the weighted
+    /// random walk.
+    pub algorithm: Option<GraphTraverseAlgorithm>,
+    /// Whether round trips between pure converter functions are pruned before traversal.
+    pub prune_converter_cycles: bool,
+    /// Extra filter applied after the builtin prelude type and mod visibility filters.
+    pub function_filter: Option<FunctionFilter>,
+    /// Whether every (trait, type) impl gets a target calling all of its methods, in addition
+    /// to the sequences chosen for function coverage. Defaults to `FUZZ_TARGET_TRAIT_COVERAGE`
+    /// being set in the environment.
+    pub trait_coverage: bool,
+    /// Directory holding sources of crates that depend on the crate under test. When set, the
+    /// APIs and call orders used there are preferred when choosing sequences. Defaults to
+    /// `FUZZ_TARGET_USAGE_CORPUS`; `scripts/download-reverse-dependencies` fills such a directory.
+    pub usage_corpus: Option<PathBuf>,
+    /// Whether one extra target builds every type implementing `Debug` or `Display` and
+    /// formats it with `{:?}`, `{:#?}` and `{}`. Defaults to `FUZZ_TARGET_FORMAT_SWEEP` being set.
+    pub format_sweep: bool,
+    /// File listing APIs, one full name or path prefix per line, that need more sequences,
+    /// e.g. because fuzzing showed their code is poorly covered. Extra sequences ending at
+    /// these APIs are emitted first. Defaults to `FUZZ_TARGET_FOCUS_APIS`.
+    pub focus_apis: Option<PathBuf>,
+    /// File of golden serialized forms, one `type name<TAB>json` per line, as written by targets
+    /// run with `RULF_SERDE_RECORD`. When set, types implementing both `Serialize` and
+    /// `Deserialize` get targets asserting that the forms recorded for them still deserialize
+    /// and serialize back unchanged. Defaults to `FUZZ_TARGET_SERDE_COMPAT`.
+    pub serde_golden: Option<PathBuf>,
+    /// Whether stateful targets are added. Each one builds a value with the shortest sequence
+    /// producing it and then calls its `&mut self` methods in an order chosen by input bytes.
+    /// Defaults to `FUZZ_TARGET_STATEFUL` being set.
+    pub stateful: bool,
+}
+
+impl Default for SequenceGenerator {
+    fn default() -> Self {
+        SequenceGenerator {
+            algorithm: traverse_strategy(),
+            prune_converter_cycles: true,
+            function_filter: None,
+            trait_coverage: std::env::var_os("FUZZ_TARGET_TRAIT_COVERAGE").is_some(),
+            usage_corpus: std::env::var_os("FUZZ_TARGET_USAGE_CORPUS").map(PathBuf::from),
+            format_sweep: std::env::var_os("FUZZ_TARGET_FORMAT_SWEEP").is_some(),
+            focus_apis: std::env::var_os("FUZZ_TARGET_FOCUS_APIS").map(PathBuf::from),
+            serde_golden: std::env::var_os("FUZZ_TARGET_SERDE_COMPAT").map(PathBuf::from),
+            stateful: std::env::var_os("FUZZ_TARGET_STATEFUL").is_some(),
+        }
+    }
+}
+
+impl SequenceGenerator {
+    /// Creates a generator using the given traversal algorithm.
+    pub fn new(algorithm: GraphTraverseAlgorithm) -> Self {
+        SequenceGenerator { algorithm: Some(algorithm), ..SequenceGenerator::default() }
+    }
+
+    /// Keeps only the API functions for which `filter` returns true.
+    pub fn with_function_filter(mut self, filter: FunctionFilter) -> Self {
+        self.function_filter = Some(filter);
+        self
+    }
+
+    /// Whether the traversal is one of the random strategies, which write to the random test
+    /// directories and use a different sequence selection.
+    pub fn is_random_strategy(&self) -> bool {
+        match self.algorithm {
+            Some(GraphTraverseAlgorithm::_RandomWalk)
+            | Some(GraphTraverseAlgorithm::_RandomWalkEndPoint) => true,
+            _ => false,
+        }
+    }
+
+    /// Filters the functions of `api_graph`, computes their dependencies and fills
+    /// `api_graph.api_sequences`.
+    pub fn generate(&self, api_graph: &mut ApiGraph) {
+        //泛型参数只是错误类型的函数，用crate中产生的错误类型实例化
+        api_graph._instantiate_error_generics();
+        //其他泛型函数用trait solver检查过约束的基本类型和crate中的类型实例化
+        api_graph._instantiate_generics();
+        //const泛型参数用一组固定的值实例化
+        api_graph._instantiate_const_generics();
+        //返回impl Stream的函数，加入取出stream item的helper函数
+        api_graph._add_stream_consumers();
+        //返回impl Iterator的函数，加入调用next取出item的helper函数
+        api_graph._add_iterator_consumers();
+        api_graph._add_ecosystem_constructors();
+        //FUZZ_TARGET_PRODUCER_CRATES中的依赖crate的构造函数
+        api_graph._add_producer_functions();
+        //enum的变体当作构造函数
+        api_graph._add_variant_constructors();
+        if self.format_sweep {
+            for format_function in api_graph.format_functions.clone() {
+                api_graph.add_api_function(format_function);
+            }
+        }
+        if let Some(ref serde_golden) = self.serde_golden {
+            for serde_function in api_graph.serde_functions.clone() {
+                api_graph.add_api_function(serde_function);
+            }
+            api_graph.serde_golden = read_serde_golden(serde_golden);
+            println!("serde compat: {} golden forms", api_graph.serde_golden.len());
+        }
+        //根据mod可见性和预包含类型过滤function
+        api_graph.filter_functions();
+        if let Some(ref function_filter) = self.function_filter {
+            api_graph.api_functions.retain(|api_function| function_filter(api_function));
+        }
+        if let Some(ref usage_corpus) = self.usage_corpus {
+            api_graph.api_usage =
+                usage_util::mine_api_usage(usage_corpus, &api_graph.api_functions);
+        }
+        api_graph._report_metadata_errors();
+        api_graph._report_non_exhaustive_types();
+        //寻找所有依赖，并且构建序列
+        api_graph.find_all_dependencies();
+        api_graph._apply_annotated_constructors();
+        if self.prune_converter_cycles {
+            api_graph.prune_converter_cycles();
+        }
+        match self.algorithm {
+            None => api_graph.default_generate_sequences(),
+            Some(algorithm) => api_graph.generate_all_possoble_sequences(algorithm),
+        }
+        if self.trait_coverage {
+            api_graph._cover_trait_impls();
+        }
+        if self.stateful {
+            api_graph._build_stateful_sequences();
+        }
+        if self.format_sweep {
+            api_graph._build_format_sweep();
+        }
+        //#[rulf::fuzz_entry]标注的api和focus api一样额外选择序列
+        let mut focus_apis = api_graph.fuzz_entries.clone();
+        if let Some(ref focus_apis_file) = self.focus_apis {
+            focus_apis.extend(
+                fs::read_to_string(focus_apis_file)
+                    .unwrap_or_default()
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#')),
+            );
+        }
+        if !focus_apis.is_empty() {
+            api_graph._build_focus_sequences(&focus_apis);
+        }
+    }
+}
+
+//FUZZ_TARGET_STRATEGY=bfs|random-walk，bfs是默认的策略
+fn traverse_strategy() -> Option<GraphTraverseAlgorithm> {
+    match std::env::var("FUZZ_TARGET_STRATEGY") {
+        Ok(strategy) => match strategy.trim() {
+            "" | "bfs" => None,
+            "random-walk" => Some(GraphTraverseAlgorithm::_WeightedRandomWalk),
+            _ => {
+                println!("invalid traverse strategy {}", strategy);
+                None
+            }
+        },
+        Err(_) => None,
+    }
+}
+
+//同一个类型记录的golden形式去重，并且最多保留MAX_GOLDEN_FORMS_PER_TYPE个，避免target过大。
+//第一次生成时文件还不存在，所有target都没有golden形式
+static MAX_GOLDEN_FORMS_PER_TYPE: usize = 32;
+
+fn read_serde_golden(serde_golden: &PathBuf) -> Vec<(String, String)> {
+    let mut forms: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for line in fs::read_to_string(serde_golden).unwrap_or_default().lines() {
+        let mut type_and_form = line.splitn(2, '\t');
+        if let (Some(type_name), Some(form)) = (type_and_form.next(), type_and_form.next()) {
+            let type_forms = forms.entry(type_name.to_string()).or_default();
+            if type_forms.len() < MAX_GOLDEN_FORMS_PER_TYPE {
+                type_forms.insert(form.to_string());
+            }
+        }
+    }
+    forms
+        .into_iter()
+        .flat_map(|(type_name, type_forms)| {
+            type_forms.into_iter().map(move |form| (type_name.clone(), form))
+        })
+        .collect()
+}
+
+/// A fuzzing engine the generated sequences can be rendered for.
+///
+/// The afl targets are always written. Every other backend named in `FUZZ_TARGET_FUZZER`
+/// gets a cargo project of its own in the output directory, so one analysis pass can emit
+/// targets for several engines at once.
+pub trait FuzzBackend: Sync {
+    /// The name of the backend in `FUZZ_TARGET_FUZZER`.
+    fn name(&self) -> &'static str;
+
+    /// Renders `sequence` as the source of a complete target file.
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String;
+
+    /// The layout of the project holding the targets, or `None` when the targets are written
+    /// to `test_files` like the afl targets.
+    fn project(&self) -> Option<&'static FuzzerProject>;
+}
+
+/// afl.rs targets using the `fuzz!` macro.
+pub struct AflBackend;
+
+/// cargo-fuzz targets using the `fuzz_target!` macro.
+pub struct LibFuzzerBackend;
+
+/// honggfuzz-rs targets calling the `fuzz!` macro in a loop.
+pub struct HonggfuzzBackend;
+
+/// bolero targets calling `bolero::check!` from `main`.
+pub struct BoleroBackend;
+
+/// Kani proof harnesses reading the input bytes from `kani::any()`, so that the sequences can
+/// be model-checked instead of fuzzed.
+pub struct KaniBackend;
+
+/// Plain binaries running the sequence once for every file in a seed directory, to check
+/// many targets quickly without a fuzzer.
+pub struct SmokeBackend;
+
+impl FuzzBackend for AflBackend {
+    fn name(&self) -> &'static str {
+        "afl"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_afl_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        None
+    }
+}
+
+impl FuzzBackend for LibFuzzerBackend {
+    fn name(&self) -> &'static str {
+        "libfuzzer"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_libfuzzer_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        Some(&file_util::CARGO_FUZZ_PROJECT)
+    }
+}
+
+impl FuzzBackend for HonggfuzzBackend {
+    fn name(&self) -> &'static str {
+        "honggfuzz"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_honggfuzz_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        Some(&file_util::HONGGFUZZ_PROJECT)
+    }
+}
+
+impl FuzzBackend for BoleroBackend {
+    fn name(&self) -> &'static str {
+        "bolero"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_bolero_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        Some(&file_util::BOLERO_PROJECT)
+    }
+}
+
+impl FuzzBackend for KaniBackend {
+    fn name(&self) -> &'static str {
+        "kani"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_kani_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        Some(&file_util::KANI_PROJECT)
+    }
+}
+
+impl FuzzBackend for SmokeBackend {
+    fn name(&self) -> &'static str {
+        "smoke"
+    }
+
+    fn render(&self, sequence: &ApiSequence, api_graph: &ApiGraph, test_index: usize) -> String {
+        sequence._to_smoke_test_file(api_graph, test_index)
+    }
+
+    fn project(&self) -> Option<&'static FuzzerProject> {
+        Some(&file_util::SMOKE_PROJECT)
+    }
+}
+
+/// Every backend the generator can render targets for.
+pub static FUZZ_BACKENDS: [&'static dyn FuzzBackend; 6] = [
+    &AflBackend,
+    &LibFuzzerBackend,
+    &HonggfuzzBackend,
+    &BoleroBackend,
+    &KaniBackend,
+    &SmokeBackend,
+];
+
+/// Looks up a backend by its name in `FUZZ_TARGET_FUZZER`.
+pub fn fuzz_backend(name: &str) -> Option<&'static dyn FuzzBackend> {
+    FUZZ_BACKENDS.iter().copied().find(|backend| backend.name() == name)
+}
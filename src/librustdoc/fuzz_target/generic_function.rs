@@ -4,6 +4,11 @@ use crate::clean;
 
 use super::api_function::ApiFunction;
 
+//只以这些trait为约束的泛型参数，可以用crate中其他api返回的错误类型来实例化
+static _ERROR_BOUNDS: [&'static str; 6] = ["Display", "Debug", "Error", "Send", "Sync", "Sized"];
+//auto trait和Sized不需要检查错误类型是否实现
+static _AUTO_BOUNDS: [&'static str; 3] = ["Send", "Sync", "Sized"];
+
 #[derive(Debug, Clone)]
 pub struct GenericFunction {
     pub api_function: ApiFunction,
@@ -15,3 +20,285 @@ impl From<ApiFunction> for GenericFunction {
         GenericFunction { api_function, generic_substitute: HashMap::new() }
     }
 }
+
+impl GenericFunction {
+    //函数只有一个类型参数，并且约束都是错误类型常见的trait，如fn log_err<E: Display>(e: E)
+    //返回参数名和需要错误类型实现的trait
+    pub fn _error_type_param(&self) -> Option<(String, Vec<String>)> {
+        let generics = &self.api_function.generics;
+        let mut type_params = Vec::new();
+        for param in &generics.params {
+            match &param.kind {
+                clean::GenericParamDefKind::Lifetime => {}
+                clean::GenericParamDefKind::Type { bounds, synthetic: None, .. } => {
+                    type_params.push((param.name.clone(), bounds.clone()));
+                }
+                _ => return None,
+            }
+        }
+        if type_params.len() != 1 {
+            return None;
+        }
+        let (param_name, mut bounds) = type_params.pop().unwrap();
+        for where_predicate in &generics.where_predicates {
+            if let clean::WherePredicate::BoundPredicate { ty: clean::Generic(name), bounds: b } =
+                where_predicate
+            {
+                if *name == param_name {
+                    bounds.extend(b.iter().cloned());
+                }
+            }
+        }
+        let mut required_traits = Vec::new();
+        for bound in &bounds {
+            if let clean::GenericBound::TraitBound(poly_trait, _) = bound {
+                let trait_name = match &poly_trait.trait_ {
+                    clean::ResolvedPath { path, .. } => path.segments.last()?.name.clone(),
+                    _ => return None,
+                };
+                if !_ERROR_BOUNDS.contains(&trait_name.as_str()) {
+                    return None;
+                }
+                if !_AUTO_BOUNDS.contains(&trait_name.as_str()) {
+                    required_traits.push(trait_name);
+                }
+            }
+        }
+        Some((param_name, required_traits))
+    }
+
+    //用具体的类型替换泛型参数，得到可以加入图中的api
+    pub fn _instantiate(
+        &self,
+        param_name: &String,
+        concrete_type: &clean::Type,
+        concrete_type_name: &String,
+    ) -> ApiFunction {
+        self._instantiate_all(
+            &[(param_name.clone(), concrete_type.clone(), concrete_type_name.clone())],
+            0,
+        )
+    }
+
+    //同时替换多个泛型参数，substitutes按泛型参数声明的顺序给出(参数名, 具体类型, 类型名)，
+    //类型名按同样的顺序写入turbofish。前impl_param_count个是泛型impl块的参数，
+    //写在类型名后面(Stack::<u8>::push)；通过trait名调用的方法由self参数推导这些参数
+    pub fn _instantiate_all(
+        &self,
+        substitutes: &[(String, clean::Type, String)],
+        impl_param_count: usize,
+    ) -> ApiFunction {
+        let mut api_function = self.api_function.clone();
+        let (impl_substitutes, method_substitutes) = substitutes.split_at(impl_param_count);
+        if let Some(type_full_path) = &api_function._impl_type_full_path {
+            let type_prefix = format!("{}::", type_full_path);
+            if !impl_substitutes.is_empty() && api_function.full_name.starts_with(&type_prefix) {
+                api_function.full_name = format!(
+                    "{}::<{}>::{}",
+                    type_full_path,
+                    _turbofish_type_names(impl_substitutes),
+                    &api_function.full_name[type_prefix.len()..]
+                );
+            }
+        }
+        if !method_substitutes.is_empty() {
+            api_function.full_name = format!(
+                "{}::<{}>",
+                api_function.full_name,
+                _turbofish_type_names(method_substitutes)
+            );
+        }
+        api_function.generics = clean::Generics::default();
+        api_function.inputs = api_function
+            .inputs
+            .iter()
+            .map(|input| _substitute_generic(input, substitutes))
+            .collect();
+        api_function.output =
+            api_function.output.as_ref().map(|output| _substitute_generic(output, substitutes));
+        api_function
+    }
+
+    //只有const泛型参数(以及生命周期参数)的函数，返回const参数的名字。
+    //只考虑整数类型的const参数
+    pub fn _const_params(&self) -> Option<Vec<(String, clean::PrimitiveType)>> {
+        let mut const_params = Vec::new();
+        for param in &self.api_function.generics.params {
+            match &param.kind {
+                clean::GenericParamDefKind::Lifetime => {}
+                clean::GenericParamDefKind::Const {
+                    ty: clean::Primitive(primitive_type), ..
+                } if _const_max_value(primitive_type).is_some() => {
+                    const_params.push((param.name.clone(), *primitive_type));
+                }
+                _ => return None,
+            }
+        }
+        if const_params.is_empty() { None } else { Some(const_params) }
+    }
+
+    //用具体的值替换const泛型参数，值按参数声明的顺序写入turbofish
+    pub fn _instantiate_consts(&self, values: &[(String, String)]) -> ApiFunction {
+        let mut api_function = self.api_function.clone();
+        let const_values: Vec<&str> = values.iter().map(|(_, value)| value.as_str()).collect();
+        api_function.full_name =
+            format!("{}::<{}>", api_function.full_name, const_values.join(", "));
+        api_function.generics = clean::Generics::default();
+        api_function.inputs =
+            api_function.inputs.iter().map(|input| _substitute_const(input, values)).collect();
+        api_function.output =
+            api_function.output.as_ref().map(|output| _substitute_const(output, values));
+        api_function
+    }
+}
+
+//整数类型的const参数能取的最大值
+pub fn _const_max_value(primitive_type: &clean::PrimitiveType) -> Option<u128> {
+    match primitive_type {
+        clean::PrimitiveType::U8 => Some(u8::MAX as u128),
+        clean::PrimitiveType::U16 => Some(u16::MAX as u128),
+        clean::PrimitiveType::U32 => Some(u32::MAX as u128),
+        clean::PrimitiveType::U64 | clean::PrimitiveType::Usize => Some(u64::MAX as u128),
+        clean::PrimitiveType::U128 => Some(u128::MAX),
+        clean::PrimitiveType::I8 => Some(i8::MAX as u128),
+        clean::PrimitiveType::I16 => Some(i16::MAX as u128),
+        clean::PrimitiveType::I32 => Some(i32::MAX as u128),
+        clean::PrimitiveType::I64 | clean::PrimitiveType::Isize => Some(i64::MAX as u128),
+        clean::PrimitiveType::I128 => Some(i128::MAX as u128),
+        _ => None,
+    }
+}
+
+//[u8; N]中的长度和Foo<N>中的const参数替换为具体的值
+fn _substitute_const(ty_: &clean::Type, values: &[(String, String)]) -> clean::Type {
+    let const_value = |expr: &String| {
+        values.iter().find(|(param_name, _)| param_name == expr).map(|(_, value)| value.clone())
+    };
+    match ty_ {
+        clean::Array(type_, length) => clean::Array(
+            Box::new(_substitute_const(type_, values)),
+            const_value(length).unwrap_or_else(|| length.clone()),
+        ),
+        clean::BorrowedRef { lifetime, mutability, type_ } => clean::BorrowedRef {
+            lifetime: lifetime.clone(),
+            mutability: *mutability,
+            type_: Box::new(_substitute_const(type_, values)),
+        },
+        clean::Slice(type_) => clean::Slice(Box::new(_substitute_const(type_, values))),
+        clean::Tuple(types) => {
+            clean::Tuple(types.iter().map(|type_| _substitute_const(type_, values)).collect())
+        }
+        clean::ResolvedPath { path, param_names, did, is_generic } => {
+            let mut path = path.clone();
+            for segment in &mut path.segments {
+                if let clean::GenericArgs::AngleBracketed { args, .. } = &mut segment.args {
+                    for arg in args.iter_mut() {
+                        match arg {
+                            clean::GenericArg::Type(inner_type) => {
+                                *inner_type = _substitute_const(inner_type, values);
+                            }
+                            clean::GenericArg::Const(constant) => {
+                                if let Some(value) = const_value(&constant.expr) {
+                                    constant.expr = value.clone();
+                                    constant.value = Some(value);
+                                    constant.is_literal = true;
+                                }
+                            }
+                            clean::GenericArg::Lifetime(_) => {}
+                        }
+                    }
+                }
+            }
+            clean::ResolvedPath {
+                path,
+                param_names: param_names.clone(),
+                did: *did,
+                is_generic: *is_generic,
+            }
+        }
+        _ => ty_.clone(),
+    }
+}
+
+fn _turbofish_type_names(substitutes: &[(String, clean::Type, String)]) -> String {
+    let type_names: Vec<&str> =
+        substitutes.iter().map(|(_, _, type_name)| type_name.as_str()).collect();
+    type_names.join(", ")
+}
+
+pub fn _substitute_generic(
+    ty_: &clean::Type,
+    substitutes: &[(String, clean::Type, String)],
+) -> clean::Type {
+    match ty_ {
+        clean::Generic(name) => {
+            match substitutes.iter().find(|(param_name, ..)| param_name == name) {
+                Some((_, concrete_type, _)) => concrete_type.clone(),
+                None => ty_.clone(),
+            }
+        }
+        clean::BorrowedRef { lifetime, mutability, type_ } => clean::BorrowedRef {
+            lifetime: lifetime.clone(),
+            mutability: *mutability,
+            type_: Box::new(_substitute_generic(type_, substitutes)),
+        },
+        clean::Slice(type_) => clean::Slice(Box::new(_substitute_generic(type_, substitutes))),
+        clean::Array(type_, length) => {
+            clean::Array(Box::new(_substitute_generic(type_, substitutes)), length.clone())
+        }
+        clean::RawPointer(mutability, type_) => {
+            clean::RawPointer(*mutability, Box::new(_substitute_generic(type_, substitutes)))
+        }
+        clean::Tuple(types) => clean::Tuple(
+            types.iter().map(|type_| _substitute_generic(type_, substitutes)).collect(),
+        ),
+        //<T as Trait>::Name
+        clean::QPath { name, self_type, trait_ } => clean::QPath {
+            name: name.clone(),
+            self_type: Box::new(_substitute_generic(self_type, substitutes)),
+            trait_: Box::new(_substitute_generic(trait_, substitutes)),
+        },
+        //Option<T>、Vec<Option<T>>、Box<dyn Iterator<Item = T>>、Box<dyn Fn(T) -> T>等路径参数中的泛型
+        clean::ResolvedPath { path, param_names, did, is_generic } => {
+            let mut path = path.clone();
+            for segment in &mut path.segments {
+                _substitute_generic_args(&mut segment.args, substitutes);
+            }
+            clean::ResolvedPath {
+                path,
+                param_names: param_names.clone(),
+                did: *did,
+                is_generic: *is_generic,
+            }
+        }
+        _ => ty_.clone(),
+    }
+}
+
+fn _substitute_generic_args(
+    generic_args: &mut clean::GenericArgs,
+    substitutes: &[(String, clean::Type, String)],
+) {
+    match generic_args {
+        clean::GenericArgs::AngleBracketed { args, bindings } => {
+            for arg in args.iter_mut() {
+                if let clean::GenericArg::Type(inner_type) = arg {
+                    *inner_type = _substitute_generic(inner_type, substitutes);
+                }
+            }
+            for binding in bindings.iter_mut() {
+                if let clean::TypeBindingKind::Equality { ty } = &mut binding.kind {
+                    *ty = _substitute_generic(ty, substitutes);
+                }
+            }
+        }
+        clean::GenericArgs::Parenthesized { inputs, output } => {
+            for input in inputs.iter_mut() {
+                *input = _substitute_generic(input, substitutes);
+            }
+            if let Some(output) = output {
+                *output = _substitute_generic(output, substitutes);
+            }
+        }
+    }
+}
@@ -1,15 +1,114 @@
 use crate::clean::{self, types::GetDefId};
 use crate::fuzz_target::api_function::ApiFunction;
 use crate::fuzz_target::api_util;
+use crate::fuzz_target::bug_report_util::{self, MetadataError};
+use crate::fuzz_target::generic_function;
 use crate::html::item_type::ItemType;
 use crate::html::render::cache::Cache;
+use rustc_data_structures::fx::FxHashMap;
 use rustc_hir::def_id::DefId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 //TODO:是否需要为impl里面的method重新设计数据结构？目前沿用了ApiFunction,或者直接对ApiFunction进行扩展
 //两种函数目前相差一个defaultness
 use crate::fuzz_target::api_function::ApiUnsafety;
 use crate::fuzz_target::api_graph::ApiGraph;
-use crate::fuzz_target::prelude_type;
+use crate::fuzz_target::prelude_type::{self, _PreludeHelper};
+use crate::fuzz_target::relation_util;
+use rustc_ast::attr;
+use rustc_hir::Mutability;
+use rustc_span::symbol::sym;
+
+lazy_static! {
+    //不在当前crate中、但其方法可以作为api的std trait，在harness中需要use引入。
+    //TryFrom::try_from返回Result，Err时由_unwrap_result直接退出
+    //derive和过程宏(thiserror的#[from]、derive_builder等)生成的构造和转换大多是这几个trait的impl
+    static ref STD_CONVERSION_TRAITS: HashMap<&'static str, &'static str> = {
+        let mut m = HashMap::new();
+        m.insert("TryFrom", "std::convert::TryFrom");
+        m.insert("From", "std::convert::From");
+        m.insert("FromStr", "std::str::FromStr");
+        m.insert("Default", "std::default::Default");
+        m
+    };
+    //运算符trait：trait名 -> trait路径，以及方法的全名 -> 调用时的写法，{0}和{1}是两个参数。
+    //这些trait的方法同样作为api，在harness中写成对应的运算符
+    static ref STD_OPERATOR_TRAITS: HashMap<String, String> = {
+        let mut m = HashMap::new();
+        for (trait_name, trait_path, _, _) in _operator_methods() {
+            m.insert(trait_name, trait_path);
+        }
+        m
+    };
+    static ref OPERATOR_CALLS: HashMap<String, String> = {
+        let mut m = HashMap::new();
+        for (_, trait_path, method_name, call_pattern) in _operator_methods() {
+            m.insert(format!("{}::{}", trait_path, method_name), call_pattern);
+        }
+        m
+    };
+}
+
+//二元运算符trait的名字，方法名和运算符，对应的XxxAssign trait写成复合赋值
+static BINARY_OPERATORS: [(&'static str, &'static str, &'static str); 10] = [
+    ("Add", "add", "+"),
+    ("Sub", "sub", "-"),
+    ("Mul", "mul", "*"),
+    ("Div", "div", "/"),
+    ("Rem", "rem", "%"),
+    ("BitAnd", "bitand", "&"),
+    ("BitOr", "bitor", "|"),
+    ("BitXor", "bitxor", "^"),
+    ("Shl", "shl", "<<"),
+    ("Shr", "shr", ">>"),
+];
+
+//(trait名, trait路径, 方法名, 调用时的写法)。PartialOrd::partial_cmp没有对应的运算符，照常调用
+fn _operator_methods() -> Vec<(String, String, String, String)> {
+    let mut res = Vec::new();
+    for (trait_name, method_name, operator) in BINARY_OPERATORS.iter() {
+        res.push((
+            trait_name.to_string(),
+            format!("std::ops::{}", trait_name),
+            method_name.to_string(),
+            format!("({{0}}) {} ({{1}})", operator),
+        ));
+        res.push((
+            format!("{}Assign", trait_name),
+            format!("std::ops::{}Assign", trait_name),
+            format!("{}_assign", method_name),
+            format!("*({{0}}) {}= ({{1}})", operator),
+        ));
+    }
+    let other_operators = [
+        ("Neg", "std::ops::Neg", "neg", "-({0})"),
+        ("Not", "std::ops::Not", "not", "!({0})"),
+        ("Index", "std::ops::Index", "index", "&(*({0}))[{1}]"),
+        ("IndexMut", "std::ops::IndexMut", "index_mut", "&mut (*({0}))[{1}]"),
+        ("PartialOrd", "std::cmp::PartialOrd", "lt", "*({0}) < *({1})"),
+        ("PartialOrd", "std::cmp::PartialOrd", "le", "*({0}) <= *({1})"),
+        ("PartialOrd", "std::cmp::PartialOrd", "gt", "*({0}) > *({1})"),
+        ("PartialOrd", "std::cmp::PartialOrd", "ge", "*({0}) >= *({1})"),
+    ];
+    for (trait_name, trait_path, method_name, call_pattern) in other_operators.iter() {
+        res.push((
+            trait_name.to_string(),
+            trait_path.to_string(),
+            method_name.to_string(),
+            call_pattern.to_string(),
+        ));
+    }
+    res
+}
+
+//运算符trait的方法调用写成对应的运算符，其他函数返回None
+pub fn _operator_call_string(full_name: &str, param_strings: &[String]) -> Option<String> {
+    let call_pattern = OPERATOR_CALLS.get(full_name)?;
+    let mut call_string = call_pattern.clone();
+    for (index, param_string) in param_strings.iter().enumerate() {
+        call_string = call_string.replace(format!("{{{}}}", index).as_str(), param_string);
+    }
+    Some(call_string)
+}
 
 #[derive(Debug, Clone)]
 pub struct CrateImplCollection {
@@ -56,12 +155,97 @@ impl CrateImplCollection {
 #[derive(Debug, Clone)]
 pub struct FullNameMap {
     pub map: HashMap<DefId, (String, ItemType)>,
+    //public unit structs, which can be constructed by their path directly
+    pub zero_sized_types: HashSet<DefId>,
+    //所有字段都是pub的primitive类型的struct，可以由输入字节直接构造字面量。值是字段名和类型
+    pub literal_structs: HashMap<DefId, Vec<(String, clean::Type)>>,
+    //enum变体构造函数的api名 -> 变体。有多个变体时第一个参数用来选择构造哪个变体
+    pub variant_constructors: HashMap<String, Vec<api_util::EnumVariant>>,
+    //trait -> 实现了这个trait的没有泛型参数的类型，用于给dyn Trait参数找具体的类型
+    pub trait_implementors: HashMap<DefId, HashSet<DefId>>,
 }
 
 impl FullNameMap {
     pub fn new() -> Self {
         let map = HashMap::default();
-        FullNameMap { map }
+        let zero_sized_types = HashSet::default();
+        let literal_structs = HashMap::default();
+        let variant_constructors = HashMap::default();
+        let trait_implementors = HashMap::default();
+        FullNameMap {
+            map,
+            zero_sized_types,
+            literal_structs,
+            variant_constructors,
+            trait_implementors,
+        }
+    }
+
+    pub fn push_trait_implementor(&mut self, trait_def_id: &DefId, type_def_id: &DefId) {
+        self.trait_implementors
+            .entry(trait_def_id.clone())
+            .or_default()
+            .insert(type_def_id.clone());
+    }
+
+    pub fn _implements_trait(&self, type_def_id: &DefId, trait_def_id: &DefId) -> bool {
+        match self.trait_implementors.get(trait_def_id) {
+            Some(implementors) => implementors.contains(type_def_id),
+            None => false,
+        }
+    }
+
+    //trait object(dyn Trait)在clean中是以trait为did的ResolvedPath
+    pub fn _is_trait(&self, def_id: &DefId) -> bool {
+        match self.map.get(def_id) {
+            Some((_, ItemType::Trait)) => true,
+            _ => false,
+        }
+    }
+
+    pub fn push_zero_sized_type(&mut self, def_id: &DefId) {
+        self.zero_sized_types.insert(def_id.clone());
+    }
+
+    pub fn _is_zero_sized_type(&self, def_id: &DefId) -> bool {
+        self.zero_sized_types.contains(def_id)
+    }
+
+    pub fn push_literal_struct(&mut self, def_id: &DefId, fields: Vec<(String, clean::Type)>) {
+        self.literal_structs.insert(def_id.clone(), fields);
+    }
+
+    pub fn _literal_struct_fields(&self, def_id: &DefId) -> Option<&Vec<(String, clean::Type)>> {
+        self.literal_structs.get(def_id)
+    }
+
+    pub fn push_variant_constructor(
+        &mut self,
+        full_name: &String,
+        variants: Vec<api_util::EnumVariant>,
+    ) {
+        self.variant_constructors.insert(full_name.clone(), variants);
+    }
+
+    //多个变体时生成match，由输入字节选择变体，其余参数按变体的顺序依次是各变体的字段
+    pub fn _variant_call_string(
+        &self,
+        full_name: &String,
+        param_strings: &Vec<String>,
+    ) -> Option<String> {
+        let variants = self.variant_constructors.get(full_name)?;
+        if variants.len() == 1 {
+            return Some(variants[0]._to_call_string(param_strings));
+        }
+        let (selector, mut field_params) = param_strings.split_first()?;
+        let mut arms = Vec::new();
+        for (i, variant) in variants.iter().enumerate() {
+            let (variant_params, rest_params) = field_params.split_at(variant.fields.len());
+            field_params = rest_params;
+            let arm = if i == variants.len() - 1 { "_".to_string() } else { i.to_string() };
+            arms.push(format!("{} => {}", arm, variant._to_call_string(variant_params)));
+        }
+        Some(format!("match {} as usize % {} {{ {} }}", selector, variants.len(), arms.join(", ")))
     }
 
     pub fn push_mapping(&mut self, def_id: &DefId, full_name: &String, item_type: ItemType) {
@@ -86,6 +270,14 @@ pub fn extract_impls_from_cache(
     let paths = &cache.paths;
 
     let mut crate_impl_collection = CrateImplCollection::new();
+    //记录每个类型通过derive实现的trait
+    let mut derived_traits: HashMap<DefId, (clean::Type, HashSet<String>)> = HashMap::new();
+    //实现了Debug/Display的类型，用于format sweep
+    let mut format_traits: HashMap<DefId, (clean::Type, HashSet<String>)> = HashMap::new();
+    //手写了Hash实现的类型和实现了Hasher的类型，以及检查时需要的Clone/PartialEq
+    let mut hash_traits: HashMap<DefId, (clean::Type, HashSet<String>)> = HashMap::new();
+    //实现了serde的Serialize/Deserialize的类型，用于检查序列化形式的兼容性
+    let mut serde_traits: HashMap<DefId, (clean::Type, HashSet<String>)> = HashMap::new();
 
     //construct the map of `did to type`
     for (did, (strings, item_type)) in paths {
@@ -93,6 +285,11 @@ pub fn extract_impls_from_cache(
         full_name_map.push_mapping(&did, &full_name, *item_type);
     }
 
+    //被测crate依赖tokio时，async api用tokio的runtime执行
+    if cache.extern_locations.values().any(|(crate_name, ..)| crate_name == "tokio") {
+        api_graph.async_helper = _PreludeHelper::_TokioBlockOnHelper;
+    }
+
     let extertal_paths = &cache.external_paths;
     for (did, (strings, item_type)) in extertal_paths {
         let full_name = full_path(&strings);
@@ -100,6 +297,23 @@ pub fn extract_impls_from_cache(
         if prelude_type::is_preluded_type(&full_name) {
             full_name_map.push_mapping(&did, &full_name, *item_type);
         }
+        //常见外部类型不加入full_name_map，避免把它们的方法当作被测crate的api
+        if let Some(helper) = prelude_type::_ecosystem_helper(&full_name) {
+            api_graph.ecosystem_types.insert(did.clone(), helper);
+        }
+    }
+
+    //记录trait的实现者，带泛型参数的impl块不知道具体的类型，不记录
+    for (did, impls) in type_impl_maps {
+        for impl_ in impls {
+            let impl_ = impl_.inner_impl();
+            if impl_.generics.params.len() > 0 || _has_type_args(&impl_.for_) {
+                continue;
+            }
+            if let Some(trait_def_id) = impl_.trait_.as_ref().and_then(|trait_| trait_.def_id()) {
+                full_name_map.push_trait_implementor(&trait_def_id, did);
+            }
+        }
     }
 
     api_graph.set_full_name_map(&full_name_map);
@@ -111,6 +325,43 @@ pub fn extract_impls_from_cache(
             for impl_ in impls {
                 //println!("full_name = {:?}", full_name_map._get_full_name(did).unwrap());
                 crate_impl_collection.add_impl(impl_.inner_impl());
+                if let Some(trait_name) = _trait_last_name(impl_.inner_impl()) {
+                    let type_name = full_name_map._get_full_name(did).unwrap().clone();
+                    api_graph.add_implemented_trait(&type_name, &trait_name);
+                    if trait_name == "Debug" || trait_name == "Display" {
+                        format_traits
+                            .entry(did.clone())
+                            .or_insert_with(|| (impl_.inner_impl().for_.clone(), HashSet::new()))
+                            .1
+                            .insert(trait_name.clone());
+                    }
+                    if trait_name == "Serialize" || trait_name == "Deserialize" {
+                        serde_traits
+                            .entry(did.clone())
+                            .or_insert_with(|| (impl_.inner_impl().for_.clone(), HashSet::new()))
+                            .1
+                            .insert(trait_name.clone());
+                    }
+                    let is_derived = _derived_trait_name(&impl_.impl_item).is_some();
+                    if (trait_name == "Hash" && !is_derived)
+                        || trait_name == "Hasher"
+                        || trait_name == "Clone"
+                        || trait_name == "PartialEq"
+                    {
+                        hash_traits
+                            .entry(did.clone())
+                            .or_insert_with(|| (impl_.inner_impl().for_.clone(), HashSet::new()))
+                            .1
+                            .insert(trait_name);
+                    }
+                }
+                if let Some(trait_name) = _derived_trait_name(&impl_.impl_item) {
+                    derived_traits
+                        .entry(did.clone())
+                        .or_insert_with(|| (impl_.inner_impl().for_.clone(), HashSet::new()))
+                        .1
+                        .insert(trait_name);
+                }
             }
         }
     }
@@ -118,12 +369,50 @@ pub fn extract_impls_from_cache(
     //分析impl type类型
     for impl_ in &crate_impl_collection.impl_types {
         //println!("analyse_impl_");
-        _analyse_impl(impl_, &full_name_map, &mut api_graph);
+        let checkpoint = api_graph._checkpoint();
+        let analyse_result = bug_report_util::_guard(
+            &_impl_name(impl_, &full_name_map),
+            bug_report_util::_impl_span(impl_).as_ref(),
+            || _analyse_impl(impl_, &full_name_map, &cache.traits, &mut api_graph),
+        );
+        if let Err(metadata_error) = analyse_result {
+            api_graph._rollback(checkpoint);
+            api_graph.add_metadata_error(metadata_error);
+        }
     }
 
     //println!("analyse impl Trait for Type");
     for impl_ in &crate_impl_collection.impl_trait_for_types {
-        _analyse_impl(impl_, &full_name_map, &mut api_graph);
+        let checkpoint = api_graph._checkpoint();
+        let analyse_result = bug_report_util::_guard(
+            &_impl_name(impl_, &full_name_map),
+            bug_report_util::_impl_span(impl_).as_ref(),
+            || {
+                _analyse_impl(impl_, &full_name_map, &cache.traits, &mut api_graph);
+                _analyse_collection_impl(impl_, &full_name_map, &mut api_graph);
+                _analyse_stream_impl(impl_, &full_name_map, &mut api_graph);
+                _analyse_iterator_impl(impl_, &full_name_map, &mut api_graph);
+                _analyse_deref_impl(impl_, &mut api_graph);
+                _analyse_conversion_impl(impl_, &mut api_graph);
+            },
+        );
+        if let Err(metadata_error) = analyse_result {
+            api_graph._rollback(checkpoint);
+            api_graph.add_metadata_error(metadata_error);
+        }
+    }
+
+    for (ty_, traits) in derived_traits.values() {
+        _analyse_derived_impls(ty_, traits, &full_name_map, &mut api_graph);
+    }
+    for (ty_, traits) in format_traits.values() {
+        _analyse_format_impls(ty_, traits, &full_name_map, &mut api_graph);
+    }
+    for (ty_, traits) in hash_traits.values() {
+        _analyse_hash_impls(ty_, traits, &full_name_map, &mut api_graph);
+    }
+    for (ty_, traits) in serde_traits.values() {
+        _analyse_serde_impls(ty_, traits, &full_name_map, &mut api_graph);
     }
     //TODO：如何提取trait对应的impl，impl traitA for traitB? impl dyn traitA?下面的逻辑有误
     //for (did, impls) in trait_impl_maps {
@@ -160,8 +449,16 @@ fn full_path(paths: &Vec<String>) -> String {
     return full;
 }
 
-pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph: &mut ApiGraph) {
+pub fn _analyse_impl(
+    impl_: &clean::Impl,
+    full_name_map: &FullNameMap,
+    traits: &FxHashMap<DefId, clean::Trait>,
+    api_graph: &mut ApiGraph,
+) {
     let inner_items = &impl_.items;
+    //trait中没有在impl块中重写的默认方法也作为Type的方法，trait的类型参数替换为impl块给出的类型
+    let provided_methods = _provided_methods(impl_, traits);
+    let trait_substitutes = _trait_substitutes(impl_, traits, full_name_map);
 
     //BUG FIX: TRAIT作为全限定名只能用于输入类型中带有self type的情况，这样可以推测self type，否则需要用具体的类型名
 
@@ -169,9 +466,26 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
         None => None,
         Some(trait_) => {
             //println!("{:?}", trait_);
-            let trait_ty_def_id = &trait_.def_id().unwrap();
-            let trait_full_name = full_name_map._get_full_name(trait_ty_def_id);
-            if let Some(trait_name) = trait_full_name { Some(trait_name.clone()) } else { None }
+            let trait_ty_def_id = match trait_.def_id() {
+                Some(def_id) => def_id,
+                None => {
+                    api_graph.add_metadata_error(MetadataError::MissingTraitDefId {
+                        impl_name: _impl_name(impl_, full_name_map),
+                    });
+                    return;
+                }
+            };
+            let trait_full_name = full_name_map._get_full_name(&trait_ty_def_id);
+            if let Some(trait_name) = trait_full_name {
+                Some(trait_name.clone())
+            } else {
+                _trait_last_name(impl_).and_then(|trait_name| {
+                    STD_CONVERSION_TRAITS
+                        .get(trait_name.as_str())
+                        .map(|trait_path| trait_path.to_string())
+                        .or_else(|| STD_OPERATOR_TRAITS.get(&trait_name).cloned())
+                })
+            }
         }
     };
 
@@ -183,7 +497,8 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
         None
     };
 
-    for item in inner_items {
+    for (item_index, item) in inner_items.iter().chain(provided_methods.iter()).enumerate() {
+        let is_provided_method = item_index >= inner_items.len();
         //println!("item_name, {:?}", item.name.as_ref().unwrap());
         match &item.inner {
             //TODO:这段代码暂时没用了，impl块里面的是method item，而不是function item,暂时留着，看里面是否会出现function item
@@ -196,11 +511,30 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                 println!("function name in impl:{:?}", function_name);
             }
             clean::MethodItem(_method) => {
+                let item_name = match &item.name {
+                    Some(item_name) => item_name,
+                    None => {
+                        api_graph.add_metadata_error(MetadataError::MissingName {
+                            parent: _impl_name(impl_, full_name_map),
+                        });
+                        continue;
+                    }
+                };
                 let decl = _method.decl.clone();
                 let clean::FnDecl { inputs, output, .. } = decl;
                 let generics = _method.generics.clone();
+                let mut param_relations = api_util::_extract_param_relations(&inputs);
+                relation_util::_merge_param_relations(
+                    &mut param_relations,
+                    api_graph.mir_param_relations.relations.get(&item.def_id),
+                );
                 let mut inputs = api_util::_extract_input_types(&inputs);
-                let output = api_util::_extract_output_type(&output);
+                let is_async = api_util::_is_async(&_method.header);
+                let output = if is_async {
+                    api_util::_extract_async_output_type(&_method.decl)
+                } else {
+                    api_util::_extract_output_type(&output)
+                };
                 //println!("input types = {:?}", inputs);
 
                 let mut contains_self_type = false;
@@ -216,7 +550,7 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                 }
                 //println!("after replace, input = {:?}", inputs);
 
-                let output = match output {
+                let mut output = match output {
                     None => None,
                     Some(ty_) => {
                         if is_param_self_type(&ty_) {
@@ -227,6 +561,15 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                         }
                     }
                 };
+                //Self::Item等关联类型替换为impl块中给出的具体类型
+                api_graph.normalize_projections(item.def_id, &mut inputs, &mut output);
+                if is_provided_method && !trait_substitutes.is_empty() {
+                    for input in inputs.iter_mut() {
+                        *input = generic_function::_substitute_generic(input, &trait_substitutes);
+                    }
+                    output = output
+                        .map(|ty_| generic_function::_substitute_generic(&ty_, &trait_substitutes));
+                }
 
                 let mut method_name = String::new();
                 //使用全限定名称：type::f
@@ -252,9 +595,22 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                 };
                 method_name.push_str(method_type_name.as_str());
                 method_name.push_str("::");
-                method_name.push_str(item.name.as_ref().unwrap().as_str());
+                method_name.push_str(item_name.as_str());
                 //println!("method name in impl:{:?}", method_name);
 
+                if api_util::_is_helper_cfg_gated(&item.attrs) {
+                    api_graph.add_cfg_gated_function(&method_name);
+                }
+                api_graph.add_feature_gates(&method_name, &item.attrs);
+                let annotations = api_util::_rulf_annotations(&item.attrs);
+                api_graph.add_function_annotations(&method_name, &annotations);
+                api_graph.add_unsafe_sites(&method_name, item.def_id);
+                api_graph.add_ffi_callees(&method_name, item.def_id);
+                api_graph.add_api_lines(&method_name, &item.source);
+                api_graph.add_generic_instances(&method_name, item.def_id);
+                if is_async {
+                    api_graph.add_async_function(&method_name);
+                }
                 let api_unsafety = ApiUnsafety::_get_unsafety_from_fnheader(&_method.header);
                 //生成api function
                 //如果是实现了trait的话，需要把trait的全路径也包括进去
@@ -265,7 +621,10 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                         inputs,
                         output,
                         _trait_full_path: None,
+                        _impl_type_full_path: type_full_name.clone(),
                         _unsafe_tag: api_unsafety,
+                        _helper: None,
+                        _param_relations: param_relations,
                     },
                     Some(_) => {
                         if let Some(ref real_trait_name) = trait_full_name {
@@ -275,7 +634,10 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
                                 inputs,
                                 output,
                                 _trait_full_path: Some(real_trait_name.clone()),
+                                _impl_type_full_path: type_full_name.clone(),
                                 _unsafe_tag: api_unsafety,
+                                _helper: None,
+                                _param_relations: param_relations,
                             }
                         } else {
                             //println!("Trait not found in current crate.");
@@ -292,6 +654,560 @@ pub fn _analyse_impl(impl_: &clean::Impl, full_name_map: &FullNameMap, api_graph
     }
 }
 
+//impl块实现的trait中有默认实现、并且没有在impl块中重写的方法
+fn _provided_methods(
+    impl_: &clean::Impl,
+    traits: &FxHashMap<DefId, clean::Trait>,
+) -> Vec<clean::Item> {
+    let trait_ = match impl_.trait_.as_ref().and_then(|trait_| trait_.def_id()) {
+        Some(trait_def_id) => match traits.get(&trait_def_id) {
+            Some(trait_) => trait_,
+            None => return Vec::new(),
+        },
+        None => return Vec::new(),
+    };
+    trait_
+        .items
+        .iter()
+        .filter(|item| match (&item.inner, &item.name) {
+            (clean::MethodItem(_), Some(name)) => impl_.provided_trait_methods.contains(name),
+            _ => false,
+        })
+        .cloned()
+        .collect()
+}
+
+//trait的类型参数 -> impl块中给出的类型，如impl Encoder<Frame> for Codec中Item -> Frame
+fn _trait_substitutes(
+    impl_: &clean::Impl,
+    traits: &FxHashMap<DefId, clean::Trait>,
+    full_name_map: &FullNameMap,
+) -> Vec<(String, clean::Type, String)> {
+    let (trait_path, trait_def_id) = match &impl_.trait_ {
+        Some(clean::Type::ResolvedPath { path, did, .. }) => (path, did),
+        _ => return Vec::new(),
+    };
+    let trait_ = match traits.get(trait_def_id) {
+        Some(trait_) => trait_,
+        None => return Vec::new(),
+    };
+    let trait_args = match trait_path.segments.last().map(|segment| &segment.args) {
+        Some(clean::GenericArgs::AngleBracketed { args, .. }) => args,
+        _ => return Vec::new(),
+    };
+    let trait_types = trait_args.iter().filter_map(|arg| match arg {
+        clean::GenericArg::Type(ty_) => Some(ty_),
+        _ => None,
+    });
+    let type_params = trait_.generics.params.iter().filter(|param| match param.kind {
+        clean::GenericParamDefKind::Type { .. } => true,
+        _ => false,
+    });
+    type_params
+        .zip(trait_types)
+        .map(|(param, ty_)| {
+            (param.name.clone(), ty_.clone(), api_util::_type_name(ty_, full_name_map))
+        })
+        .collect()
+}
+
+//为实现了FromIterator/Extend的类型生成由helper函数实现的api，
+//从fuzz数据得到的不同长度的切片来构造集合
+pub fn _analyse_collection_impl(
+    impl_: &clean::Impl,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    let trait_ = match &impl_.trait_ {
+        Some(trait_) => trait_,
+        None => return,
+    };
+    let trait_path = match trait_ {
+        clean::Type::ResolvedPath { path, .. } => path,
+        _ => return,
+    };
+    let trait_segment = match trait_path.segments.last() {
+        Some(segment) => segment,
+        None => return,
+    };
+    let helper = match trait_segment.name.as_str() {
+        "FromIterator" => _PreludeHelper::_FromIterHelper,
+        "Extend" => _PreludeHelper::_ExtendHelper,
+        _ => return,
+    };
+    //带泛型参数的impl块暂不考虑
+    if impl_.generics.params.len() > 0 || api_util::_is_generic_type(&impl_.for_) {
+        return;
+    }
+    if _has_type_args(&impl_.for_) {
+        return;
+    }
+    let item_type = match &trait_segment.args {
+        clean::GenericArgs::AngleBracketed { args, .. } if args.len() == 1 => match &args[0] {
+            clean::GenericArg::Type(item_type) => item_type.clone(),
+            _ => return,
+        },
+        _ => return,
+    };
+    //只考虑item可以直接由fuzz数据构造的情况,引用类型的item不考虑
+    if let clean::Type::BorrowedRef { .. } = item_type {
+        return;
+    }
+    if !api_util::is_fuzzable_type(&item_type, full_name_map) {
+        return;
+    }
+    let collection_name = match impl_.for_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    let item_name = api_util::_type_name(&item_type, full_name_map);
+    let items_type = clean::Type::BorrowedRef {
+        lifetime: None,
+        mutability: Mutability::Not,
+        type_: Box::new(clean::Type::Slice(Box::new(item_type))),
+    };
+    let (full_name, inputs, output) = match helper {
+        _PreludeHelper::_FromIterHelper => (
+            format!("_from_iter::<{}, {}>", collection_name, item_name),
+            vec![items_type],
+            Some(impl_.for_.clone()),
+        ),
+        _ => {
+            let collection_type = clean::Type::BorrowedRef {
+                lifetime: None,
+                mutability: Mutability::Mut,
+                type_: Box::new(impl_.for_.clone()),
+            };
+            (
+                format!("_extend::<{}, {}>", collection_name, item_name),
+                vec![collection_type, items_type],
+                None,
+            )
+        }
+    };
+    let api_function = ApiFunction {
+        full_name,
+        generics: clean::Generics::default(),
+        inputs,
+        output,
+        _trait_full_path: None,
+        _impl_type_full_path: None,
+        _unsafe_tag: ApiUnsafety::Normal,
+        _helper: Some(helper),
+        _param_relations: Vec::new(),
+    };
+    api_graph.add_api_function(api_function);
+}
+
+//为实现了Stream的类型生成由helper函数实现的api，在harness中用executor取出stream的item，
+//这样item就可以像普通函数的返回值一样被后续的api使用
+pub fn _analyse_stream_impl(
+    impl_: &clean::Impl,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    _analyse_item_impl(
+        impl_,
+        full_name_map,
+        api_graph,
+        "Stream",
+        "_stream_next",
+        _PreludeHelper::_StreamNextHelper,
+    );
+}
+
+//为实现了Iterator的类型生成由helper函数实现的api，在harness中调用next取出item
+pub fn _analyse_iterator_impl(
+    impl_: &clean::Impl,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    _analyse_item_impl(
+        impl_,
+        full_name_map,
+        api_graph,
+        "Iterator",
+        "_iter_next",
+        _PreludeHelper::_IterNextHelper,
+    );
+}
+
+//记录Deref的Target和实现了DerefMut的类型，这样类型的值可以通过&*作为Target的方法的参数
+pub fn _analyse_deref_impl(impl_: &clean::Impl, api_graph: &mut ApiGraph) {
+    //带泛型参数的impl块暂不考虑
+    if impl_.generics.params.len() > 0
+        || api_util::_is_generic_type(&impl_.for_)
+        || _has_type_args(&impl_.for_)
+    {
+        return;
+    }
+    let def_id = match impl_.for_.def_id() {
+        Some(def_id) => def_id,
+        None => return,
+    };
+    match _trait_last_name(impl_).as_ref().map(String::as_str) {
+        Some("Deref") => {
+            let target_type = impl_.items.iter().find_map(|item| match &item.inner {
+                clean::TypedefItem(typedef, true) if item.name.as_deref() == Some("Target") => {
+                    Some(typedef.type_.clone())
+                }
+                _ => None,
+            });
+            if let Some(target_type) = target_type {
+                api_graph.add_deref_target(def_id, target_type);
+            }
+        }
+        Some("DerefMut") => api_graph.add_deref_mut_type(def_id),
+        _ => {}
+    }
+}
+
+//impl From<A> for B和impl TryFrom<A> for B记录为A到B的转换，A的值可以通过into/try_into作为B的参数
+pub fn _analyse_conversion_impl(impl_: &clean::Impl, api_graph: &mut ApiGraph) {
+    let fallible = match _trait_last_name(impl_).as_ref().map(String::as_str) {
+        Some("From") => false,
+        Some("TryFrom") => true,
+        _ => return,
+    };
+    //带泛型参数的impl块暂不考虑
+    if impl_.generics.params.len() > 0 || api_util::_is_generic_type(&impl_.for_) {
+        return;
+    }
+    let from_type = match &impl_.trait_ {
+        Some(clean::Type::ResolvedPath { path, .. }) => {
+            match path.segments.last().map(|segment| &segment.args) {
+                Some(clean::GenericArgs::AngleBracketed { args, .. }) => {
+                    args.iter().find_map(|arg| match arg {
+                        clean::GenericArg::Type(ty_) => Some(ty_.clone()),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    if let Some(from_type) = from_type {
+        if !api_util::_is_generic_type(&from_type) {
+            api_graph.add_conversion(from_type, impl_.for_.clone(), fallible);
+        }
+    }
+}
+
+fn _analyse_item_impl(
+    impl_: &clean::Impl,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+    trait_name: &str,
+    helper_name: &str,
+    helper: _PreludeHelper,
+) {
+    if _trait_last_name(impl_).as_ref().map(String::as_str) != Some(trait_name) {
+        return;
+    }
+    //带泛型参数的impl块暂不考虑
+    if impl_.generics.params.len() > 0
+        || api_util::_is_generic_type(&impl_.for_)
+        || _has_type_args(&impl_.for_)
+    {
+        return;
+    }
+    let item_type = impl_.items.iter().find_map(|item| match &item.inner {
+        clean::TypedefItem(typedef, true) if item.name.as_deref() == Some("Item") => {
+            Some(typedef.type_.clone())
+        }
+        _ => None,
+    });
+    let item_type = match item_type {
+        Some(item_type) => item_type,
+        None => return,
+    };
+    let producer_name = match impl_.for_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    let item_name = api_util::_type_name(&item_type, full_name_map);
+    let api_function = ApiFunction {
+        full_name: format!("{}::<{}, {}>", helper_name, item_name, producer_name),
+        generics: clean::Generics::default(),
+        inputs: vec![impl_.for_.clone()],
+        output: Some(item_type),
+        _trait_full_path: None,
+        _impl_type_full_path: None,
+        _unsafe_tag: ApiUnsafety::Normal,
+        _helper: Some(helper),
+        _param_relations: Vec::new(),
+    };
+    api_graph.add_api_function(api_function);
+}
+
+//bug report中impl块的名字，如impl_Display_for_Foo
+fn _impl_name(impl_: &clean::Impl, full_name_map: &FullNameMap) -> String {
+    let type_name = api_util::_type_name(&impl_.for_, full_name_map);
+    match _trait_last_name(impl_) {
+        Some(trait_name) => format!("impl {} for {}", trait_name, type_name),
+        None => format!("impl {}", type_name),
+    }
+}
+
+fn _trait_last_name(impl_: &clean::Impl) -> Option<String> {
+    match &impl_.trait_ {
+        Some(clean::Type::ResolvedPath { path, .. }) => {
+            path.segments.last().map(|segment| segment.name.clone())
+        }
+        _ => None,
+    }
+}
+
+//derive生成的impl带有#[automatically_derived]，返回其trait的名字
+fn _derived_trait_name(impl_item: &clean::Item) -> Option<String> {
+    if !attr::contains_name(&impl_item.attrs.other_attrs, sym::automatically_derived) {
+        return None;
+    }
+    match &impl_item.inner {
+        clean::ImplItem(impl_) => _trait_last_name(impl_),
+        _ => None,
+    }
+}
+
+//为derive了Clone和Debug的类型生成检查函数：先把值嵌套到fuzzer给出的深度，
+//clone之后用{:?}格式化，derive了PartialEq的话再进行比较。
+//derive的实现会递归处理每个字段，深层结构可能导致栈溢出。
+//嵌套用的构造函数在生成target时由ApiGraph::_nest_call_string查找
+pub fn _analyse_derived_impls(
+    ty_: &clean::Type,
+    traits: &HashSet<String>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    if !traits.contains("Clone") || !traits.contains("Debug") {
+        return;
+    }
+    //带泛型参数的类型暂不考虑
+    if api_util::_is_generic_type(ty_) || _has_type_args(ty_) {
+        return;
+    }
+    let type_name = match ty_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    if prelude_type::is_preluded_type(&type_name) {
+        return;
+    }
+    let (function_name, helper) = if traits.contains("PartialEq") {
+        ("_check_derived_eq", _PreludeHelper::_DerivedEqHelper)
+    } else {
+        ("_check_derived", _PreludeHelper::_DerivedHelper)
+    };
+    let input_type = clean::Type::BorrowedRef {
+        lifetime: None,
+        mutability: Mutability::Not,
+        type_: Box::new(ty_.clone()),
+    };
+    let api_function = ApiFunction {
+        full_name: format!("{}::<{}>", function_name, type_name),
+        generics: clean::Generics::default(),
+        inputs: vec![input_type, clean::Type::Primitive(clean::PrimitiveType::U16)],
+        output: None,
+        _trait_full_path: None,
+        _impl_type_full_path: None,
+        _unsafe_tag: ApiUnsafety::Normal,
+        _helper: Some(helper),
+        _param_relations: Vec::new(),
+    };
+    api_graph.add_api_function(api_function);
+}
+
+//为实现了Debug/Display的类型生成格式化函数，只在format sweep中使用，不直接加入api_functions
+pub fn _analyse_format_impls(
+    ty_: &clean::Type,
+    traits: &HashSet<String>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    //带泛型参数的类型暂不考虑
+    if api_util::_is_generic_type(ty_) || _has_type_args(ty_) {
+        return;
+    }
+    let type_name = match ty_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    if prelude_type::is_preluded_type(&type_name) {
+        return;
+    }
+    let mut format_functions = Vec::new();
+    if traits.contains("Debug") {
+        format_functions.push(("_format_debug", _PreludeHelper::_FormatDebugHelper));
+    }
+    if traits.contains("Display") {
+        format_functions.push(("_format_display", _PreludeHelper::_FormatDisplayHelper));
+    }
+    for (function_name, helper) in format_functions {
+        let input_type = clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Not,
+            type_: Box::new(ty_.clone()),
+        };
+        let api_function = ApiFunction {
+            full_name: format!("{}::<{}>", function_name, type_name),
+            generics: clean::Generics::default(),
+            inputs: vec![input_type],
+            output: None,
+            _trait_full_path: None,
+            _impl_type_full_path: None,
+            _unsafe_tag: ApiUnsafety::Normal,
+            _helper: Some(helper),
+            _param_relations: Vec::new(),
+        };
+        api_graph.format_functions.push(api_function);
+    }
+}
+
+//为同时实现了Serialize和Deserialize的类型生成序列化兼容性的检查函数，只在serde compat模式中使用
+pub fn _analyse_serde_impls(
+    ty_: &clean::Type,
+    traits: &HashSet<String>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    if !traits.contains("Serialize") || !traits.contains("Deserialize") {
+        return;
+    }
+    //带泛型参数的类型暂不考虑
+    if api_util::_is_generic_type(ty_) || _has_type_args(ty_) {
+        return;
+    }
+    let type_name = match ty_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    if prelude_type::is_preluded_type(&type_name) {
+        return;
+    }
+    let input_type = clean::Type::BorrowedRef {
+        lifetime: None,
+        mutability: Mutability::Not,
+        type_: Box::new(ty_.clone()),
+    };
+    let api_function = ApiFunction {
+        full_name: format!("_check_serde_compat::<{}>", type_name),
+        generics: clean::Generics::default(),
+        inputs: vec![input_type],
+        output: None,
+        _trait_full_path: None,
+        _impl_type_full_path: None,
+        _unsafe_tag: ApiUnsafety::Normal,
+        _helper: Some(_PreludeHelper::_SerdeCompatHelper),
+        _param_relations: Vec::new(),
+    };
+    api_graph.serde_functions.push(api_function);
+}
+
+//为实现了Hasher的类型生成分块写入的检查函数，为手写了Hash实现的类型生成重复计算hash的检查函数。
+//hash的结果依赖于输入的分块方式或者与PartialEq不一致，是hash相关crate中常见的逻辑错误
+pub fn _analyse_hash_impls(
+    ty_: &clean::Type,
+    traits: &HashSet<String>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph,
+) {
+    //带泛型参数的类型暂不考虑
+    if api_util::_is_generic_type(ty_) || _has_type_args(ty_) {
+        return;
+    }
+    let type_name = match ty_.def_id() {
+        Some(def_id) => match full_name_map._get_full_name(&def_id) {
+            Some(name) => name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    if prelude_type::is_preluded_type(&type_name) {
+        return;
+    }
+    let mut hash_functions = Vec::new();
+    //需要clone出另一个hasher来比较结果
+    if traits.contains("Hasher") && traits.contains("Clone") {
+        let hasher_type = clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Mut,
+            type_: Box::new(ty_.clone()),
+        };
+        let data_type = clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Not,
+            type_: Box::new(clean::Type::Slice(Box::new(clean::Type::Primitive(
+                clean::PrimitiveType::U8,
+            )))),
+        };
+        let chunk_sizes_type = clean::Type::Primitive(clean::PrimitiveType::Usize);
+        hash_functions.push((
+            "_check_hasher",
+            _PreludeHelper::_HasherHelper,
+            vec![hasher_type, data_type, chunk_sizes_type],
+        ));
+    }
+    if traits.contains("Hash") {
+        let (function_name, helper) = if traits.contains("Clone") && traits.contains("PartialEq") {
+            ("_check_hash_eq", _PreludeHelper::_HashEqHelper)
+        } else {
+            ("_check_hash", _PreludeHelper::_HashHelper)
+        };
+        let input_type = clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Not,
+            type_: Box::new(ty_.clone()),
+        };
+        hash_functions.push((function_name, helper, vec![input_type]));
+    }
+    for (function_name, helper, inputs) in hash_functions {
+        let api_function = ApiFunction {
+            full_name: format!("{}::<{}>", function_name, type_name),
+            generics: clean::Generics::default(),
+            inputs,
+            output: None,
+            _trait_full_path: None,
+            _impl_type_full_path: None,
+            _unsafe_tag: ApiUnsafety::Normal,
+            _helper: Some(helper),
+            _param_relations: Vec::new(),
+        };
+        api_graph.add_api_function(api_function);
+    }
+}
+
+//类型本身是否带有泛型参数，如Foo<u8>,这种类型无法只用全限定名来表示
+fn _has_type_args(ty_: &clean::Type) -> bool {
+    if let clean::Type::ResolvedPath { path, .. } = ty_ {
+        for segment in &path.segments {
+            if let clean::GenericArgs::AngleBracketed { args, .. } = &segment.args {
+                for arg in args {
+                    if let clean::GenericArg::Type(..) = arg {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
 //递归判断一个参数是否是self类型的
 //TODO：考虑在resolved path里面的括号里面可能存在self type
 fn is_param_self_type(ty_: &clean::Type) -> bool {
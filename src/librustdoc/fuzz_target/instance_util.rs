@@ -0,0 +1,256 @@
+use crate::clean::PrimitiveType;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir as hir;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::middle::privacy::AccessLevels;
+use rustc_middle::ty::fold::TypeFoldable;
+use rustc_middle::ty::subst::{GenericArgKind, InternalSubsts};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+
+//每个泛型函数最多找出的实例数，以及最多检查的类型组合数。
+//图中再从找出的实例中选出构造代价最小的MAX_INSTANCES个
+static MAX_FOUND_INSTANCES: usize = 64;
+static MAX_CHECKED_COMBINATIONS: usize = 256;
+pub static MAX_INSTANCES: usize = 8;
+//类型参数更多的函数组合数太多，不实例化
+static MAX_TYPE_PARAMS: usize = 2;
+
+//实例化泛型参数的候选类型。crate中的类型用DefId表示，在图中用api输出里出现过的同一个类型替换
+#[derive(Clone, Debug, PartialEq)]
+pub enum InstanceType {
+    Primitive(PrimitiveType),
+    StrRef,
+    Adt(DefId),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FunctionInstances {
+    //泛型impl块中的方法，impl块的类型参数排在前面，这里记录它们的个数
+    pub impl_param_count: usize,
+    //满足所有约束的实例化，每个实例化按类型参数的顺序记录(参数名, 具体类型)
+    pub instances: Vec<Vec<(String, InstanceType)>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GenericInstances {
+    //泛型函数 -> 实例化
+    pub instances: FxHashMap<DefId, FunctionInstances>,
+}
+
+//实例化要对每个泛型函数检查最多MAX_CHECKED_COMBINATIONS个类型组合，泛型函数很多的crate可以
+//设置FUZZ_TARGET_NO_GENERIC_INSTANCES跳过，泛型函数就只用错误类型和const值实例化
+pub fn generic_instances_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_NO_GENERIC_INSTANCES").is_none()
+}
+
+//用trait solver检查crate中的类型和基本类型是否满足泛型函数的约束。
+//先用每个类型参数自己的trait约束筛选候选类型，再对候选类型的组合检查函数的所有谓词，
+//包括参数之间的约束(如T: From<U>)和关联类型的约束。
+//泛型impl块(如impl<T: Ord> Stack<T>)中的方法同时实例化impl块的参数
+pub fn generic_instances(tcx: TyCtxt<'_>, access_levels: &AccessLevels<DefId>) -> GenericInstances {
+    let candidates = _candidate_types(tcx, access_levels);
+    let mut instances = FxHashMap::default();
+    for local_def_id in tcx.body_owners() {
+        let hir_id = tcx.hir().as_local_hir_id(local_def_id);
+        match tcx.hir().body_owner_kind(hir_id) {
+            hir::BodyOwnerKind::Fn => {}
+            _ => continue,
+        }
+        let def_id = local_def_id.to_def_id();
+        let function_instances = _function_instances(tcx, def_id, &candidates);
+        if !function_instances.instances.is_empty() {
+            instances.insert(def_id, function_instances);
+        }
+    }
+    GenericInstances { instances }
+}
+
+fn _candidate_types<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    access_levels: &AccessLevels<DefId>,
+) -> Vec<(InstanceType, Ty<'tcx>)> {
+    let types = &tcx.types;
+    let mut res = vec![
+        (InstanceType::Primitive(PrimitiveType::U8), types.u8),
+        (InstanceType::Primitive(PrimitiveType::U16), types.u16),
+        (InstanceType::Primitive(PrimitiveType::U32), types.u32),
+        (InstanceType::Primitive(PrimitiveType::U64), types.u64),
+        (InstanceType::Primitive(PrimitiveType::U128), types.u128),
+        (InstanceType::Primitive(PrimitiveType::Usize), types.usize),
+        (InstanceType::Primitive(PrimitiveType::I8), types.i8),
+        (InstanceType::Primitive(PrimitiveType::I16), types.i16),
+        (InstanceType::Primitive(PrimitiveType::I32), types.i32),
+        (InstanceType::Primitive(PrimitiveType::I64), types.i64),
+        (InstanceType::Primitive(PrimitiveType::I128), types.i128),
+        (InstanceType::Primitive(PrimitiveType::Isize), types.isize),
+        (InstanceType::Primitive(PrimitiveType::F32), types.f32),
+        (InstanceType::Primitive(PrimitiveType::F64), types.f64),
+        (InstanceType::Primitive(PrimitiveType::Bool), types.bool),
+        (InstanceType::Primitive(PrimitiveType::Char), types.char),
+        (InstanceType::StrRef, tcx.mk_static_str()),
+    ];
+    //crate中公开的、本身没有泛型参数的struct/enum/union
+    for item in tcx.hir().krate().items.values() {
+        match item.kind {
+            hir::ItemKind::Struct(..) | hir::ItemKind::Enum(..) | hir::ItemKind::Union(..) => {}
+            _ => continue,
+        }
+        let def_id = tcx.hir().local_def_id(item.hir_id).to_def_id();
+        if !access_levels.is_exported(def_id) || tcx.generics_of(def_id).count() > 0 {
+            continue;
+        }
+        res.push((InstanceType::Adt(def_id), tcx.type_of(def_id)));
+    }
+    res
+}
+
+fn _function_instances<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    candidates: &Vec<(InstanceType, Ty<'tcx>)>,
+) -> FunctionInstances {
+    let no_instances = FunctionInstances::default();
+    let generics = tcx.generics_of(def_id);
+    let mut params = Vec::new();
+    let mut predicates = tcx.predicates_of(def_id).predicates.to_vec();
+    let mut impl_self_ty = None;
+    if let Some(parent) = generics.parent {
+        //trait中的默认方法需要同时实例化Self，这里不考虑
+        if tcx.def_kind(parent) != DefKind::Impl {
+            return no_instances;
+        }
+        params.extend(tcx.generics_of(parent).params.iter());
+        predicates.extend_from_slice(tcx.predicates_of(parent).predicates);
+        impl_self_ty = Some(tcx.type_of(parent));
+    }
+    params.extend(generics.params.iter());
+    let mut type_params = Vec::new();
+    let mut impl_param_count = 0;
+    for param in params {
+        match param.kind {
+            ty::GenericParamDefKind::Lifetime => {}
+            //impl Trait参数不能用turbofish指定
+            ty::GenericParamDefKind::Type { synthetic: None, .. } => {
+                if param.index < generics.parent_count as u32 {
+                    impl_param_count += 1;
+                }
+                type_params.push(param);
+            }
+            _ => return no_instances,
+        }
+    }
+    if type_params.is_empty() || type_params.len() > MAX_TYPE_PARAMS {
+        return no_instances;
+    }
+
+    let param_env = ty::ParamEnv::empty();
+    let mut param_candidates = Vec::new();
+    for type_param in &type_params {
+        let mut filtered = Vec::new();
+        for (instance_type, candidate_ty) in candidates {
+            let satisfied = predicates.iter().all(|(predicate, _)| {
+                let trait_ref = match predicate.kind() {
+                    ty::PredicateKind::Trait(poly_trait_predicate, _) => {
+                        poly_trait_predicate.skip_binder().trait_ref
+                    }
+                    _ => return true,
+                };
+                match trait_ref.self_ty().kind {
+                    ty::Param(param_ty) if param_ty.index == type_param.index => {}
+                    _ => return true,
+                }
+                //trait的参数中出现其他类型参数时，留到检查组合的时候
+                let trait_params = &trait_ref.substs[1..];
+                if trait_params.iter().any(|trait_param| trait_param.needs_subst()) {
+                    return true;
+                }
+                tcx.type_implements_trait((
+                    trait_ref.def_id,
+                    candidate_ty,
+                    tcx.intern_substs(trait_params),
+                    param_env,
+                ))
+            });
+            if satisfied {
+                filtered.push((instance_type, *candidate_ty));
+            }
+        }
+        if filtered.is_empty() {
+            return no_instances;
+        }
+        //impl块的参数对应的类型参数有默认值(如struct Stack<T = u8>)时，先尝试默认值
+        if let Some(default_ty) = _default_of_impl_param(tcx, impl_self_ty, type_param.index) {
+            if let Some(position) = filtered.iter().position(|(_, ty)| *ty == default_ty) {
+                let default_candidate = filtered.remove(position);
+                filtered.insert(0, default_candidate);
+            }
+        }
+        param_candidates.push(filtered);
+    }
+
+    let mut res = Vec::new();
+    let mut combination = vec![0; type_params.len()];
+    for _ in 0..MAX_CHECKED_COMBINATIONS {
+        let substs = InternalSubsts::for_item(tcx, def_id, |param, _| {
+            match type_params.iter().position(|type_param| type_param.index == param.index) {
+                Some(position) => param_candidates[position][combination[position]].1.into(),
+                None => tcx.lifetimes.re_erased.into(),
+            }
+        });
+        if tcx.substitute_normalize_and_test_predicates((def_id, substs)) {
+            let instance = type_params
+                .iter()
+                .enumerate()
+                .map(|(position, type_param)| {
+                    let instance_type = param_candidates[position][combination[position]].0;
+                    (type_param.name.to_string(), instance_type.clone())
+                })
+                .collect();
+            res.push(instance);
+            if res.len() >= MAX_FOUND_INSTANCES {
+                break;
+            }
+        }
+        //下一个组合，最后一个参数变化最快
+        let mut position = combination.len();
+        loop {
+            if position == 0 {
+                return FunctionInstances { impl_param_count, instances: res };
+            }
+            position -= 1;
+            combination[position] += 1;
+            if combination[position] < param_candidates[position].len() {
+                break;
+            }
+            combination[position] = 0;
+        }
+    }
+    FunctionInstances { impl_param_count, instances: res }
+}
+
+//impl<T> Stack<T>中的T作为Stack的类型参数出现时，Stack的这个参数的默认值
+fn _default_of_impl_param<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    impl_self_ty: Option<Ty<'tcx>>,
+    param_index: u32,
+) -> Option<Ty<'tcx>> {
+    let (adt_def, substs) = match impl_self_ty?.kind {
+        ty::Adt(adt_def, substs) => (adt_def, substs),
+        _ => return None,
+    };
+    let position = substs.iter().position(|arg| match arg.unpack() {
+        GenericArgKind::Type(ty) => match ty.kind {
+            ty::Param(param_ty) => param_ty.index == param_index,
+            _ => false,
+        },
+        _ => false,
+    })?;
+    let adt_param = tcx.generics_of(adt_def.did).params.get(position)?;
+    match adt_param.kind {
+        ty::GenericParamDefKind::Type { has_default: true, .. } => {
+            Some(tcx.type_of(adt_param.def_id))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,181 @@
+use crate::clean::PrimitiveType;
+use crate::fuzz_target::api_sequence::ApiSequence;
+use crate::fuzz_target::fuzzable_type::FuzzableType;
+
+//AFL++ custom mutator的源码，按照harness解析输入的方式对参数逐个进行变异：
+//定长参数原地修改(bool参数只翻转)，动态长度参数整体改变长度，多个动态长度参数时同时更新长度前缀
+pub fn _custom_mutator_source(sequence: &ApiSequence, target_name: &String) -> String {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+    for fuzzable_param in &sequence.fuzzable_params {
+        let length = fuzzable_param._fixed_part_length();
+        if length > 0 {
+            let is_bool = match fuzzable_param {
+                FuzzableType::Primitive(PrimitiveType::Bool) => 1,
+                _ => 0,
+            };
+            fields.push(format!("    {{{}, {}, {}}},\n", offset, length, is_bool));
+        }
+        offset = offset + length;
+    }
+    //C不允许空数组
+    if fields.is_empty() {
+        fields.push("    {0, 0, 0},\n".to_string());
+    }
+    let field_number = if offset > 0 { fields.len() } else { 0 };
+    format!(
+        "// AFL++ custom mutator for {target_name}, generated by fuzz-target-generator.
+// Build: cc -shared -fPIC -O2 -o {target_name}.so {target_name}.c
+// Use: AFL_CUSTOM_MUTATOR_LIBRARY={target_name}.so cargo afl fuzz ...
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+struct field {{
+    size_t offset;
+    size_t length;
+    int is_bool;
+}};
+
+static const struct field FIELDS[] = {{
+{fields}}};
+static const size_t FIELD_NUMBER = {field_number};
+static const size_t FIXED_LENGTH = {fixed_length};
+// 0: all parameters have fixed length, 1: one dynamic tail, n: length-prefixed sections
+static const size_t SECTION_NUMBER = {section_number};
+{mutator_body}",
+        target_name = target_name,
+        fields = fields.concat(),
+        field_number = field_number,
+        fixed_length = offset,
+        section_number = sequence._dynamic_length_param_number(),
+        mutator_body = _MUTATOR_BODY
+    )
+}
+
+static _MUTATOR_BODY: &'static str = r#"
+typedef struct {
+    uint8_t *buf;
+    size_t buf_size;
+} mutator_t;
+
+void *afl_custom_init(void *afl, unsigned int seed) {
+    (void)afl;
+    srand(seed);
+    return calloc(1, sizeof(mutator_t));
+}
+
+static uint8_t random_byte(void) {
+    // printable bytes keep &str parameters valid utf-8
+    return (uint8_t)(rand() % 2 ? 0x20 + rand() % 0x5f : rand() % 256);
+}
+
+static void mutate_field(uint8_t *buf, const struct field *field) {
+    uint8_t *start = buf + field->offset;
+    if (field->is_bool) {
+        start[0] = !start[0];
+        return;
+    }
+    switch (rand() % 4) {
+    case 0:
+        memset(start, 0, field->length);
+        break;
+    case 1:
+        memset(start, 0xff, field->length);
+        break;
+    case 2:
+        start[0] += rand() % 2 ? 1 : -1;
+        break;
+    default:
+        for (size_t i = 0; i < field->length; i++) {
+            start[i] = (uint8_t)rand();
+        }
+    }
+}
+
+// same layout as _split_sections in the harness
+static size_t split_sections(const uint8_t *data, size_t size, size_t *starts, size_t *ends) {
+    size_t position = FIXED_LENGTH;
+    for (size_t i = 0; i + 1 < SECTION_NUMBER; i++) {
+        size_t prefix_end = position + 2 < size ? position + 2 : size;
+        size_t length = 0;
+        for (size_t j = position; j < prefix_end; j++) {
+            length |= (size_t)data[j] << (8 * (j - position));
+        }
+        starts[i] = prefix_end;
+        ends[i] = prefix_end + length < size ? prefix_end + length : size;
+        position = ends[i];
+    }
+    starts[SECTION_NUMBER - 1] = position;
+    ends[SECTION_NUMBER - 1] = size;
+    return SECTION_NUMBER;
+}
+
+static size_t resize_sections(mutator_t *mutator, const uint8_t *in, size_t in_size, size_t max_size) {
+    size_t starts[SECTION_NUMBER + 1], ends[SECTION_NUMBER + 1];
+    split_sections(in, in_size, starts, ends);
+    size_t chosen = rand() % SECTION_NUMBER;
+    size_t old_length = ends[chosen] - starts[chosen];
+    size_t new_length = rand() % (old_length * 2 + 8);
+    size_t out_size = FIXED_LENGTH;
+    memcpy(mutator->buf, in, FIXED_LENGTH);
+    for (size_t i = 0; i < SECTION_NUMBER; i++) {
+        size_t length = i == chosen ? new_length : ends[i] - starts[i];
+        if (i + 1 < SECTION_NUMBER) {
+            if (length > 0xffff) {
+                length = 0xffff;
+            }
+            if (out_size + 2 > max_size) {
+                break;
+            }
+            mutator->buf[out_size++] = (uint8_t)(length & 0xff);
+            mutator->buf[out_size++] = (uint8_t)(length >> 8);
+        }
+        for (size_t j = 0; j < length && out_size < max_size; j++) {
+            size_t old_index = starts[i] + j;
+            mutator->buf[out_size++] = old_index < ends[i] ? in[old_index] : random_byte();
+        }
+    }
+    return out_size;
+}
+
+size_t afl_custom_fuzz(void *data, uint8_t *buf, size_t buf_size, uint8_t **out_buf,
+                       uint8_t *add_buf, size_t add_buf_size, size_t max_size) {
+    (void)add_buf;
+    (void)add_buf_size;
+    mutator_t *mutator = (mutator_t *)data;
+    size_t capacity = max_size > FIXED_LENGTH ? max_size : FIXED_LENGTH;
+    if (mutator->buf_size < capacity) {
+        mutator->buf = realloc(mutator->buf, capacity);
+        mutator->buf_size = capacity;
+    }
+    // the harness rejects inputs shorter than the fixed part
+    size_t in_size = buf_size > FIXED_LENGTH ? buf_size : FIXED_LENGTH;
+    uint8_t *in = calloc(in_size, 1);
+    memcpy(in, buf, buf_size);
+    if (SECTION_NUMBER == 0) {
+        in_size = FIXED_LENGTH;
+    }
+
+    size_t out_size;
+    if (SECTION_NUMBER > 0 && (FIELD_NUMBER == 0 || rand() % 2)) {
+        out_size = resize_sections(mutator, in, in_size, capacity);
+    } else {
+        out_size = in_size < capacity ? in_size : capacity;
+        memcpy(mutator->buf, in, out_size);
+        if (FIELD_NUMBER > 0) {
+            mutate_field(mutator->buf, &FIELDS[rand() % FIELD_NUMBER]);
+        }
+    }
+    free(in);
+    *out_buf = mutator->buf;
+    return out_size;
+}
+
+void afl_custom_deinit(void *data) {
+    mutator_t *mutator = (mutator_t *)data;
+    free(mutator->buf);
+    free(mutator);
+}
+"#;
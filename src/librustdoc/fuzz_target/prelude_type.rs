@@ -3,6 +3,7 @@ use crate::clean::{self, types::GetDefId};
 use crate::fuzz_target::api_util;
 use crate::fuzz_target::call_type::CallType;
 use crate::fuzz_target::impl_util::FullNameMap;
+use rustc_hir::Mutability;
 use std::collections::{HashMap, HashSet};
 
 lazy_static! {
@@ -11,14 +12,57 @@ lazy_static! {
         m.insert("core::option::Option", "Option");
         m.insert("core::result::Result", "Result");
         m.insert("alloc::string::String", "String");
+        m.insert("core::marker::PhantomData", "PhantomData");
         //m.insert("alloc::boxed::Box", "Box");
         m
     };
+    //helper函数依赖的crate、兼容的版本范围和需要开启的feature，
+    //只有用到这些helper的target才需要加入到fuzz crate的依赖中
+    static ref HELPER_DEPENDENCIES: HashMap<_PreludeHelper, Vec<HelperDependency>> = {
+        let mut m = HashMap::new();
+        m.insert(_PreludeHelper::_StreamNextHelper, vec![("futures", "0.3", _NO_FEATURES)]);
+        m.insert(_PreludeHelper::_BlockOnHelper, vec![("futures", "0.3", _NO_FEATURES)]);
+        //tokio的runtime需要和被测crate使用同一个版本。
+        //rt用来创建runtime，被测的async api常常还需要time和net的driver
+        m.insert(_PreludeHelper::_TokioBlockOnHelper, vec![("tokio", "1", _TOKIO_FEATURES)]);
+        //生态中的类型需要和被测crate使用同一个版本，所以只限定兼容的版本范围，由cargo统一版本
+        m.insert(_PreludeHelper::_BytesHelper, vec![("bytes", "1", _NO_FEATURES)]);
+        m.insert(_PreludeHelper::_NaiveDateHelper, vec![("chrono", "0.4", _NO_FEATURES)]);
+        m.insert(_PreludeHelper::_UuidHelper, vec![("uuid", "1", _NO_FEATURES)]);
+        m.insert(_PreludeHelper::_JsonValueHelper, vec![("serde_json", "1", _NO_FEATURES)]);
+        m.insert(
+            _PreludeHelper::_SerdeCompatHelper,
+            vec![("serde", "1", _NO_FEATURES), ("serde_json", "1", _NO_FEATURES)],
+        );
+        m
+    };
+    //常见的外部crate中的类型(crate名, 类型名)，以及构造它们的helper函数
+    static ref ECOSYSTEM_TYPES: HashMap<(&'static str, &'static str), _PreludeHelper> = {
+        let mut m = HashMap::new();
+        m.insert(("bytes", "Bytes"), _PreludeHelper::_BytesHelper);
+        m.insert(("chrono", "NaiveDate"), _PreludeHelper::_NaiveDateHelper);
+        m.insert(("uuid", "Uuid"), _PreludeHelper::_UuidHelper);
+        m.insert(("serde_json", "Value"), _PreludeHelper::_JsonValueHelper);
+        m
+    };
+}
+
+//(crate名, 版本, feature)
+pub type HelperDependency = (&'static str, &'static str, &'static [&'static str]);
+static _NO_FEATURES: &'static [&'static str] = &[];
+static _TOKIO_FEATURES: &'static [&'static str] = &["rt", "time", "net"];
+
+//外部类型的定义路径在不同版本中可能不同，只比较crate名和类型名
+pub fn _ecosystem_helper(full_name: &String) -> Option<_PreludeHelper> {
+    let crate_name = full_name.split("::").next()?;
+    let type_name = full_name.rsplit("::").next()?;
+    ECOSYSTEM_TYPES.get(&(crate_name, type_name)).cloned()
 }
 
 static _OPTION: &'static str = "Option";
 static _RESULT: &'static str = "Result";
 static _STRING: &'static str = "String";
+static _PHANTOM_DATA: &'static str = "PhantomData";
 
 pub fn is_preluded_type(type_name: &String) -> bool {
     if PRELUDED_TYPE.contains_key(type_name.as_str()) {
@@ -46,6 +90,25 @@ pub fn preluded_type(type_: &clean::Type, full_name_map: &FullNameMap) -> bool {
     return false;
 }
 
+//PhantomData<T> can always be constructed, no matter what T is
+pub fn is_phantom_data(type_: &clean::Type, full_name_map: &FullNameMap) -> bool {
+    if let Some(def_id) = type_.def_id() {
+        if let Some(type_name) = full_name_map._get_full_name(&def_id) {
+            return to_strip_type_name(type_name) == _PHANTOM_DATA;
+        }
+    }
+    return false;
+}
+
+pub fn is_string(type_: &clean::Type, full_name_map: &FullNameMap) -> bool {
+    if let Some(def_id) = type_.def_id() {
+        if let Some(type_name) = full_name_map._get_full_name(&def_id) {
+            return to_strip_type_name(type_name) == _STRING;
+        }
+    }
+    return false;
+}
+
 pub fn to_strip_type_name(type_name: &String) -> String {
     if PRELUDED_TYPE.contains_key(type_name.as_str()) {
         PRELUDED_TYPE.get(type_name.as_str()).unwrap().to_string()
@@ -208,6 +271,26 @@ pub fn _prelude_type_need_special_dealing(
 pub enum _PreludeHelper {
     _ResultHelper,
     _OptionHelper,
+    _PanicResultHelper,
+    _PanicOptionHelper,
+    _FromIterHelper,
+    _ExtendHelper,
+    _DerivedHelper,
+    _DerivedEqHelper,
+    _StreamNextHelper,
+    _FormatDebugHelper,
+    _FormatDisplayHelper,
+    _HasherHelper,
+    _HashHelper,
+    _HashEqHelper,
+    _BytesHelper,
+    _NaiveDateHelper,
+    _UuidHelper,
+    _JsonValueHelper,
+    _SerdeCompatHelper,
+    _BlockOnHelper,
+    _TokioBlockOnHelper,
+    _IterNextHelper,
 }
 
 impl _PreludeHelper {
@@ -223,6 +306,9 @@ impl _PreludeHelper {
             | CallType::_Deref(inner_call_type)
             | CallType::_ToOption(inner_call_type)
             | CallType::_ToResult(inner_call_type)
+            | CallType::_ToBox(inner_call_type)
+            | CallType::_Into(inner_call_type, _)
+            | CallType::_TryInto(inner_call_type, _)
             | CallType::_UnsafeDeref(inner_call_type) => {
                 _PreludeHelper::_from_call_type(&**inner_call_type)
             }
@@ -239,10 +325,62 @@ impl _PreludeHelper {
         }
     }
 
+    //构造外部类型的helper函数的名字和它的fuzzable输入
+    pub fn _ecosystem_constructor(&self) -> Option<(&'static str, clean::Type)> {
+        let borrowed = |type_: clean::Type| clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Not,
+            type_: Box::new(type_),
+        };
+        match self {
+            _PreludeHelper::_BytesHelper => Some((
+                "_make_bytes",
+                borrowed(clean::Type::Slice(Box::new(clean::Type::Primitive(
+                    clean::PrimitiveType::U8,
+                )))),
+            )),
+            _PreludeHelper::_NaiveDateHelper => {
+                Some(("_make_naive_date", clean::Type::Primitive(clean::PrimitiveType::I32)))
+            }
+            _PreludeHelper::_UuidHelper => {
+                Some(("_make_uuid", clean::Type::Primitive(clean::PrimitiveType::U128)))
+            }
+            _PreludeHelper::_JsonValueHelper => Some((
+                "_make_json_value",
+                borrowed(clean::Type::Primitive(clean::PrimitiveType::Str)),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn _required_dependencies(&self) -> Vec<HelperDependency> {
+        HELPER_DEPENDENCIES.get(self).cloned().unwrap_or_default()
+    }
+
     pub fn _to_helper_function(&self) -> &'static str {
         match self {
             _PreludeHelper::_ResultHelper => _unwrap_result_function(),
             _PreludeHelper::_OptionHelper => _unwrap_option_function(),
+            _PreludeHelper::_PanicResultHelper => _panic_unwrap_result_function(),
+            _PreludeHelper::_PanicOptionHelper => _panic_unwrap_option_function(),
+            _PreludeHelper::_FromIterHelper => _from_iter_function(),
+            _PreludeHelper::_ExtendHelper => _extend_function(),
+            _PreludeHelper::_DerivedHelper => _check_derived_function(),
+            _PreludeHelper::_DerivedEqHelper => _check_derived_eq_function(),
+            _PreludeHelper::_StreamNextHelper => _stream_next_function(),
+            _PreludeHelper::_FormatDebugHelper => _format_debug_function(),
+            _PreludeHelper::_FormatDisplayHelper => _format_display_function(),
+            _PreludeHelper::_HasherHelper => _check_hasher_function(),
+            _PreludeHelper::_HashHelper => _check_hash_function(),
+            _PreludeHelper::_HashEqHelper => _check_hash_eq_function(),
+            _PreludeHelper::_BytesHelper => _make_bytes_function(),
+            _PreludeHelper::_NaiveDateHelper => _make_naive_date_function(),
+            _PreludeHelper::_UuidHelper => _make_uuid_function(),
+            _PreludeHelper::_JsonValueHelper => _make_json_value_function(),
+            _PreludeHelper::_SerdeCompatHelper => _check_serde_compat_function(),
+            _PreludeHelper::_BlockOnHelper => _block_on_function(),
+            _PreludeHelper::_TokioBlockOnHelper => _tokio_block_on_function(),
+            _PreludeHelper::_IterNextHelper => _iter_next_function(),
         }
     }
 }
@@ -270,3 +408,312 @@ fn _unwrap_option_function() -> &'static str {
     }
 }\n"
 }
+
+//unwrap策略下Err和None直接panic
+fn _panic_unwrap_result_function() -> &'static str {
+    "fn _unwrap_result<T, E>(_res: Result<T, E>) -> T {
+    match _res {
+        Ok(_t) => _t,
+        Err(_) => panic!(\"unexpected Err\"),
+    }
+}\n"
+}
+
+fn _panic_unwrap_option_function() -> &'static str {
+    "fn _unwrap_option<T>(_opt: Option<T>) -> T {
+    match _opt {
+        Some(_t) => _t,
+        None => panic!(\"unexpected None\"),
+    }
+}\n"
+}
+
+fn _from_iter_function() -> &'static str {
+    "fn _from_iter<C: std::iter::FromIterator<T>, T: Clone>(_items: &[T]) -> C {
+    _items.iter().cloned().collect()
+}\n"
+}
+
+fn _extend_function() -> &'static str {
+    "fn _extend<C: std::iter::Extend<T>, T: Clone>(_collection: &mut C, _items: &[T]) {
+    _collection.extend(_items.iter().cloned());
+}\n"
+}
+
+//derive生成的Clone/Debug/PartialEq实现会递归处理每个字段，嵌套较深时可能栈溢出。
+//先由_nest_derived嵌套_depth层，_DerivedNest在target中为每个检查的类型实现
+fn _check_derived_function() -> &'static str {
+    "fn _check_derived<T: _DerivedNest + std::fmt::Debug>(_value: &T, _depth: u16) {
+    let _nested = _nest_derived(_value, _depth);
+    let _cloned = _nested.clone();
+    let _ = format!(\"{:?}\", _cloned);
+}\n"
+}
+
+fn _check_derived_eq_function() -> &'static str {
+    "fn _check_derived_eq<T: _DerivedNest + std::fmt::Debug + PartialEq>(_value: &T, _depth: u16) {
+    let _nested = _nest_derived(_value, _depth);
+    let _cloned = _nested.clone();
+    let _ = format!(\"{:?}\", _cloned);
+    let _ = _nested == _cloned;
+}\n"
+}
+
+//没有嵌套api的类型使用默认的_nest，不加深嵌套
+pub fn _derived_nest_definition() -> &'static str {
+    "trait _DerivedNest: Clone {
+    fn _nest(self) -> Self {
+        self
+    }
+}
+
+fn _nest_derived<T: _DerivedNest>(_value: &T, _depth: u16) -> T {
+    let mut _nested = _value.clone();
+    for _ in 0.._depth {
+        _nested = _nested._nest();
+    }
+    _nested
+}\n"
+}
+
+//format sweep中用来格式化每个类型的函数，格式化代码中的递归、切片和unwrap都可能panic
+fn _format_debug_function() -> &'static str {
+    "fn _format_debug<T: std::fmt::Debug>(_value: &T) {
+    let _ = format!(\"{:?}\", _value);
+    let _ = format!(\"{:#?}\", _value);
+}\n"
+}
+
+fn _format_display_function() -> &'static str {
+    "fn _format_display<T: std::fmt::Display>(_value: &T) {
+    let _ = format!(\"{}\", _value);
+}\n"
+}
+
+//FUZZ_TARGET_HASHER_STREAM=1时，额外检查分块写入和一次写入全部数据的结果相同。
+//Hasher的文档不要求这一点(相邻的write可能不会被合并)，FxHasher这类hasher会因此报出误报，所以默认不检查
+pub fn hasher_stream_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_HASHER_STREAM").is_some()
+}
+
+//用不同大小的分块写入两个相同的hasher，分块大小由_chunk_sizes的每4位决定，两者finish的结果必须相同，
+//并且finish不能改变hasher的状态
+fn _check_hasher_function() -> &'static str {
+    if hasher_stream_requested() {
+        return _check_hasher_stream_function();
+    }
+    "fn _check_hasher<H: std::hash::Hasher + Clone>(_hasher: &mut H, _data: &[u8], _chunk_sizes: usize) {
+    let mut _other = _hasher.clone();
+    let mut _chunk_sizes = _chunk_sizes;
+    let mut _rest = _data;
+    while !_rest.is_empty() {
+        let _chunk_size = std::cmp::min(_chunk_sizes % 16 + 1, _rest.len());
+        _chunk_sizes = _chunk_sizes.rotate_right(4);
+        let (_chunk, _next) = _rest.split_at(_chunk_size);
+        _hasher.write(_chunk);
+        _other.write(_chunk);
+        _rest = _next;
+    }
+    let _finish = _hasher.finish();
+    assert_eq!(_finish, _other.finish());
+    assert_eq!(_finish, _hasher.finish());
+}\n"
+}
+
+//同一份数据，一个hasher按不同大小的分块写入，它的clone一次写入全部数据，两者finish的结果必须相同
+fn _check_hasher_stream_function() -> &'static str {
+    "fn _check_hasher<H: std::hash::Hasher + Clone>(_hasher: &mut H, _data: &[u8], _chunk_sizes: usize) {
+    let mut _other = _hasher.clone();
+    _other.write(_data);
+    let mut _chunk_sizes = _chunk_sizes;
+    let mut _rest = _data;
+    while !_rest.is_empty() {
+        let _chunk_size = std::cmp::min(_chunk_sizes % 16 + 1, _rest.len());
+        _chunk_sizes = _chunk_sizes.rotate_right(4);
+        let (_chunk, _next) = _rest.split_at(_chunk_size);
+        _hasher.write(_chunk);
+        _rest = _next;
+    }
+    let _finish = _hasher.finish();
+    assert_eq!(_finish, _other.finish());
+    assert_eq!(_finish, _hasher.finish());
+}\n"
+}
+
+fn _check_hash_function() -> &'static str {
+    "fn _check_hash<T: std::hash::Hash>(_value: &T) {
+    fn _hash_of<T: std::hash::Hash>(_value: &T) -> u64 {
+        use std::hash::Hasher;
+        let mut _hasher = std::collections::hash_map::DefaultHasher::new();
+        _value.hash(&mut _hasher);
+        _hasher.finish()
+    }
+    assert_eq!(_hash_of(_value), _hash_of(_value));
+}\n"
+}
+
+//相等的值hash必须相同
+fn _check_hash_eq_function() -> &'static str {
+    "fn _check_hash_eq<T: std::hash::Hash + Clone + PartialEq>(_value: &T) {
+    fn _hash_of<T: std::hash::Hash>(_value: &T) -> u64 {
+        use std::hash::Hasher;
+        let mut _hasher = std::collections::hash_map::DefaultHasher::new();
+        _value.hash(&mut _hasher);
+        _hasher.finish()
+    }
+    let _hash = _hash_of(_value);
+    assert_eq!(_hash, _hash_of(_value));
+    let _cloned = _value.clone();
+    if *_value == _cloned {
+        assert_eq!(_hash, _hash_of(&_cloned));
+    }
+}\n"
+}
+
+//被测crate之前的版本记录的golden序列化形式(target中的_SERDE_GOLDEN)必须仍然可以被反序列化，
+//并且序列化回相同的json值。golden形式和输入无关，每个进程只检查一次。
+//反方向(旧版本读取新版本的形式)不在同一个target中检查：用新版本的target记录golden形式，再用它生成旧版本的target
+//设置了RULF_SERDE_RECORD=<file>的话，把当前版本的序列化形式追加到这个文件中，作为下一个版本的golden形式
+fn _check_serde_compat_function() -> &'static str {
+    "fn _check_serde_compat<T: serde::Serialize + serde::de::DeserializeOwned>(_value: &T) {
+    let _type_name = std::any::type_name::<T>();
+    if let Some(_record) = std::env::var_os(\"RULF_SERDE_RECORD\") {
+        use std::io::Write;
+        if let Ok(_serialized) = serde_json::to_string(_value) {
+            let _file = std::fs::OpenOptions::new().create(true).append(true).open(_record);
+            if let Ok(mut _file) = _file {
+                let _ = writeln!(_file, \"{}\\t{}\", _type_name, _serialized);
+            }
+        }
+    }
+    static _GOLDEN_CHECKED: std::sync::Once = std::sync::Once::new();
+    _GOLDEN_CHECKED.call_once(|| {
+        for (_golden_type, _golden) in _SERDE_GOLDEN {
+            if *_golden_type != _type_name {
+                continue;
+            }
+            let _decoded: T = match serde_json::from_str(_golden) {
+                Ok(_decoded) => _decoded,
+                Err(_err) => {
+                    panic!(\"serde compat: {} no longer accepts golden form {}: {}\", _type_name, _golden, _err)
+                }
+            };
+            //比较json的值而不是字符串，空白和map中key的顺序不同不算不兼容
+            let _golden_value: serde_json::Value = match serde_json::from_str(_golden) {
+                Ok(_golden_value) => _golden_value,
+                Err(_) => continue,
+            };
+            let _encoded = serde_json::to_value(&_decoded).ok();
+            if _encoded.as_ref() != Some(&_golden_value) {
+                panic!(\"serde compat: {} serializes golden form {} as {:?}\", _type_name, _golden, _encoded);
+            }
+        }
+    });
+}\n"
+}
+
+fn _make_bytes_function() -> &'static str {
+    "fn _make_bytes(_data: &[u8]) -> bytes::Bytes {
+    bytes::Bytes::copy_from_slice(_data)
+}\n"
+}
+
+//超出chrono支持范围的天数直接退出
+fn _make_naive_date_function() -> &'static str {
+    "fn _make_naive_date(_days: i32) -> chrono::NaiveDate {
+    match chrono::NaiveDate::from_num_days_from_ce_opt(_days) {
+        Some(_date) => _date,
+        None => {
+            use std::process;
+            process::exit(0);
+        }
+    }
+}\n"
+}
+
+fn _make_uuid_function() -> &'static str {
+    "fn _make_uuid(_value: u128) -> uuid::Uuid {
+    uuid::Uuid::from_u128(_value)
+}\n"
+}
+
+fn _make_json_value_function() -> &'static str {
+    "fn _make_json_value(_text: &str) -> serde_json::Value {
+    match serde_json::from_str(_text) {
+        Ok(_value) => _value,
+        Err(_) => {
+            use std::process;
+            process::exit(0);
+        }
+    }
+}\n"
+}
+
+//调用next取出iterator的item，最多取16个，返回最后一个；一个item都没有的话直接退出
+fn _iter_next_function() -> &'static str {
+    "fn _iter_next<T, I: Iterator<Item = T>>(mut _iter: I) -> T {
+    let mut _item = None;
+    for _ in 0..16 {
+        match _iter.next() {
+            Some(_next) => _item = Some(_next),
+            None => break,
+        }
+    }
+    match _item {
+        Some(_t) => _t,
+        None => {
+            use std::process;
+            process::exit(0);
+        }
+    }
+}\n"
+}
+
+//调用了async api的target，整个函数体放在async块中，用futures自带的executor执行
+fn _block_on_function() -> &'static str {
+    "//NOTE: the target needs the futures crate as a dependency, see fuzz_dependencies.toml
+fn _block_on<F: std::future::Future>(_future: F) -> F::Output {
+    futures::executor::block_on(_future)
+}\n"
+}
+
+//被测crate依赖tokio时，async api可能需要tokio的runtime(如tokio::spawn、tokio::time)
+fn _tokio_block_on_function() -> &'static str {
+    "//NOTE: the target needs the tokio crate with the rt, time and net features as a dependency, see fuzz_dependencies.toml
+fn _block_on<F: std::future::Future>(_future: F) -> F::Output {
+    let _runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(_runtime) => _runtime,
+        Err(_) => {
+            use std::process;
+            process::exit(0);
+        }
+    };
+    _runtime.block_on(_future)
+}\n"
+}
+
+//用futures自带的executor取出stream的item，最多取16个，返回最后一个；一个item都没有的话直接退出。
+//stream一直处于Pending的话block_on不会返回，需要由fuzzer的超时来处理
+fn _stream_next_function() -> &'static str {
+    "//NOTE: the target needs the futures crate as a dependency, see fuzz_dependencies.toml
+fn _stream_next<T, S: futures::Stream<Item = T>>(_stream: S) -> T {
+    use futures::StreamExt;
+    let mut _stream = Box::pin(_stream);
+    let mut _item = None;
+    futures::executor::block_on(async {
+        for _ in 0..16 {
+            match _stream.next().await {
+                Some(_next) => _item = Some(_next),
+                None => break,
+            }
+        }
+    });
+    match _item {
+        Some(_t) => _t,
+        None => {
+            use std::process;
+            process::exit(0);
+        }
+    }
+}\n"
+}
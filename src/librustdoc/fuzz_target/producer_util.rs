@@ -0,0 +1,144 @@
+use crate::clean::{self, Clean};
+use crate::core::DocContext;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir as hir;
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{DefId, CRATE_DEF_INDEX};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::symbol::Symbol;
+
+//被测crate的api参数是依赖crate中的类型(如regex::Regex、url::Url)时，没有api可以产生这些参数。
+//FUZZ_TARGET_PRODUCER_CRATES中列出的依赖crate的构造函数只作为产生参数的节点加入图中
+#[derive(Clone, Debug, Default)]
+pub struct ProducerFunctions {
+    pub functions: Vec<ProducerFunction>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProducerFunction {
+    //依赖crate中的公开路径，如regex::Regex::new
+    pub full_name: String,
+    pub crate_name: String,
+    //构造的类型，返回值是这个类型、Option<这个类型>或Result<这个类型, _>
+    pub produced_type: DefId,
+    pub inputs: Vec<clean::Type>,
+    pub output: clean::Type,
+}
+
+//FUZZ_TARGET_PRODUCER_CRATES=regex,url
+pub fn producer_crates() -> Vec<String> {
+    match std::env::var("FUZZ_TARGET_PRODUCER_CRATES") {
+        Ok(crate_names) => crate_names
+            .split(',')
+            .map(|crate_name| crate_name.trim().replace("-", "_"))
+            .filter(|crate_name| !crate_name.is_empty())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn producer_functions(cx: &DocContext<'_>) -> ProducerFunctions {
+    let tcx = cx.tcx;
+    let producer_crates = producer_crates();
+    let mut functions = Vec::new();
+    for cnum in tcx.crates().iter() {
+        let crate_name = tcx.crate_name(*cnum).to_string();
+        if !producer_crates.contains(&crate_name) {
+            continue;
+        }
+        //公开的struct和enum -> 最短的公开路径
+        let mut public_types: FxHashMap<DefId, Vec<String>> = FxHashMap::default();
+        let mut visited_mods = FxHashSet::default();
+        let crate_root = DefId { krate: *cnum, index: CRATE_DEF_INDEX };
+        _visit_mod(tcx, crate_root, vec![crate_name.clone()], &mut visited_mods, &mut public_types);
+        let mut public_types: Vec<(DefId, Vec<String>)> = public_types.into_iter().collect();
+        public_types.sort_by(|(_, left), (_, right)| left.cmp(right));
+        for (type_def_id, type_path) in public_types {
+            for impl_def_id in tcx.inherent_impls(type_def_id).iter() {
+                for assoc_item in tcx.associated_items(*impl_def_id).in_definition_order() {
+                    if assoc_item.kind != ty::AssocKind::Fn
+                        || assoc_item.fn_has_self_parameter
+                        || assoc_item.vis != ty::Visibility::Public
+                    {
+                        continue;
+                    }
+                    let def_id = assoc_item.def_id;
+                    if tcx.generics_of(def_id).requires_monomorphization(tcx) {
+                        continue;
+                    }
+                    let fn_sig = tcx.fn_sig(def_id);
+                    if fn_sig.unsafety() == hir::Unsafety::Unsafe {
+                        continue;
+                    }
+                    let fn_sig = tcx.erase_late_bound_regions(&fn_sig);
+                    if !_produces(tcx, fn_sig.output(), type_def_id) {
+                        continue;
+                    }
+                    functions.push(ProducerFunction {
+                        full_name: format!("{}::{}", type_path.join("::"), assoc_item.ident),
+                        crate_name: crate_name.clone(),
+                        produced_type: type_def_id,
+                        inputs: fn_sig.inputs().iter().map(|ty| ty.clean(cx)).collect(),
+                        output: fn_sig.output().clean(cx),
+                    });
+                }
+            }
+        }
+    }
+    ProducerFunctions { functions }
+}
+
+//和LibEmbargoVisitor一样只进入公开的mod，pub use导出的类型用导出的路径
+fn _visit_mod(
+    tcx: TyCtxt<'_>,
+    mod_def_id: DefId,
+    mod_path: Vec<String>,
+    visited_mods: &mut FxHashSet<DefId>,
+    public_types: &mut FxHashMap<DefId, Vec<String>>,
+) {
+    if !visited_mods.insert(mod_def_id) {
+        return;
+    }
+    for item in tcx.item_children(mod_def_id).iter() {
+        if item.vis != ty::Visibility::Public {
+            continue;
+        }
+        let mut item_path = mod_path.clone();
+        item_path.push(item.ident.to_string());
+        match item.res {
+            Res::Def(DefKind::Mod, def_id) => {
+                _visit_mod(tcx, def_id, item_path, visited_mods, public_types);
+            }
+            Res::Def(DefKind::Struct, def_id) | Res::Def(DefKind::Enum, def_id) => {
+                let is_shorter = match public_types.get(&def_id) {
+                    Some(type_path) => item_path.len() < type_path.len(),
+                    None => true,
+                };
+                if is_shorter {
+                    public_types.insert(def_id, item_path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+//返回值是type_def_id的类型，或者包着它的Option、Result
+fn _produces<'tcx>(tcx: TyCtxt<'tcx>, output: Ty<'tcx>, type_def_id: DefId) -> bool {
+    let (adt_def, substs) = match output.kind {
+        ty::Adt(adt_def, substs) => (adt_def, substs),
+        _ => return false,
+    };
+    if adt_def.did == type_def_id {
+        return true;
+    }
+    let is_wrapper = tcx.is_diagnostic_item(Symbol::intern("option_type"), adt_def.did)
+        || tcx.is_diagnostic_item(Symbol::intern("result_type"), adt_def.did);
+    if !is_wrapper {
+        return false;
+    }
+    match substs.types().next().map(|ty| &ty.kind) {
+        Some(ty::Adt(inner_adt_def, _)) => inner_adt_def.did == type_def_id,
+        _ => false,
+    }
+}
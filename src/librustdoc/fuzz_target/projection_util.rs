@@ -0,0 +1,73 @@
+use crate::clean::{self, Clean};
+use crate::core::DocContext;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_middle::ty::fold::TypeFoldable;
+use rustc_middle::ty::{ParamEnv, Ty, TyCtxt};
+use rustc_trait_selection::traits::query::normalize::AtExt;
+use rustc_trait_selection::traits::ObligationCause;
+
+//签名中的关联类型(如<Foo as Iterator>::Item、Self::Err)在clean中是QPath，无法和其他api的类型匹配。
+//用trait solver把它们规范化为具体的类型，再clean成clean::Type
+#[derive(Clone, Debug, Default)]
+pub struct NormalizedSignatures {
+    //函数 -> 规范化之后的参数类型和返回类型，不含关联类型或无法规范化的位置为None
+    pub signatures: FxHashMap<DefId, (Vec<Option<clean::Type>>, Option<clean::Type>)>,
+}
+
+pub fn normalized_signatures(cx: &DocContext<'_>) -> NormalizedSignatures {
+    let tcx = cx.tcx;
+    let mut signatures = FxHashMap::default();
+    for local_def_id in tcx.body_owners() {
+        let hir_id = tcx.hir().as_local_hir_id(local_def_id);
+        match tcx.hir().body_owner_kind(hir_id) {
+            hir::BodyOwnerKind::Fn => {}
+            _ => continue,
+        }
+        let def_id = local_def_id.to_def_id();
+        let fn_sig = tcx.erase_late_bound_regions(&tcx.fn_sig(def_id));
+        if !fn_sig.inputs_and_output.iter().any(|ty| ty.has_projections()) {
+            continue;
+        }
+        //泛型参数的关联类型(如T::Item)在函数自己的param_env中也无法规范化，保持原样
+        let param_env = tcx.param_env(def_id);
+        let inputs = fn_sig
+            .inputs()
+            .iter()
+            .map(|ty| _normalize(tcx, param_env, ty).map(|ty| ty.clean(cx)))
+            .collect();
+        let output = if fn_sig.output().is_unit() {
+            None
+        } else {
+            _normalize(tcx, param_env, &fn_sig.output()).map(|ty| ty.clean(cx))
+        };
+        signatures.insert(def_id, (inputs, output));
+    }
+    NormalizedSignatures { signatures }
+}
+
+fn _normalize<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    ty: &Ty<'tcx>,
+) -> Option<Ty<'tcx>> {
+    if !ty.has_projections() {
+        return None;
+    }
+    let normalized = tcx.infer_ctxt().enter(|infcx| {
+        let cause = ObligationCause::dummy();
+        match infcx.at(&cause, param_env).normalize(ty) {
+            Ok(normalized) => {
+                let ty = infcx.resolve_vars_if_possible(&normalized.value);
+                if ty.needs_infer() { None } else { Some(ty) }
+            }
+            Err(_) => None,
+        }
+    })?;
+    if normalized.has_projections() || normalized.has_param_types_or_consts() {
+        return None;
+    }
+    Some(tcx.erase_regions(&normalized))
+}
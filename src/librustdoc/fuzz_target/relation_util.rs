@@ -0,0 +1,210 @@
+use crate::fuzz_target::api_function::{ParamRelation, ParamRelationKind};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_hir::BodyOwnerKind;
+use rustc_middle::mir::{AssertKind, Body, Local, Operand, Rvalue, StatementKind, TerminatorKind};
+use rustc_middle::ty::{self, TyCtxt};
+
+//由函数体的MIR得到的参数之间的长度/下标关系，补充api_util::_extract_param_relations按参数名的推测
+#[derive(Clone, Debug, Default)]
+pub struct MirParamRelations {
+    pub relations: FxHashMap<DefId, Vec<ParamRelation>>,
+}
+
+//只在设置了FUZZ_TARGET_MIR_RELATIONS时计算，需要为crate中的每个函数生成optimized MIR。
+//和unsafe_util一样在core::fuzz_target_body_analyses的普通rustc session中计算
+pub fn mir_relations_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_MIR_RELATIONS").is_some()
+}
+
+pub fn mir_param_relations(tcx: TyCtxt<'_>) -> MirParamRelations {
+    let mut relations = FxHashMap::default();
+    for local_def_id in tcx.body_owners() {
+        let hir_id = tcx.hir().as_local_hir_id(local_def_id);
+        match tcx.hir().body_owner_kind(hir_id) {
+            BodyOwnerKind::Fn => {}
+            _ => continue,
+        }
+        let def_id = local_def_id.to_def_id();
+        let function_relations = _body_param_relations(tcx, tcx.optimized_mir(def_id));
+        if !function_relations.is_empty() {
+            relations.insert(def_id, function_relations);
+        }
+    }
+    MirParamRelations { relations }
+}
+
+//把MIR得到的关系加到按名字推测的关系中，按名字已经有关系的参数不变
+pub fn _merge_param_relations(
+    name_relations: &mut Vec<ParamRelation>,
+    mir_relations: Option<&Vec<ParamRelation>>,
+) {
+    let mir_relations = match mir_relations {
+        Some(mir_relations) => mir_relations,
+        None => return,
+    };
+    for relation in mir_relations {
+        if name_relations.iter().all(|name_relation| name_relation.param != relation.param) {
+            name_relations.push(*relation);
+        }
+    }
+    name_relations.sort_by_key(|relation| relation.param);
+}
+
+//usize参数的用法：
+//buf[index]的越界检查(assert Lt(index, Len(*buf)))和buf.get_unchecked(index)说明它是下标，
+//slice::from_raw_parts(buf.as_ptr(), len)说明它是长度
+fn _body_param_relations<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> Vec<ParamRelation> {
+    let origins = _local_origins(tcx, body);
+    let origin = |operand: &Operand<'tcx>| {
+        let local = operand.place()?.local_or_deref_local()?;
+        Some(*origins.get(&local).unwrap_or(&local))
+    };
+    let mut len_locals: FxHashMap<Local, Local> = FxHashMap::default();
+    for basic_block in body.basic_blocks() {
+        for statement in &basic_block.statements {
+            let (place, len_place) = match &statement.kind {
+                StatementKind::Assign(assign) => match &**assign {
+                    (place, Rvalue::Len(len_place)) => (place, len_place),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if let (Some(local), Some(buffer)) =
+                (place.as_local(), len_place.local_or_deref_local())
+            {
+                len_locals.insert(local, *origins.get(&buffer).unwrap_or(&buffer));
+            }
+        }
+    }
+
+    let mut res = Vec::new();
+    let mut add_relation = |param: Option<Local>, buffer: Option<Local>, kind| {
+        let (param, buffer) = match (param, buffer) {
+            (Some(param), Some(buffer)) => (param, buffer),
+            _ => return,
+        };
+        if !_is_usize_param(tcx, body, param) || !_is_buffer_param(body, buffer) {
+            return;
+        }
+        let relation = ParamRelation { param: param.index() - 1, buffer: buffer.index() - 1, kind };
+        if res.iter().all(|other: &ParamRelation| other.param != relation.param) {
+            res.push(relation);
+        }
+    };
+    for basic_block in body.basic_blocks() {
+        match &basic_block.terminator().kind {
+            TerminatorKind::Assert { msg: AssertKind::BoundsCheck { len, index }, .. } => {
+                let buffer = len
+                    .place()
+                    .and_then(|place| place.as_local())
+                    .and_then(|local| len_locals.get(&local).cloned());
+                add_relation(origin(index), buffer, ParamRelationKind::_Index);
+            }
+            TerminatorKind::Call { func, args, .. } if args.len() == 2 => {
+                let callee_def_id = match func.ty(body, tcx).kind {
+                    ty::FnDef(callee_def_id, _) => callee_def_id,
+                    _ => continue,
+                };
+                match &*tcx.item_name(callee_def_id).as_str() {
+                    "get_unchecked" | "get_unchecked_mut" => {
+                        add_relation(origin(&args[1]), origin(&args[0]), ParamRelationKind::_Index)
+                    }
+                    "from_raw_parts" | "from_raw_parts_mut" => {
+                        add_relation(origin(&args[1]), origin(&args[0]), ParamRelationKind::_Len)
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+//临时变量 -> 它来自的参数。只跟踪复制、重新借用、类型转换和as_ptr/as_mut_ptr，
+//被赋值多次的变量不跟踪
+fn _local_origins<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> FxHashMap<Local, Local> {
+    let mut sources: FxHashMap<Local, Option<Local>> = FxHashMap::default();
+    let mut add_source = |local: Local, source: Option<Local>| {
+        if local.index() == 0 || local.index() > body.arg_count {
+            let entry = sources.entry(local).or_insert(source);
+            if *entry != source {
+                *entry = None;
+            }
+        }
+    };
+    for basic_block in body.basic_blocks() {
+        for statement in &basic_block.statements {
+            let (place, rvalue) = match &statement.kind {
+                StatementKind::Assign(assign) => &**assign,
+                _ => continue,
+            };
+            let local = match place.as_local() {
+                Some(local) => local,
+                None => continue,
+            };
+            let source = match rvalue {
+                Rvalue::Use(operand) | Rvalue::Cast(_, operand, _) => {
+                    operand.place().and_then(|place| place.local_or_deref_local())
+                }
+                Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) => {
+                    place.local_or_deref_local()
+                }
+                _ => None,
+            };
+            add_source(local, source);
+        }
+        if let TerminatorKind::Call { func, args, destination: Some((place, _)), .. } =
+            &basic_block.terminator().kind
+        {
+            if let (ty::FnDef(callee_def_id, _), Some(local)) =
+                (&func.ty(body, tcx).kind, place.as_local())
+            {
+                let source = match &*tcx.item_name(*callee_def_id).as_str() {
+                    "as_ptr" | "as_mut_ptr" if args.len() == 1 => {
+                        args[0].place().and_then(|place| place.local_or_deref_local())
+                    }
+                    _ => None,
+                };
+                add_source(local, source);
+            }
+        }
+    }
+
+    let mut res = FxHashMap::default();
+    for local in sources.keys() {
+        let mut current = *local;
+        let mut steps = 0;
+        while let Some(Some(source)) = sources.get(&current) {
+            current = *source;
+            steps += 1;
+            if steps > sources.len() {
+                break;
+            }
+        }
+        if current.index() >= 1 && current.index() <= body.arg_count {
+            res.insert(*local, current);
+        }
+    }
+    res
+}
+
+fn _is_usize_param<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, local: Local) -> bool {
+    local.index() >= 1
+        && local.index() <= body.arg_count
+        && body.local_decls[local].ty == tcx.types.usize
+}
+
+fn _is_buffer_param(body: &Body<'_>, local: Local) -> bool {
+    if local.index() < 1 || local.index() > body.arg_count {
+        return false;
+    }
+    match body.local_decls[local].ty.kind {
+        ty::Ref(_, inner, _) => match inner.kind {
+            ty::Slice(_) | ty::Str => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,165 @@
+//没有设置FUZZ_TARGET_SANDBOX时，只有这些crate的target在sandbox中运行。
+//它们的api会读写文件系统
+static DEFAULT_SANDBOX_CRATES: [&'static str; 3] = ["bat", "xi_core_lib", "flate2"];
+
+//FUZZ_TARGET_SANDBOX=1(或all)时所有crate都加sandbox，=0(或none)时都不加，
+//其他值是逗号分隔的crate名
+pub fn need_sandbox(crate_name: &str) -> bool {
+    match std::env::var("FUZZ_TARGET_SANDBOX") {
+        Ok(value) => match value.trim() {
+            "1" | "all" => true,
+            "0" | "none" => false,
+            crate_names => crate_names
+                .split(',')
+                .any(|sandbox_crate| sandbox_crate.trim().replace("-", "_") == crate_name),
+        },
+        Err(_) => DEFAULT_SANDBOX_CRATES.contains(&crate_name),
+    }
+}
+
+//main函数开头的语句
+pub fn _enter_sandbox_statement(indent: &str) -> String {
+    format!("{}_enter_sandbox();\n", indent)
+}
+
+//libfuzzer的闭包和C ABI入口对每个输入都会调用，只在第一次调用时进入sandbox
+pub fn _enter_sandbox_once_statement(indent: &str) -> String {
+    format!(
+        "{indent}static _SANDBOX: std::sync::Once = std::sync::Once::new();
+{indent}_SANDBOX.call_once(_enter_sandbox);\n",
+        indent = indent
+    )
+}
+
+//每个进程使用单独的临时目录作为当前目录和TMPDIR，避免相对路径的文件操作污染宿主机或crate的源码。
+//正常退出时(atexit)删除这个目录；abort或被kill的进程留下的目录在下一次进入sandbox时删除。
+//afl的fork server在进入sandbox之后才fork，子进程和父进程共用目录，只有创建目录的进程会删除它。
+//在linux(x86_64/aarch64)上再用seccomp禁止创建AF_UNIX以外的socket，返回EACCES
+pub fn _sandbox_functions() -> &'static str {
+    r#"fn _enter_sandbox() {
+    use std::env;
+    use std::fs;
+    use std::process;
+    let _temp_dir = env::temp_dir();
+    _remove_stale_sandboxes(&_temp_dir);
+    let _sandbox = _temp_dir.join(format!("fuzz_sandbox_{}", process::id()));
+    if fs::create_dir_all(&_sandbox).is_err() || env::set_current_dir(&_sandbox).is_err() {
+        println!("Can not enter sandbox directory {:?}", _sandbox);
+        process::exit(-1);
+    }
+    env::set_var("TMPDIR", &_sandbox);
+    env::set_var("RULF_SANDBOX_DIR", &_sandbox);
+    extern "C" {
+        fn atexit(_callback: extern "C" fn()) -> i32;
+    }
+    unsafe {
+        atexit(_leave_sandbox);
+    }
+    if !_block_network() {
+        println!("Can not block network access, running without network isolation");
+    }
+}
+
+extern "C" fn _leave_sandbox() {
+    let _sandbox = match std::env::var_os("RULF_SANDBOX_DIR") {
+        Some(_sandbox) => std::path::PathBuf::from(_sandbox),
+        None => return,
+    };
+    let _owner = format!("fuzz_sandbox_{}", std::process::id());
+    if _sandbox.file_name().map_or(true, |_name| _name != _owner.as_str()) {
+        return;
+    }
+    if let Some(_temp_dir) = _sandbox.parent() {
+        let _ = std::env::set_current_dir(_temp_dir);
+    }
+    let _ = std::fs::remove_dir_all(&_sandbox);
+}
+
+//只在有/proc的系统上能判断创建目录的进程是否还在
+fn _remove_stale_sandboxes(_temp_dir: &std::path::Path) {
+    if !std::path::Path::new("/proc/self").exists() {
+        return;
+    }
+    let _entries = match std::fs::read_dir(_temp_dir) {
+        Ok(_entries) => _entries,
+        Err(_) => return,
+    };
+    for _entry in _entries.flatten() {
+        let _name = _entry.file_name();
+        let _pid = match _name.to_str().and_then(|_name| _name.strip_prefix("fuzz_sandbox_")) {
+            Some(_pid) if _pid.parse::<u32>().is_ok() => _pid.to_string(),
+            _ => continue,
+        };
+        if !std::path::Path::new("/proc").join(_pid).exists() {
+            let _ = std::fs::remove_dir_all(_entry.path());
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn _block_network() -> bool {
+    #[repr(C)]
+    struct _SockFilter {
+        code: u16,
+        jt: u8,
+        jf: u8,
+        k: u32,
+    }
+    #[repr(C)]
+    struct _SockFprog {
+        len: u16,
+        filter: *const _SockFilter,
+    }
+    extern "C" {
+        fn prctl(_option: i32, _arg2: u64, _arg3: u64, _arg4: u64, _arg5: u64) -> i32;
+        fn syscall(_number: i64, ...) -> i64;
+    }
+    #[cfg(target_arch = "x86_64")]
+    const _AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "x86_64")]
+    const _NR_SOCKET: u32 = 41;
+    #[cfg(target_arch = "x86_64")]
+    const _NR_SECCOMP: i64 = 317;
+    #[cfg(target_arch = "aarch64")]
+    const _AUDIT_ARCH: u32 = 0xc000_00b7;
+    #[cfg(target_arch = "aarch64")]
+    const _NR_SOCKET: u32 = 198;
+    #[cfg(target_arch = "aarch64")]
+    const _NR_SECCOMP: i64 = 277;
+    const _PR_SET_NO_NEW_PRIVS: i32 = 38;
+    const _SECCOMP_SET_MODE_FILTER: i64 = 1;
+    const _SECCOMP_FILTER_FLAG_TSYNC: i64 = 1;
+    const _AF_UNIX: u32 = 1;
+    const _SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+    const _SECCOMP_RET_EACCES: u32 = 0x0005_0000 | 13;
+    let _filter = [
+        //seccomp_data.arch
+        _SockFilter { code: 0x20, jt: 0, jf: 0, k: 4 },
+        _SockFilter { code: 0x15, jt: 0, jf: 5, k: _AUDIT_ARCH },
+        //seccomp_data.nr
+        _SockFilter { code: 0x20, jt: 0, jf: 0, k: 0 },
+        _SockFilter { code: 0x15, jt: 0, jf: 3, k: _NR_SOCKET },
+        //socket的domain参数，seccomp_data.args[0]的低32位
+        _SockFilter { code: 0x20, jt: 0, jf: 0, k: 16 },
+        _SockFilter { code: 0x15, jt: 1, jf: 0, k: _AF_UNIX },
+        _SockFilter { code: 0x06, jt: 0, jf: 0, k: _SECCOMP_RET_EACCES },
+        _SockFilter { code: 0x06, jt: 0, jf: 0, k: _SECCOMP_RET_ALLOW },
+    ];
+    let _program = _SockFprog { len: _filter.len() as u16, filter: _filter.as_ptr() };
+    unsafe {
+        prctl(_PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == 0
+            && syscall(
+                _NR_SECCOMP,
+                _SECCOMP_SET_MODE_FILTER,
+                _SECCOMP_FILTER_FLAG_TSYNC,
+                &_program as *const _SockFprog,
+            ) == 0
+    }
+}
+
+#[cfg(not(all(target_os = "linux", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn _block_network() -> bool {
+    false
+}
+"#
+}
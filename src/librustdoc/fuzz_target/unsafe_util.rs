@@ -0,0 +1,107 @@
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_hir::{BodyOwnerKind, Unsafety};
+use rustc_middle::mir::{AggregateKind, Rvalue, StatementKind, TerminatorKind};
+use rustc_middle::ty::{self, TyCtxt};
+use std::collections::{BTreeSet, HashSet};
+
+//根据MIR中的调用关系，计算每个函数可以传递到达的unsafe块和unsafe函数。
+//unsafe的位置用"unsafe block in <函数> at <span>"或"unsafe fn <函数> at <span>"表示
+#[derive(Clone, Debug, Default)]
+pub struct UnsafeReachability {
+    //函数 -> 从它出发可以到达的unsafe位置
+    pub reachable_sites: FxHashMap<DefId, BTreeSet<String>>,
+    //crate中所有的unsafe位置
+    pub all_sites: BTreeSet<String>,
+}
+
+//只在设置了FUZZ_TARGET_UNSAFE_REPORT时计算，需要为crate中的每个函数生成optimized MIR。
+//rustdoc的session中函数体都被替换成了loop {}，所以在core::fuzz_target_body_analyses的普通rustc session中计算
+pub fn unsafe_report_requested() -> bool {
+    std::env::var_os("FUZZ_TARGET_UNSAFE_REPORT").is_some()
+}
+
+pub fn unsafe_reachability(tcx: TyCtxt<'_>) -> UnsafeReachability {
+    let mut direct_sites: FxHashMap<DefId, BTreeSet<String>> = FxHashMap::default();
+    let mut callees: FxHashMap<DefId, HashSet<DefId>> = FxHashMap::default();
+    let source_map = tcx.sess.source_map();
+    for local_def_id in tcx.body_owners() {
+        let hir_id = tcx.hir().as_local_hir_id(local_def_id);
+        let body_owner_kind = tcx.hir().body_owner_kind(hir_id);
+        if !body_owner_kind.is_fn_or_closure() {
+            continue;
+        }
+        let def_id = local_def_id.to_def_id();
+        //闭包中的unsafe块记在所在的函数上，同一个块不会被记录两次
+        let function_name = tcx.def_path_str(tcx.closure_base_def_id(def_id));
+        let mut sites = BTreeSet::new();
+        if let BodyOwnerKind::Fn = body_owner_kind {
+            if let Unsafety::Unsafe = tcx.fn_sig(def_id).unsafety() {
+                let span = source_map.span_to_string(tcx.def_span(def_id));
+                sites.insert(format!("unsafe fn {} at {}", function_name, span));
+            }
+        }
+        for (block_hir_id, _) in tcx.unsafety_check_result(local_def_id).unsafe_blocks.iter() {
+            let span = source_map.span_to_string(tcx.hir().span(*block_hir_id));
+            sites.insert(format!("unsafe block in {} at {}", function_name, span));
+        }
+        callees.insert(def_id, _local_callees(tcx, def_id));
+        direct_sites.insert(def_id, sites);
+    }
+
+    let all_sites = direct_sites.values().flatten().cloned().collect();
+    let mut reachable_sites = FxHashMap::default();
+    for def_id in direct_sites.keys() {
+        let mut sites = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut worklist = vec![*def_id];
+        while let Some(current) = worklist.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            if let Some(current_sites) = direct_sites.get(&current) {
+                sites.extend(current_sites.iter().cloned());
+            }
+            if let Some(current_callees) = callees.get(&current) {
+                worklist.extend(current_callees.iter().cloned());
+            }
+        }
+        reachable_sites.insert(*def_id, sites);
+    }
+    UnsafeReachability { reachable_sites, all_sites }
+}
+
+//MIR中调用的本crate中的函数，以及创建的闭包。trait方法能解析到具体实现的话使用具体的实现，
+//泛型参数决定实现的调用无法解析，不计入
+fn _local_callees(tcx: TyCtxt<'_>, def_id: DefId) -> HashSet<DefId> {
+    let mut res = HashSet::new();
+    let body = tcx.optimized_mir(def_id);
+    let param_env = tcx.param_env(def_id);
+    for basic_block in body.basic_blocks() {
+        for statement in &basic_block.statements {
+            if let StatementKind::Assign(assign) = &statement.kind {
+                if let Rvalue::Aggregate(aggregate_kind, _) = &assign.1 {
+                    if let AggregateKind::Closure(closure_def_id, _) = **aggregate_kind {
+                        res.insert(closure_def_id);
+                    }
+                }
+            }
+        }
+        let func = match &basic_block.terminator().kind {
+            TerminatorKind::Call { func, .. } => func,
+            _ => continue,
+        };
+        let (callee_def_id, substs) = match func.ty(body, tcx).kind {
+            ty::FnDef(callee_def_id, substs) => (callee_def_id, substs),
+            _ => continue,
+        };
+        let callee_def_id = match ty::Instance::resolve(tcx, param_env, callee_def_id, substs) {
+            Ok(Some(instance)) => instance.def_id(),
+            _ => callee_def_id,
+        };
+        if callee_def_id.is_local() {
+            res.insert(callee_def_id);
+        }
+    }
+    res
+}
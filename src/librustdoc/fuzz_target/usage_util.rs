@@ -0,0 +1,109 @@
+use crate::fuzz_target::api_function::ApiFunction;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+//从反向依赖crate的源码中统计到的api使用情况，key是api的全限定名
+#[derive(Clone, Debug, Default)]
+pub struct ApiUsage {
+    //每个api在多少个文件中被调用
+    pub call_counts: HashMap<String, usize>,
+    //同一个文件中a的调用出现在b之前的次数(只统计相邻的两次调用)
+    pub call_orders: HashMap<(String, String), usize>,
+}
+
+impl ApiUsage {
+    pub fn is_empty(&self) -> bool {
+        self.call_counts.is_empty()
+    }
+
+    //序列中的api被使用的次数，加上相邻调用顺序出现的次数
+    pub fn _score(&self, function_names: &Vec<&String>) -> usize {
+        let mut score = 0;
+        for function_name in function_names {
+            score = score + self.call_counts.get(*function_name).cloned().unwrap_or(0);
+        }
+        for pair in function_names.windows(2) {
+            let order = (pair[0].clone(), pair[1].clone());
+            score = score + self.call_orders.get(&order).cloned().unwrap_or(0);
+        }
+        score
+    }
+}
+
+//在反向依赖的源码中查找api的调用：
+//type::method或者mod::function的形式，以及名字在crate中唯一的方法的.method(形式。
+//只做文本匹配，use引入之后直接调用的函数会被漏掉
+pub fn mine_api_usage(corpus_dir: &Path, api_functions: &Vec<ApiFunction>) -> ApiUsage {
+    let mut last_name_count: HashMap<&str, usize> = HashMap::new();
+    for api_function in api_functions {
+        let last_name = api_function.full_name.rsplit("::").next().unwrap();
+        *last_name_count.entry(last_name).or_insert(0) += 1;
+    }
+    let mut patterns = Vec::new();
+    for api_function in api_functions {
+        let segments: Vec<&str> = api_function.full_name.split("::").collect();
+        //helper函数等不属于crate的api
+        if segments.len() < 2 {
+            continue;
+        }
+        let last_name = segments[segments.len() - 1];
+        let mut function_patterns =
+            vec![format!("{}::{}(", segments[segments.len() - 2], last_name)];
+        if last_name_count[last_name] == 1 && api_function._impl_type_full_path.is_some() {
+            function_patterns.push(format!(".{}(", last_name));
+        }
+        patterns.push((api_function.full_name.clone(), function_patterns));
+    }
+
+    let mut source_files = Vec::new();
+    collect_rust_files(corpus_dir, &mut source_files);
+    let mut api_usage = ApiUsage::default();
+    for source_file in &source_files {
+        let content = match fs::read_to_string(source_file) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let mut calls = Vec::new();
+        for (full_name, function_patterns) in &patterns {
+            for pattern in function_patterns {
+                for (position, _) in content.match_indices(pattern.as_str()) {
+                    calls.push((position, full_name));
+                }
+            }
+        }
+        calls.sort();
+        let used_functions: HashSet<&String> = calls.iter().map(|(_, name)| *name).collect();
+        for full_name in used_functions {
+            *api_usage.call_counts.entry(full_name.clone()).or_insert(0) += 1;
+        }
+        for pair in calls.windows(2) {
+            if pair[0].1 != pair[1].1 {
+                let order = (pair[0].1.clone(), pair[1].1.clone());
+                *api_usage.call_orders.entry(order).or_insert(0) += 1;
+            }
+        }
+    }
+    println!(
+        "api usage: {} source files, {} apis used, {} call orders",
+        source_files.len(),
+        api_usage.call_counts.len(),
+        api_usage.call_orders.len()
+    );
+    api_usage
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().map_or(false, |extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+}
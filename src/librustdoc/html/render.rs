@@ -63,7 +63,12 @@ use crate::clean::{self, AttributesExt, Deprecation, GetDefId, SelfTy, TypeKind}
 use crate::config::{OutputFormat, RenderOptions};
 use crate::docfs::{DocFS, ErrorStorage, PathError};
 use crate::doctree;
-use crate::fuzz_target::{api_function, api_graph, api_util, file_util, impl_util};
+use crate::fuzz_target::bug_report_util::MetadataError;
+use crate::fuzz_target::generator::SequenceGenerator;
+use crate::fuzz_target::{
+    api_function, api_graph, api_util, bug_report_util, file_util, fuzzable_type, impl_util,
+    relation_util,
+};
 use crate::html::escape::Escape;
 use crate::html::format::fmt_impl_for_trait_page;
 use crate::html::format::Function;
@@ -274,6 +279,12 @@ pub struct RenderInfo {
     pub deref_mut_trait_did: Option<DefId>,
     pub owned_box_did: Option<DefId>,
     pub output_format: Option<OutputFormat>,
+    pub unsafe_reachability: crate::fuzz_target::unsafe_util::UnsafeReachability,
+    pub ffi_classification: crate::fuzz_target::ffi_util::FfiClassification,
+    pub mir_param_relations: crate::fuzz_target::relation_util::MirParamRelations,
+    pub generic_instances: crate::fuzz_target::instance_util::GenericInstances,
+    pub normalized_signatures: crate::fuzz_target::projection_util::NormalizedSignatures,
+    pub producer_functions: crate::fuzz_target::producer_util::ProducerFunctions,
 }
 
 // Helper structs for rendering items/sidebars and carrying along contextual
@@ -583,9 +594,10 @@ pub fn run(
 pub fn fuzz_target_run_clean_krate(
     raw_krate: &clean::Crate,
     raw_options: &RenderOptions,
-    renderinfo: RenderInfo,
+    mut renderinfo: RenderInfo,
     diag: &rustc_errors::Handler,
     edition: Edition,
+    generator: &SequenceGenerator,
 ) -> Result<(), Error> {
     let mut krate = raw_krate.clone();
     let options = raw_options.clone();
@@ -652,10 +664,22 @@ pub fn fuzz_target_run_clean_krate(
     let dst = output;
     scx.ensure_dir(&dst)?;
     krate = sources::render(&dst, &mut scx, krate)?;
+    let unsafe_reachability = std::mem::take(&mut renderinfo.unsafe_reachability);
+    let ffi_classification = std::mem::take(&mut renderinfo.ffi_classification);
+    let mir_param_relations = std::mem::take(&mut renderinfo.mir_param_relations);
+    let generic_instances = std::mem::take(&mut renderinfo.generic_instances);
+    let normalized_signatures = std::mem::take(&mut renderinfo.normalized_signatures);
+    let producer_functions = std::mem::take(&mut renderinfo.producer_functions);
     let (new_crate, index, cache) =
         Cache::from_krate(renderinfo, document_private, &extern_html_root_urls, &dst, krate);
 
     let mut api_dependency_graph = api_graph::ApiGraph::new(&new_crate.name);
+    api_dependency_graph.unsafe_reachability = unsafe_reachability;
+    api_dependency_graph.ffi_classification = ffi_classification;
+    api_dependency_graph.mir_param_relations = mir_param_relations;
+    api_dependency_graph.generic_instances = generic_instances;
+    api_dependency_graph.normalized_signatures = normalized_signatures;
+    api_dependency_graph.producer_functions = producer_functions;
     //从cache中提出def_id与full_name的对应关系，存入full_name_map来进行调用
     //同时提取impl块中的内容，存入api_dependency_graph
     let mut full_name_map = impl_util::FullNameMap::new();
@@ -685,19 +709,11 @@ pub fn fuzz_target_run_clean_krate(
 
     //将bare function添加到graph中去
     let ret = cx.analyse_clean_krate(&krate, &mut api_dependency_graph);
-    //根据mod可见性和预包含类型过滤function
-    api_dependency_graph.filter_functions();
-    //寻找所有依赖，并且构建序列
-    api_dependency_graph.find_all_dependencies();
+    //过滤function，寻找所有依赖，并且构建序列
+    generator.generate(&mut api_dependency_graph);
     //api_dependency_graph._print_pretty_dependencies();
 
-    let random_strategy = false;
-    if !random_strategy {
-        api_dependency_graph.default_generate_sequences();
-    } else {
-        use crate::fuzz_target::api_graph::GraphTraverseAlgorithm::_RandomWalk;
-        api_dependency_graph.generate_all_possoble_sequences(_RandomWalk);
-    }
+    let random_strategy = generator.is_random_strategy();
     //api_dependency_graph._print_generated_libfuzzer_file();
     //api_dependency_graph._print_pretty_functions(false);
     //api_dependency_graph._print_generated_test_functions();
@@ -707,6 +723,36 @@ pub fn fuzz_target_run_clean_krate(
     //print_message::_print_generated_afl_file(&api_dependency_graph);
     print_message::_print_generic_functions(&api_dependency_graph);
     println!("total functions in crate : {:?}", api_dependency_graph.api_functions.len());
+    if api_util::helper_cfg().is_some() {
+        let cfg_gated_number = api_dependency_graph
+            .api_functions
+            .iter()
+            .filter(|api_function| {
+                api_dependency_graph._is_cfg_gated_function(&api_function.full_name)
+            })
+            .count();
+        println!("cfg gated helper functions : {:?}", cfg_gated_number);
+    }
+    if !api_util::selected_features().is_empty() {
+        let feature_gated_number = api_dependency_graph
+            .api_functions
+            .iter()
+            .filter(|api_function| {
+                !api_dependency_graph._feature_gates_of(&api_function.full_name).is_empty()
+            })
+            .count();
+        println!("feature gated functions : {:?}", feature_gated_number);
+    }
+    if !api_dependency_graph.api_ffi_callees.is_empty() {
+        let ffi_number = api_dependency_graph
+            .api_functions
+            .iter()
+            .filter(|api_function| {
+                api_dependency_graph._ffi_callees_of(&api_function.full_name).is_some()
+            })
+            .count();
+        println!("functions forwarding to foreign functions : {:?}", ffi_number);
+    }
     //println!("total test sequences : {:?}", api_dependency_graph.api_sequences.len());
     //use crate::html::afl_util;
     //afl_util::_AflHelpers::_print_all();
@@ -715,6 +761,11 @@ pub fn fuzz_target_run_clean_krate(
         let file_helper = file_util::FileHelper::new(&api_dependency_graph, random_strategy);
         //println!("file_helper:{:?}", file_helper);
         file_helper.write_files();
+        file_helper.write_pinning_files(&cx.shared.src_root);
+        file_helper.write_crate_path(&cx.shared.src_root);
+        file_helper.write_coverage_diff(&api_dependency_graph, &cx.shared.src_root);
+        file_helper.write_env_recipe();
+        file_helper.write_fuzzer_projects(&cx.shared.src_root);
 
         if file_util::can_generate_libfuzzer_target(&api_dependency_graph._crate_name) {
             file_helper.write_libfuzzer_files();
@@ -1578,9 +1629,24 @@ impl Context {
         {
             let mut work = vec![(self.clone(), item)];
             while let Some((mut cx, item)) = work.pop() {
-                cx.another_analyse_item(item, &mut api_dependency_graph, |cx, item| {
-                    work.push((cx.clone(), item))
-                })?
+                //generator本身在某个item上panic时跳过这个item，并生成bug report
+                let mut item_path = cx.current.clone();
+                item_path.extend(item.name.clone());
+                let item_path = item_path.join("::");
+                let span = item.source.clone();
+                let checkpoint = api_dependency_graph._checkpoint();
+                let analyse_result = bug_report_util::_guard(&item_path, Some(&span), || {
+                    cx.another_analyse_item(item, &mut api_dependency_graph, |cx, item| {
+                        work.push((cx.clone(), item))
+                    })
+                });
+                match analyse_result {
+                    Ok(analyse_result) => analyse_result?,
+                    Err(metadata_error) => {
+                        api_dependency_graph._rollback(checkpoint);
+                        api_dependency_graph.add_metadata_error(metadata_error);
+                    }
+                }
             }
         }
         Ok(())
@@ -1759,10 +1825,15 @@ impl Context {
         F: FnMut(&mut Context, clean::Item),
     {
         if item.is_mod() {
-            let name = item.name.as_ref().unwrap().to_string();
-            if name.is_empty() {
-                panic!("empty name : {:?}", self.current);
-            }
+            let name = match &item.name {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => {
+                    api_dependency_graph.add_metadata_error(MetadataError::MissingName {
+                        parent: self.current.join("::"),
+                    });
+                    return Ok(());
+                }
+            };
 
             let prev = self.dst.clone();
             self.dst.push(&name);
@@ -1770,10 +1841,20 @@ impl Context {
 
             let mod_name = self.current.join("::");
             api_dependency_graph.add_mod_visibility(&mod_name, &item.visibility);
+            if api_util::_is_helper_cfg_gated(&item.attrs) {
+                api_dependency_graph.add_cfg_gated_mod(&mod_name);
+            }
+            api_dependency_graph.add_feature_gates(&mod_name, &item.attrs);
 
             let m = match item.inner {
                 clean::StrippedItem(box clean::ModuleItem(m)) | clean::ModuleItem(m) => m,
-                _ => unreachable!(),
+                _ => {
+                    api_dependency_graph
+                        .add_metadata_error(MetadataError::MissingModule { mod_path: mod_name });
+                    self.dst = prev;
+                    self.current.pop();
+                    return Ok(());
+                }
             };
 
             for item in m.items {
@@ -1793,8 +1874,36 @@ impl Context {
                         let decl = func.decl.clone();
                         let clean::FnDecl { inputs, output, .. } = decl;
                         let generics = func.generics.clone();
-                        let inputs = api_util::_extract_input_types(&inputs);
-                        let output = api_util::_extract_output_type(&output);
+                        let mut param_relations = api_util::_extract_param_relations(&inputs);
+                        relation_util::_merge_param_relations(
+                            &mut param_relations,
+                            api_dependency_graph.mir_param_relations.relations.get(&item.def_id),
+                        );
+                        let mut inputs = api_util::_extract_input_types(&inputs);
+                        let is_async = api_util::_is_async(&func.header);
+                        let mut output = if is_async {
+                            api_util::_extract_async_output_type(&func.decl)
+                        } else {
+                            api_util::_extract_output_type(&output)
+                        };
+                        api_dependency_graph.normalize_projections(
+                            item.def_id,
+                            &mut inputs,
+                            &mut output,
+                        );
+                        if api_util::_is_helper_cfg_gated(&item.attrs) {
+                            api_dependency_graph.add_cfg_gated_function(&full_name);
+                        }
+                        api_dependency_graph.add_feature_gates(&full_name, &item.attrs);
+                        let annotations = api_util::_rulf_annotations(&item.attrs);
+                        api_dependency_graph.add_function_annotations(&full_name, &annotations);
+                        api_dependency_graph.add_unsafe_sites(&full_name, item.def_id);
+                        api_dependency_graph.add_ffi_callees(&full_name, item.def_id);
+                        api_dependency_graph.add_api_lines(&full_name, &item.source);
+                        api_dependency_graph.add_generic_instances(&full_name, item.def_id);
+                        if is_async {
+                            api_dependency_graph.add_async_function(&full_name);
+                        }
                         let api_unsafety =
                             api_function::ApiUnsafety::_get_unsafety_from_fnheader(&func.header);
                         let api_fun = api_function::ApiFunction {
@@ -1803,7 +1912,10 @@ impl Context {
                             inputs,
                             output,
                             _trait_full_path: None,
+                            _impl_type_full_path: None,
                             _unsafe_tag: api_unsafety,
+                            _helper: None,
+                            _param_relations: param_relations,
                         };
 
                         //let output_type = api_fun.output.clone().unwrap();
@@ -1817,6 +1929,56 @@ impl Context {
                     }
                     _ => {}
                 }
+            } else if item_type == ItemType::Struct || item_type == ItemType::Enum {
+                let annotations = api_util::_rulf_annotations(&item.attrs);
+                if !annotations.is_empty() {
+                    let type_name = full_path(self, &item);
+                    api_dependency_graph.add_type_annotations(
+                        item.def_id,
+                        &type_name,
+                        &annotations,
+                    );
+                }
+                //enum的变体可以在crate外直接构造，#[non_exhaustive]的enum也一样
+                if let clean::EnumItem(ref e) = item.inner {
+                    if item.visibility == clean::Visibility::Public {
+                        let enum_path =
+                            match api_dependency_graph.full_name_map._get_full_name(&item.def_id) {
+                                Some(enum_path) => enum_path.clone(),
+                                None => full_path(self, &item),
+                            };
+                        if let Some(variants) = api_util::_enum_variants(&enum_path, e) {
+                            api_dependency_graph.add_enum_variants(
+                                item.def_id,
+                                &enum_path,
+                                variants,
+                            );
+                        }
+                    }
+                }
+                //#[non_exhaustive]的类型在crate外不能用字面量构造，只能通过构造函数或者Default得到
+                if item.is_non_exhaustive() {
+                    let type_name =
+                        match api_dependency_graph.full_name_map._get_full_name(&item.def_id) {
+                            Some(type_name) => type_name.clone(),
+                            None => full_path(self, &item),
+                        };
+                    api_dependency_graph.add_non_exhaustive_type(&type_name);
+                } else if let clean::StructItem(ref s) = item.inner {
+                    //unit struct可以直接通过路径构造，当作zero sized的fuzzable参数
+                    if let doctree::Unit = s.struct_type {
+                        if item.visibility == clean::Visibility::Public {
+                            api_dependency_graph.full_name_map.push_zero_sized_type(&item.def_id);
+                        }
+                    } else if item.visibility == clean::Visibility::Public {
+                        //字段全是pub的primitive类型时，可以由输入直接构造字面量
+                        if let Some(fields) = fuzzable_type::literal_struct_fields(s) {
+                            api_dependency_graph
+                                .full_name_map
+                                .push_literal_struct(&item.def_id, fields);
+                        }
+                    }
+                }
             }
         }
         Ok(())
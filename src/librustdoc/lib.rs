@@ -80,19 +80,32 @@ pub mod html {
 
 pub mod fuzz_target {
     crate mod afl_util;
-    crate mod api_function;
-    crate mod api_graph;
-    crate mod api_sequence;
+    pub mod api_function;
+    pub mod api_graph;
+    pub mod api_sequence;
     crate mod api_util;
+    crate mod bug_report_util;
     crate mod call_type;
+    crate mod config_util;
+    crate mod env_util;
+    crate mod ffi_util;
     crate mod file_util;
     crate mod fuzzable_type;
     crate mod generic_function;
+    pub mod generator;
     crate mod impl_util;
+    crate mod instance_util;
     crate mod mod_visibility;
+    crate mod mutator_util;
     crate mod prelude_type;
     crate mod print_message;
+    crate mod producer_util;
+    crate mod projection_util;
+    crate mod relation_util;
     crate mod replay_util;
+    crate mod sandbox_util;
+    crate mod unsafe_util;
+    crate mod usage_util;
 }
 
 mod markdown;
@@ -126,7 +139,33 @@ pub fn main() {
 }
 
 pub fn fuzz_target_generator_main() {
+    let res = match get_args() {
+        Some(args) => fuzz_target_generator_run(args.into_iter().skip(1).collect(), |_| {}),
+        None => 1,
+    };
+    process::exit(res);
+}
+
+/// Runs the fuzz target generator on the rustdoc arguments `args`, given without the program
+/// name, and returns the exit code. `configure` adjusts the generator after the options of
+/// `fuzz-target-gen.toml` and the `FUZZ_TARGET_*` environment variables have been applied.
+pub fn fuzz_target_generator_run(
+    mut args: Vec<String>,
+    configure: impl FnOnce(&mut fuzz_target::generator::SequenceGenerator),
+) -> i32 {
     let start = Instant::now();
+    //fuzz-target-gen.toml中的选项要在读取环境变量的SequenceGenerator::default之前生效
+    let config = match fuzz_target::config_util::GeneratorConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return rustc_driver::EXIT_FAILURE;
+        }
+    };
+    config.apply_to_env();
+    let mut generator = fuzz_target::generator::SequenceGenerator::default();
+    generator.function_filter = config.function_filter();
+    configure(&mut generator);
 
     let thread_stack_size: usize = if cfg!(target_os = "haiku") {
         16_000_000 // 16MB on Haiku
@@ -134,10 +173,12 @@ pub fn fuzz_target_generator_main() {
         32_000_000 // 32MB on other platforms
     };
     rustc_driver::set_sigpipe_handler();
-    env_logger::init_from_env("FUZZ-TARGET-GENERATOR");
+    //库的调用者可能在同一个进程中运行多次，logger只能初始化一次
+    let _ = env_logger::try_init_from_env("FUZZ-TARGET-GENERATOR");
+    args.insert(0, "fuzz-target-generator".to_string());
     let res = std::thread::Builder::new()
         .stack_size(thread_stack_size)
-        .spawn(move || get_args().map(|args| fuzz_target_generator_main_args(&args)).unwrap_or(1))
+        .spawn(move || fuzz_target_generator_main_args(&args, generator))
         .unwrap()
         .join()
         .unwrap_or(rustc_driver::EXIT_FAILURE);
@@ -146,8 +187,7 @@ pub fn fuzz_target_generator_main() {
         "Fuzz Target Generator exits successfully. Total time cost: {:?} ms",
         start.elapsed().as_millis()
     );
-
-    process::exit(res);
+    res
 }
 
 fn get_args() -> Option<Vec<String>> {
@@ -489,7 +529,10 @@ fn main_args(args: &[String]) -> i32 {
     )
 }
 
-fn fuzz_target_generator_main_args(args: &[String]) -> i32 {
+fn fuzz_target_generator_main_args(
+    args: &[String],
+    generator: fuzz_target::generator::SequenceGenerator,
+) -> i32 {
     let mut options = getopts::Options::new();
     for option in opts() {
         (option.apply)(&mut options);
@@ -506,7 +549,7 @@ fn fuzz_target_generator_main_args(args: &[String]) -> i32 {
     };
     rustc_interface::interface::setup_callbacks_and_run_in_default_thread_pool_with_globals(
         options.edition,
-        move || fuzz_target_generator_main_options(options),
+        move || fuzz_target_generator_main_options(options, generator),
     )
 }
 
@@ -591,7 +634,10 @@ fn main_options(options: config::Options) -> i32 {
     }
 }
 
-fn fuzz_target_generator_main_options(options: config::Options) -> i32 {
+fn fuzz_target_generator_main_options(
+    options: config::Options,
+    generator: fuzz_target::generator::SequenceGenerator,
+) -> i32 {
     let diag = core::new_handler(options.error_format, None, &options.debugging_options);
 
     match (options.should_test, options.markdown_input()) {
@@ -649,6 +695,7 @@ fn fuzz_target_generator_main_options(options: config::Options) -> i32 {
             renderinfo,
             &diag,
             edition,
+            &generator,
         ) {
             Ok(_) => rustc_driver::EXIT_SUCCESS,
             Err(s) => {
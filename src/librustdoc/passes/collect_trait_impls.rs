@@ -123,6 +123,20 @@ pub fn collect_trait_impls(krate: Crate, cx: &DocContext<'_>) -> Crate {
         }
     }
 
+    // Inherent impls expanded from derive and proc macros are often nested in
+    // `const _: () = { .. }` blocks, which the module visitor never enters, so pick up
+    // the ones it missed from the type-level index. Only the fuzz target generator needs
+    // them as APIs; regular documentation keeps its current output.
+    if cx.generating_fuzz_targets {
+        for impls in cx.tcx.crate_inherent_impls(LOCAL_CRATE).inherent_impls.values() {
+            for &impl_did in impls.iter() {
+                if !cleaner.items.contains(&impl_did) {
+                    inline::build_impl(cx, impl_did, None, &mut new_items);
+                }
+            }
+        }
+    }
+
     if let Some(ref mut it) = krate.module {
         if let ModuleItem(Module { ref mut items, .. }) = it.inner {
             items.extend(synth.impls);
@@ -293,8 +293,8 @@ impl<'a, 'tcx> RustdocVisitor<'a, 'tcx> {
 
         let use_attrs = tcx.hir().attrs(id);
         // Don't inline `doc(hidden)` imports so they can be stripped at a later stage.
-        let is_no_inline = use_attrs.lists(sym::doc).has_word(sym::no_inline)
-            || use_attrs.lists(sym::doc).has_word(sym::hidden);
+        let is_hidden_import = use_attrs.lists(sym::doc).has_word(sym::hidden);
+        let is_no_inline = use_attrs.lists(sym::doc).has_word(sym::no_inline) || is_hidden_import;
 
         // For cross-crate impl inlining we need to know whether items are
         // reachable in documentation -- a previously nonreachable item can be
@@ -334,8 +334,13 @@ impl<'a, 'tcx> RustdocVisitor<'a, 'tcx> {
         let is_private = !self.cx.renderinfo.borrow().access_levels.is_public(res_did);
         let is_hidden = inherits_doc_hidden(self.cx, res_hir_id);
 
-        // Only inline if requested or if the item would otherwise be stripped.
-        if (!please_inline && !is_private && !is_hidden) || is_no_inline {
+        // Only inline if requested or if the item would otherwise be stripped. A target that
+        // lives in a private module is inlined even under `doc(no_inline)`: this import is its
+        // only public path, and the fuzz target generator needs the item under that path.
+        if (!please_inline && !is_private && !is_hidden)
+            || is_hidden_import
+            || (is_no_inline && !is_private)
+        {
             return false;
         }
 
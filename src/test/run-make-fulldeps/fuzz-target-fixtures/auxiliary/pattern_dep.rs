@@ -0,0 +1,44 @@
+//! Dependency of the `dependency_producers` fixture. Its types are only built through
+//! its own constructors, like `regex::Regex`.
+
+mod imp {
+    pub struct Pattern {
+        source: String,
+        anchored: bool,
+    }
+
+    #[derive(Debug)]
+    pub struct PatternError {
+        pub position: usize,
+    }
+
+    impl Pattern {
+        pub fn new(source: &str) -> Result<Pattern, PatternError> {
+            match source.find(')') {
+                Some(position) => Err(PatternError { position }),
+                None => Ok(Pattern { source: source.to_string(), anchored: false }),
+            }
+        }
+
+        pub fn anchored(source: &str) -> Option<Pattern> {
+            if source.is_empty() {
+                return None;
+            }
+            Some(Pattern { source: source.to_string(), anchored: true })
+        }
+
+        pub fn as_str(&self) -> &str {
+            &self.source
+        }
+
+        pub fn is_match(&self, haystack: &str) -> bool {
+            if self.anchored {
+                haystack.starts_with(&self.source)
+            } else {
+                haystack.contains(&self.source)
+            }
+        }
+    }
+}
+
+pub use self::imp::{Pattern, PatternError};
@@ -0,0 +1,36 @@
+//! `decode` returns `Self::Output` and `major_of` takes `<Header as Decode>::Output`.
+//! Both only connect to the rest of the API once the associated type is normalized
+//! to `Version`.
+pub trait Decode {
+    type Output;
+
+    fn decode(&self) -> Self::Output;
+}
+
+pub struct Header {
+    raw: u16,
+}
+
+impl Header {
+    pub fn new(raw: u16) -> Header {
+        Header { raw }
+    }
+}
+
+pub struct Version(u8);
+
+impl Decode for Header {
+    type Output = Version;
+
+    fn decode(&self) -> Self::Output {
+        Version((self.raw >> 8) as u8)
+    }
+}
+
+pub fn major(version: &Version) -> u8 {
+    version.0 / 10
+}
+
+pub fn major_of(version: <Header as Decode>::Output) -> u8 {
+    major(&version)
+}
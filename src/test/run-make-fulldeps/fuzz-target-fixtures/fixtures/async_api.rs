@@ -0,0 +1,31 @@
+//! `send` is an async function. Targets call it with `.await` inside an async block
+//! that they run with `futures::executor::block_on`, and pass its output to `retry`.
+pub struct Request {
+    id: u32,
+}
+
+impl Request {
+    pub fn new(id: u32) -> Request {
+        Request { id }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+pub async fn send(request: Request) -> u32 {
+    request.id
+}
+
+pub async fn retry(id: u32) -> Request {
+    Request::new(id + 1)
+}
+
+pub async fn send_all(requests: Vec<Request>) -> u32 {
+    let mut total = 0;
+    for request in requests {
+        total += send(request).await;
+    }
+    total
+}
@@ -0,0 +1,46 @@
+//! `parse` and the `Lexer` methods return values that borrow from their arguments.
+//! The targets must keep the borrowed input or lexer alive, and must not move or
+//! mutate the lexer while a token borrowed from it is still used.
+pub struct Token<'a> {
+    text: &'a str,
+}
+
+impl<'a> Token<'a> {
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+}
+
+pub fn parse<'a>(input: &'a str) -> Token<'a> {
+    Token { text: input.split_whitespace().next().unwrap_or("") }
+}
+
+pub struct Lexer {
+    input: String,
+    position: usize,
+}
+
+impl Lexer {
+    pub fn new(input: &str) -> Lexer {
+        Lexer { input: input.to_string(), position: 0 }
+    }
+
+    pub fn next_token(&mut self) -> Token<'_> {
+        let rest = &self.input[self.position..];
+        let end = rest.find(' ').map(|end| end + 1).unwrap_or(rest.len());
+        self.position += end;
+        Token { text: rest[..end].trim_end() }
+    }
+
+    pub fn rest(&self) -> &str {
+        &self.input[self.position..]
+    }
+
+    pub fn into_input(self) -> String {
+        self.input
+    }
+}
@@ -0,0 +1,48 @@
+//! Every builder method takes and returns the builder, so all of them should be
+//! reachable from `ConfigBuilder::new`, and `Config` only from `build`.
+pub struct Config {
+    name: String,
+    retries: u8,
+    verbose: bool,
+}
+
+impl Config {
+    pub fn retries(&self) -> u8 {
+        self.retries
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} retries={} verbose={}", self.name, self.retries, self.verbose)
+    }
+}
+
+pub struct ConfigBuilder {
+    name: String,
+    retries: u8,
+    verbose: bool,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> ConfigBuilder {
+        ConfigBuilder { name: String::new(), retries: 3, verbose: false }
+    }
+
+    pub fn name(mut self, name: &str) -> ConfigBuilder {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn retries(mut self, retries: u8) -> ConfigBuilder {
+        self.retries = retries;
+        self
+    }
+
+    pub fn verbose(mut self, verbose: bool) -> ConfigBuilder {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        Config { name: self.name, retries: self.retries, verbose: self.verbose }
+    }
+}
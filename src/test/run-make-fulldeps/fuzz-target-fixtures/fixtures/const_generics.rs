@@ -0,0 +1,16 @@
+//! `chunk` has a const parameter, so it is only called as instances such as
+//! `chunk::<4>`. `checksum` is only reachable from the instance for 4.
+#![feature(const_generics)]
+#![allow(incomplete_features)]
+
+pub fn chunk<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut res = [0; N];
+    for (slot, byte) in res.iter_mut().zip(data) {
+        *slot = *byte;
+    }
+    res
+}
+
+pub fn checksum(block: &[u8; 4]) -> u8 {
+    block.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+}
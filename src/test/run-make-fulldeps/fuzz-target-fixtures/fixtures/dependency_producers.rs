@@ -0,0 +1,13 @@
+//! APIs taking a type from a dependency crate. The suite runs this fixture with
+//! `FUZZ_TARGET_PRODUCER_CRATES=pattern_dep`, so `Pattern` is built by the constructors of
+//! `pattern_dep`.
+
+use pattern_dep::Pattern;
+
+pub fn count_matches(pattern: &Pattern, lines: &str) -> usize {
+    lines.lines().filter(|line| pattern.is_match(line)).count()
+}
+
+pub fn describe(pattern: Pattern) -> String {
+    format!("/{}/", pattern.as_str())
+}
@@ -0,0 +1,64 @@
+//! `Buffer` has no public constructor. Its methods are only reachable through the
+//! `Deref` and `DerefMut` impls of its wrappers: `Pooled` derefs to `Buffer`, and
+//! `Guard` derefs to `Pooled`, so a `Guard` reaches `Buffer` through a chain.
+use std::ops::{Deref, DerefMut};
+
+pub struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Buffer {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.data.push(byte);
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+pub struct Pooled {
+    buffer: Buffer,
+}
+
+impl Pooled {
+    pub fn with_capacity(capacity: u16) -> Pooled {
+        Pooled { buffer: Buffer { data: Vec::with_capacity(capacity as usize) } }
+    }
+}
+
+impl Deref for Pooled {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+impl DerefMut for Pooled {
+    fn deref_mut(&mut self) -> &mut Buffer {
+        &mut self.buffer
+    }
+}
+
+pub struct Guard {
+    pooled: Pooled,
+}
+
+impl Guard {
+    pub fn lock(pooled: Pooled) -> Guard {
+        Guard { pooled }
+    }
+}
+
+impl Deref for Guard {
+    type Target = Pooled;
+
+    fn deref(&self) -> &Pooled {
+        &self.pooled
+    }
+}
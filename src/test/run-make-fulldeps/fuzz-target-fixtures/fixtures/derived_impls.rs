@@ -0,0 +1,80 @@
+//! None of these types has a hand-written public constructor: they are only produced
+//! by derived or macro-expanded impls, the way serde, thiserror and derive_builder
+//! expand. Every consumer below is only reachable if those impls are indexed.
+use std::str::FromStr;
+
+// expands like a derive macro, into an anonymous const the module visitor never enters
+macro_rules! derive_token {
+    ($ty:ident) => {
+        const _: () = {
+            impl $ty {
+                pub fn from_bytes(bytes: &[u8]) -> $ty {
+                    $ty { bytes: bytes.to_vec() }
+                }
+            }
+        };
+    };
+}
+
+macro_rules! derive_from {
+    ($from:ty, $ty:ident) => {
+        impl From<$from> for $ty {
+            fn from(value: $from) -> $ty {
+                $ty { value: value as u64 }
+            }
+        }
+    };
+}
+
+pub struct Token {
+    bytes: Vec<u8>,
+}
+
+derive_token!(Token);
+
+impl Token {
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+pub struct Level {
+    value: u64,
+}
+
+derive_from!(u32, Level);
+
+impl Level {
+    pub fn is_high(&self) -> bool {
+        self.value > 100
+    }
+}
+
+#[derive(Default)]
+pub struct Settings {
+    depth: u8,
+}
+
+impl Settings {
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+}
+
+pub struct Name {
+    inner: String,
+}
+
+impl FromStr for Name {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Name, ()> {
+        if s.is_empty() { Err(()) } else { Ok(Name { inner: s.to_string() }) }
+    }
+}
+
+impl Name {
+    pub fn as_str(&self) -> &str {
+        &self.inner
+    }
+}
@@ -0,0 +1,41 @@
+//! No function returns `Input` or `Command`, so they can only be built from their variants.
+//! All variants of `Input` have fuzzable fields and are chosen by an input byte.
+//! `Command::Run` carries a `Job`, which comes from `Job::new`.
+
+pub enum Input {
+    Text(String),
+    Number(i64),
+    Pair { left: u8, right: u8 },
+    Empty,
+}
+
+pub struct Job {
+    id: u32,
+}
+
+impl Job {
+    pub fn new(id: u32) -> Job {
+        Job { id }
+    }
+}
+
+pub enum Command {
+    Run(Job),
+    Stop,
+}
+
+pub fn classify(input: &Input) -> usize {
+    match input {
+        Input::Text(text) => text.len(),
+        Input::Number(number) => *number as usize,
+        Input::Pair { left, right } => (*left as usize) * (*right as usize),
+        Input::Empty => 0,
+    }
+}
+
+pub fn execute(command: Command) -> u32 {
+    match command {
+        Command::Run(job) => job.id,
+        Command::Stop => 0,
+    }
+}
@@ -0,0 +1,42 @@
+//! APIs behind cargo features. The suite enables `fast` only, so `slow_sum` is
+//! documented (through `cfg(doc)`) but must not be called by any target.
+
+pub fn always(x: u8) -> u8 {
+    x.wrapping_mul(3)
+}
+
+#[cfg(feature = "fast")]
+pub fn fast_sum(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+#[cfg(any(feature = "slow", doc))]
+pub fn slow_sum(a: u32, b: u32) -> u32 {
+    (0..b).fold(a, |acc, _| acc.wrapping_add(1))
+}
+
+#[cfg(feature = "fast")]
+pub mod fast {
+    pub fn checksum(data: &[u8]) -> u32 {
+        data.iter().map(|byte| *byte as u32).sum()
+    }
+}
+
+pub struct Counter {
+    count: u64,
+}
+
+impl Counter {
+    pub fn new() -> Counter {
+        Counter { count: 0 }
+    }
+
+    #[cfg(feature = "fast")]
+    pub fn bump(&mut self, step: u8) {
+        self.count += step as u64;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
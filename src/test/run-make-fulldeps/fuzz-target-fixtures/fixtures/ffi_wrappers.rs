@@ -0,0 +1,28 @@
+//! Thin wrappers over libc next to plain Rust APIs. The suite runs this fixture with
+//! `FUZZ_TARGET_FFI_APIS=exclude` and checks that no target calls the wrappers or the
+//! `extern "C"` API.
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    fn toupper(c: i32) -> i32;
+}
+
+pub fn c_abs(x: i32) -> i32 {
+    unsafe { abs(x) }
+}
+
+pub fn c_toupper(c: u8) -> u8 {
+    unsafe { toupper(c as i32) as u8 }
+}
+
+pub extern "C" fn exported_add(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+pub fn rust_abs(x: i32) -> i32 {
+    x.wrapping_abs()
+}
+
+pub fn rust_toupper(c: u8) -> u8 {
+    c.to_ascii_uppercase()
+}
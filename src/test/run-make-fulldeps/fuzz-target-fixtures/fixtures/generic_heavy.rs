@@ -0,0 +1,103 @@
+//! Generic functions are only reachable when the generator can instantiate them.
+//! `describe` is instantiated with the error type of `parse`, `largest` and `widen` with
+//! the primitive types that satisfy their bounds, and the methods of `Stack` with the
+//! types that satisfy the bounds of its impl block. Only `Frame` satisfies all bounds
+//! of `seal`, so it is instantiated with `Frame`, which `Frame::from_bytes` produces.
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct ParseError {
+    code: u8,
+}
+
+impl ParseError {
+    pub fn new(code: u8) -> ParseError {
+        ParseError { code }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "parse error {}", self.code)
+    }
+}
+
+impl Error for ParseError {}
+
+pub struct Parsed(u32);
+
+pub fn parse(input: &str) -> Result<Parsed, ParseError> {
+    input.trim().parse().map(Parsed).map_err(|_| ParseError::new(input.len() as u8))
+}
+
+pub fn value(parsed: &Parsed) -> u32 {
+    parsed.0
+}
+
+pub fn describe<E: Error>(error: E) -> String {
+    error.to_string()
+}
+
+pub fn largest<T: PartialOrd + Copy>(items: &[T]) -> Option<T> {
+    let mut largest = *items.first()?;
+    for &item in items {
+        if item > largest {
+            largest = item;
+        }
+    }
+    Some(largest)
+}
+
+pub fn widen<T: Into<u64>>(value: T) -> u64 {
+    value.into()
+}
+
+pub struct Stack<T> {
+    items: Vec<T>,
+}
+
+impl<T: Ord> Stack<T> {
+    pub fn new() -> Self {
+        Stack { items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push(item);
+        self.items.sort();
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+pub trait Checksum {
+    fn checksum(&self) -> u32;
+}
+
+#[derive(Clone, Default)]
+pub struct Frame {
+    bytes: Vec<u8>,
+}
+
+impl Frame {
+    pub fn from_bytes(bytes: &[u8]) -> Frame {
+        Frame { bytes: bytes.to_vec() }
+    }
+}
+
+impl Checksum for Frame {
+    fn checksum(&self) -> u32 {
+        self.bytes.iter().fold(0u32, |sum, byte| sum.rotate_left(5) ^ *byte as u32)
+    }
+}
+
+pub fn seal<T: Checksum + Clone + Default>(item: T) -> u32 {
+    let sealed = item.clone();
+    sealed.checksum() ^ T::default().checksum()
+}
@@ -0,0 +1,45 @@
+//! `words` returns `impl Iterator` and `Digits` is an iterator type. `Word` and
+//! `Digit` are only produced as their items, so `Word::len` and `Digit::value` are
+//! only reachable when the targets consume the iterators.
+pub struct Word(String);
+
+impl Word {
+    pub fn len(&self) -> usize {
+        self.0.chars().count()
+    }
+}
+
+pub fn words(text: &str) -> impl Iterator<Item = Word> + '_ {
+    text.split_whitespace().map(|word| Word(word.to_string()))
+}
+
+pub struct Digit(u8);
+
+impl Digit {
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+pub struct Digits {
+    rest: u64,
+}
+
+impl Digits {
+    pub fn new(value: u64) -> Digits {
+        Digits { rest: value }
+    }
+}
+
+impl Iterator for Digits {
+    type Item = Digit;
+
+    fn next(&mut self) -> Option<Digit> {
+        if self.rest == 0 {
+            return None;
+        }
+        let digit = Digit((self.rest % 10) as u8);
+        self.rest /= 10;
+        Some(digit)
+    }
+}
@@ -0,0 +1,46 @@
+//! `Config` and `Point` have no constructors, but all of their fields are `pub` and
+//! primitive, so they can be built as struct literals from the input bytes.
+//! `Sealed` has a private field and no constructor, so `Sealed::inspect` is never covered.
+
+pub struct Config {
+    pub width: u16,
+    pub height: u16,
+    pub strict: bool,
+}
+
+pub struct Point(pub i32, pub i32);
+
+pub struct Sealed {
+    pub id: u32,
+    secret: u8,
+}
+
+impl Config {
+    pub fn area(&self) -> u32 {
+        self.width as u32 * self.height as u32
+    }
+
+    pub fn validate(&mut self) -> bool {
+        if self.strict && self.width == 0 {
+            self.width = 1;
+            return false;
+        }
+        true
+    }
+}
+
+impl Sealed {
+    pub fn inspect(&self) -> u32 {
+        self.id + self.secret as u32
+    }
+}
+
+pub fn distance(a: Point, b: Point) -> i64 {
+    let dx = a.0 as i64 - b.0 as i64;
+    let dy = a.1 as i64 - b.1 as i64;
+    dx * dx + dy * dy
+}
+
+pub fn render(config: &Config, origin: Point) -> String {
+    format!("{}x{}@{},{}", config.width, config.height, origin.0, origin.1)
+}
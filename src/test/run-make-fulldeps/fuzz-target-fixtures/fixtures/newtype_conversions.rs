@@ -0,0 +1,47 @@
+//! A newtype-heavy crate. Values move between the wrappers through `From` and
+//! `TryFrom` impls, so most parameters can only be filled by converting the
+//! result of another API.
+use std::convert::TryFrom;
+
+pub struct RawId(u64);
+
+pub struct UserId(u32);
+
+pub struct Millis(u64);
+
+pub struct Timeout {
+    millis: u64,
+}
+
+#[derive(Debug)]
+pub struct IdOutOfRange;
+
+pub fn parse_id(input: &str) -> RawId {
+    RawId(input.bytes().fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64)))
+}
+
+impl TryFrom<RawId> for UserId {
+    type Error = IdOutOfRange;
+
+    fn try_from(raw: RawId) -> Result<UserId, IdOutOfRange> {
+        u32::try_from(raw.0).map(UserId).map_err(|_| IdOutOfRange)
+    }
+}
+
+pub fn lookup(id: UserId) -> Option<String> {
+    if id.0 % 7 == 0 { None } else { Some(format!("user-{}", id.0)) }
+}
+
+pub fn elapsed(start: u32, end: u32) -> Millis {
+    Millis(end.saturating_sub(start) as u64)
+}
+
+impl From<Millis> for Timeout {
+    fn from(millis: Millis) -> Timeout {
+        Timeout { millis: millis.0 }
+    }
+}
+
+pub fn is_expired(timeout: &Timeout, now: u64) -> bool {
+    now >= timeout.millis
+}
@@ -0,0 +1,80 @@
+//! Most of the API of these types is operator overloading. `Money` is combined with
+//! `+`, `-=` and unary `-`, `Grid` is read with `grid[(x, y)]`, and `Version` is only
+//! compared through its `PartialOrd` impl.
+use std::cmp::Ordering;
+use std::ops::{Add, Index, Neg, SubAssign};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Money {
+    cents: i64,
+}
+
+impl Money {
+    pub fn from_cents(cents: i64) -> Money {
+        Money { cents }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money { cents: self.cents + other.cents }
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.cents -= other.cents;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money { cents: -self.cents }
+    }
+}
+
+pub struct Grid {
+    width: usize,
+    cells: Vec<u8>,
+}
+
+impl Grid {
+    pub fn new(width: usize, cells: &[u8]) -> Grid {
+        Grid { width, cells: cells.to_vec() }
+    }
+}
+
+impl Index<(usize, usize)> for Grid {
+    type Output = u8;
+
+    fn index(&self, pos: (usize, usize)) -> &u8 {
+        &self.cells[pos.1 * self.width + pos.0]
+    }
+}
+
+pub struct Version {
+    major: u16,
+    minor: u16,
+}
+
+impl Version {
+    pub fn new(major: u16, minor: u16) -> Version {
+        Version { major, minor }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Version) -> bool {
+        self.major == other.major && self.minor == other.minor
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some((self.major, self.minor).cmp(&(other.major, other.minor)))
+    }
+}
@@ -0,0 +1,13 @@
+//! `usize` parameters whose relation to a buffer is only visible in the function body.
+//! The MIR use analysis generates them from the buffer's length.
+pub fn byte_at(data: &[u8], at: usize) -> u8 {
+    data[at]
+}
+
+pub unsafe fn byte_at_unchecked(data: &[u8], at: usize) -> u8 {
+    *data.get_unchecked(at)
+}
+
+pub unsafe fn prefix(data: &[u8], count: usize) -> &[u8] {
+    std::slice::from_raw_parts(data.as_ptr(), count)
+}
@@ -0,0 +1,63 @@
+//! Everything is defined in private modules and only reachable through `pub use`.
+//! `parse_all` also shares its prefix with the private `parse` module.
+
+mod parse {
+    pub struct Reader {
+        data: Vec<u8>,
+        offset: usize,
+    }
+
+    impl Reader {
+        pub fn new(data: &[u8]) -> Reader {
+            Reader { data: data.to_vec(), offset: 0 }
+        }
+
+        pub fn next_byte(&mut self) -> Option<u8> {
+            let byte = self.data.get(self.offset).copied();
+            self.offset += 1;
+            byte
+        }
+    }
+
+    pub fn parse_header(data: &[u8]) -> Option<u16> {
+        if data.len() < 2 { None } else { Some(u16::from_be_bytes([data[0], data[1]])) }
+    }
+}
+
+mod codec {
+    pub struct Encoder {
+        shift: u8,
+    }
+
+    impl Encoder {
+        pub fn with_shift(shift: u8) -> Encoder {
+            Encoder { shift }
+        }
+
+        pub fn encode(&self, input: &str) -> Vec<u8> {
+            input.bytes().map(|byte| byte.wrapping_add(self.shift)).collect()
+        }
+    }
+
+    pub mod hex {
+        pub fn encode(input: &[u8]) -> String {
+            input.iter().map(|byte| format!("{:02x}", byte)).collect()
+        }
+    }
+}
+
+pub use parse::{parse_header, Reader};
+
+#[doc(no_inline)]
+pub use codec::Encoder;
+
+pub use codec::hex;
+
+pub fn parse_all(data: &[u8]) -> usize {
+    let mut reader = Reader::new(data);
+    let mut count = 0;
+    while reader.next_byte().is_some() {
+        count += 1;
+    }
+    count
+}
@@ -0,0 +1,50 @@
+//! The API of this crate lives in trait impls. `Frame` is only built through its
+//! `FromStr` impl, and `Encoder::encode_to_vec` is a default method that `LineCodec`
+//! does not override, called with `Frame` in place of the `Item` parameter.
+use std::str::FromStr;
+
+pub trait Encoder<Item> {
+    fn encode(&mut self, item: Item, dst: &mut Vec<u8>);
+
+    fn encode_to_vec(&mut self, item: Item) -> Vec<u8> {
+        let mut dst = Vec::new();
+        self.encode(item, &mut dst);
+        dst
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseFrameError;
+
+pub struct Frame {
+    payload: String,
+}
+
+impl FromStr for Frame {
+    type Err = ParseFrameError;
+
+    fn from_str(s: &str) -> Result<Frame, ParseFrameError> {
+        if s.contains('\n') {
+            return Err(ParseFrameError);
+        }
+        Ok(Frame { payload: s.to_string() })
+    }
+}
+
+pub struct LineCodec {
+    written: usize,
+}
+
+impl LineCodec {
+    pub fn new() -> LineCodec {
+        LineCodec { written: 0 }
+    }
+}
+
+impl Encoder<Frame> for LineCodec {
+    fn encode(&mut self, item: Frame, dst: &mut Vec<u8>) {
+        self.written += item.payload.len() + 1;
+        dst.extend_from_slice(item.payload.as_bytes());
+        dst.push(b'\n');
+    }
+}
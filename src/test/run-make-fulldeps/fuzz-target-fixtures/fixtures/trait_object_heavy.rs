@@ -0,0 +1,38 @@
+//! Trait methods are called through the implementing type. `&dyn Shape` and
+//! `Box<dyn Shape>` parameters take a `Square`, the only implementor. A slice of trait
+//! objects cannot be built, so `total_area` is never called.
+pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub struct Square {
+    side: f64,
+}
+
+impl Square {
+    pub fn new(side: f64) -> Square {
+        Square { side }
+    }
+}
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.side * self.side
+    }
+}
+
+pub fn boxed_square(side: f64) -> Box<dyn Shape> {
+    Box::new(Square::new(side))
+}
+
+pub fn total_area(shapes: &[Box<dyn Shape>]) -> f64 {
+    shapes.iter().map(|shape| shape.area()).sum()
+}
+
+pub fn scaled_area(shape: &dyn Shape, factor: f64) -> f64 {
+    shape.area() * factor
+}
+
+pub fn into_area(shape: Box<dyn Shape>) -> f64 {
+    shape.area()
+}
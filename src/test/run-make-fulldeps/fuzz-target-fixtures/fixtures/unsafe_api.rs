@@ -0,0 +1,30 @@
+//! Unsafe functions and raw pointers are called inside `unsafe` blocks in the targets.
+pub struct RawBuffer {
+    data: Vec<u8>,
+}
+
+impl RawBuffer {
+    pub fn new(size: u8) -> RawBuffer {
+        RawBuffer { data: vec![0; size as usize + 1] }
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data.as_ptr()
+    }
+
+    pub unsafe fn get_unchecked(&self, index: usize) -> u8 {
+        *self.data.get_unchecked(index)
+    }
+}
+
+pub unsafe fn read_byte(ptr: *const u8) -> u8 {
+    *ptr
+}
+
+pub unsafe fn write_byte(ptr: *mut u8, value: u8) {
+    *ptr = value;
+}
+
+pub unsafe fn sum_prefix(data: &[u8], len: usize) -> u32 {
+    data.get_unchecked(..len).iter().map(|byte| *byte as u32).sum()
+}
@@ -0,0 +1,36 @@
+//! Safe APIs whose bodies reach unsafe code, directly, through a private helper or through
+//! a closure. The suite runs this fixture with `FUZZ_TARGET_UNSAFE_REPORT=1` and checks that
+//! the targets reach every unsafe site.
+pub struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Buffer {
+    pub fn new(size: u8) -> Buffer {
+        Buffer { data: vec![0; size as usize + 1] }
+    }
+
+    pub fn first(&self) -> u8 {
+        unsafe { *self.data.get_unchecked(0) }
+    }
+
+    pub fn last(&self) -> u8 {
+        last_byte(&self.data)
+    }
+}
+
+fn last_byte(data: &[u8]) -> u8 {
+    unsafe { *data.get_unchecked(data.len() - 1) }
+}
+
+pub fn checked_read(data: &[u8], index: usize) -> u8 {
+    if index < data.len() { unsafe { *data.as_ptr().add(index) } } else { 0 }
+}
+
+pub fn sum(data: &[u8]) -> u32 {
+    data.iter().map(|byte| unsafe { read(byte) } as u32).sum()
+}
+
+unsafe fn read(byte: *const u8) -> u8 {
+    *byte
+}
@@ -0,0 +1,62 @@
+// Checks the `fuzz_blocking_api` lint for public APIs the fuzz target generator can not call.
+
+#![crate_type = "lib"]
+#![deny(fuzz_blocking_api)]
+
+pub trait Shape {
+    fn area(&self) -> u32;
+}
+
+pub trait Unimplemented {
+    fn run(&self);
+}
+
+pub struct Square(pub u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+pub struct Opaque {
+    value: u32,
+}
+
+pub struct Built {
+    value: u32,
+}
+
+impl Built {
+    pub fn new(value: u32) -> Built {
+        Built { value }
+    }
+}
+
+pub fn total_area(shape: &dyn Shape) -> u32 {
+    shape.area()
+}
+
+pub fn run(task: &dyn Unimplemented) {
+    //~^ ERROR parameter of type `dyn Unimplemented` blocks fuzzing
+    task.run()
+}
+
+pub fn apply(f: fn(u32) -> u32) -> u32 {
+    //~^ ERROR parameter of type `fn(u32) -> u32` blocks fuzzing
+    f(1)
+}
+
+pub fn read(opaque: &Opaque) -> u32 {
+    //~^ ERROR no public function returns `Opaque`
+    opaque.value
+}
+
+#[allow(fuzz_blocking_api)]
+pub fn read_allowed(opaque: &Opaque) -> u32 {
+    opaque.value
+}
+
+pub fn read_built(built: &Built) -> u32 {
+    built.value
+}
@@ -2739,6 +2739,10 @@ impl<'test> TestCx<'test> {
             cmd.env("RUSTDOC", cwd.join(rustdoc));
         }
 
+        if let Some(ref fuzz_target_generator) = self.config.fuzz_target_generator_path {
+            cmd.env("FUZZ_TARGET_GENERATOR", cwd.join(fuzz_target_generator));
+        }
+
         if let Some(ref node) = self.config.nodejs {
             cmd.env("NODE", node);
         }
@@ -1,4 +1,4 @@
 fn main() {
     println!("Fuzz Target Generator for Rust Libraries: v0.1.0");
-    rustdoc::fuzz_target_generator_main();
+    rulf_core::run();
 }
@@ -0,0 +1,86 @@
+//! Library interface of the fuzz target generator.
+//!
+//! The generator needs the type information of the compiler, so it still lives in
+//! `librustdoc` and must be built by the same bootstrap stage as the compiler it analyzes.
+//! This crate is the stable surface over it. Research tools and CI plugins depend on
+//! `rulf-core` instead of reaching into `rustdoc::fuzz_target`, whose types may change
+//! between versions.
+//!
+//! ```ignore
+//! let options = rulf_core::Options {
+//!     api_filter: Some(Box::new(|full_name: &str| !full_name.contains("::raw::"))),
+//!     ..rulf_core::Options::new(vec!["--edition".into(), "2018".into(), "src/lib.rs".into()])
+//! };
+//! let exit_code = rulf_core::run_with(options);
+//! ```
+
+use rustdoc::fuzz_target::api_function::ApiFunction;
+use rustdoc::fuzz_target::api_graph::GraphTraverseAlgorithm;
+use rustdoc::fuzz_target::generator::FUZZ_BACKENDS;
+
+/// A predicate on the full path of an API, such as `mycrate::Parser::parse`. APIs it rejects
+/// are left out of the graph.
+pub type ApiFilter = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// How sequences are searched in the API graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// BFS stopping at end functions, followed by backward search for the APIs BFS could not
+    /// reach. This is the default of the binary.
+    Bfs,
+    /// Weighted random walk, like `FUZZ_TARGET_STRATEGY=random-walk`.
+    RandomWalk,
+}
+
+/// Options of one generator run.
+pub struct Options {
+    /// The rustdoc command line without the program name, for example
+    /// `["--edition", "2018", "--crate-type", "lib", "src/lib.rs"]`.
+    pub args: Vec<String>,
+    /// Overrides the strategy of `fuzz-target-gen.toml` and `FUZZ_TARGET_STRATEGY`.
+    pub strategy: Option<Strategy>,
+    /// Applied in addition to the API filters of `fuzz-target-gen.toml`.
+    pub api_filter: Option<ApiFilter>,
+}
+
+impl Options {
+    /// Options running the generator on `args` with the configured strategy and filters.
+    pub fn new(args: Vec<String>) -> Options {
+        Options { args, strategy: None, api_filter: None }
+    }
+}
+
+/// Runs the generator with the options of `fuzz-target-gen.toml` and the `FUZZ_TARGET_*`
+/// environment variables, reading the rustdoc command line of the current process. Exits the
+/// process when done.
+pub fn run() {
+    rustdoc::fuzz_target_generator_main()
+}
+
+/// Runs the generator with `options` and returns its exit code. `fuzz-target-gen.toml` and
+/// the `FUZZ_TARGET_*` environment variables still apply to everything `options` leaves open.
+pub fn run_with(options: Options) -> i32 {
+    let Options { args, strategy, api_filter } = options;
+    rustdoc::fuzz_target_generator_run(args, move |generator| {
+        match strategy {
+            Some(Strategy::Bfs) => generator.algorithm = None,
+            Some(Strategy::RandomWalk) => {
+                generator.algorithm = Some(GraphTraverseAlgorithm::_WeightedRandomWalk)
+            }
+            None => {}
+        }
+        if let Some(api_filter) = api_filter {
+            let config_filter = generator.function_filter.take();
+            generator.function_filter = Some(Box::new(move |api_function: &ApiFunction| {
+                api_filter(&api_function.full_name)
+                    && config_filter.as_ref().map_or(true, |filter| filter(api_function))
+            }));
+        }
+    })
+}
+
+/// The names of the fuzzing engines targets can be written for, as accepted by
+/// `FUZZ_TARGET_FUZZER`.
+pub fn fuzz_backends() -> Vec<&'static str> {
+    FUZZ_BACKENDS.iter().map(|backend| backend.name()).collect()
+}